@@ -0,0 +1,9 @@
+CREATE TABLE projects (
+    project_id SERIAL PRIMARY KEY,
+    owner_id INTEGER NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    name VARCHAR(128) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX projects_owner_id_idx ON projects(owner_id);
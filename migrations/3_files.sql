@@ -0,0 +1,8 @@
+CREATE TABLE files (
+    project_id INTEGER NOT NULL REFERENCES projects(project_id) ON DELETE CASCADE,
+    path VARCHAR(256) NOT NULL,
+    content_oid OID NOT NULL,
+    size BIGINT NOT NULL,
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (project_id, path)
+);
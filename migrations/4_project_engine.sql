@@ -0,0 +1 @@
+ALTER TABLE projects ADD COLUMN engine VARCHAR(16) NOT NULL DEFAULT 'pdflatex';
@@ -0,0 +1,8 @@
+ALTER TABLE sessions
+    ADD COLUMN public_id SERIAL UNIQUE,
+    ADD COLUMN created_at BIGINT NOT NULL DEFAULT EXTRACT(EPOCH FROM NOW())::BIGINT,
+    ADD COLUMN last_seen BIGINT NOT NULL DEFAULT EXTRACT(EPOCH FROM NOW())::BIGINT,
+    ADD COLUMN ip_address VARCHAR(64),
+    ADD COLUMN user_agent VARCHAR(512);
+
+CREATE INDEX sessions_user_id_idx ON sessions(user_id);
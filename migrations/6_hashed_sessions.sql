@@ -0,0 +1,4 @@
+-- Tokens used to be stored as issued and cannot be hashed after the fact, so every existing session is dropped
+DELETE FROM sessions;
+
+ALTER TABLE sessions ALTER COLUMN session_id TYPE CHAR(64);
@@ -0,0 +1,12 @@
+CREATE TABLE api_tokens (
+    token_id SERIAL PRIMARY KEY,
+    token_hash CHAR(64) NOT NULL UNIQUE,
+    user_id INTEGER NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    name VARCHAR(64) NOT NULL,
+    scopes VARCHAR(64) NOT NULL,
+    created_at BIGINT NOT NULL DEFAULT EXTRACT(EPOCH FROM NOW())::BIGINT,
+    expires BIGINT,
+    last_used BIGINT
+);
+
+CREATE INDEX api_tokens_user_id_idx ON api_tokens(user_id);
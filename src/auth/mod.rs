@@ -0,0 +1,345 @@
+use std::{collections::HashSet, convert::Infallible, net::{IpAddr, SocketAddr}, task::{Context, Poll}};
+
+use anyhow::Result;
+use axum::{
+    http::Request, body::{Body, BoxBody}, response::Response, extract::{ConnectInfo, FromRequestParts}, async_trait
+};
+use axum_extra::extract::{CookieJar, cookie::{Cookie, SameSite}};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use cookie::time::OffsetDateTime;
+use futures::future::BoxFuture;
+use http::{HeaderMap, HeaderValue, Method, StatusCode, request::Parts, header::{AUTHORIZATION, SET_COOKIE, USER_AGENT}};
+use rand::{RngCore, rngs::OsRng};
+use tower::{Layer, Service};
+use tracing::warn;
+
+use crate::{
+    service::{sessions::{SessionService, SessionVerifyError}, tokens::{TokenService, TokenVerifyError}}, constants, csrf, domain::{users::User, sessions::ClientInfo, tokens::TokenScope},
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CookieConfig {
+    pub secure: bool,
+    pub same_site: SameSite,
+    pub domain: Option<String>,
+    pub path: String,
+    pub host_prefix: bool
+}
+
+impl Default for CookieConfig {
+    fn default() -> Self {
+        Self {
+            secure: true,
+            same_site: SameSite::Lax,
+            domain: None,
+            path: String::from("/"),
+            host_prefix: false
+        }
+    }
+}
+
+impl CookieConfig {
+    pub fn session_name(&self) -> String {
+        self.name(constants::SESSION_COOKIE_NAME)
+    }
+
+    pub fn csrf_name(&self) -> String {
+        self.name(constants::CSRF_COOKIE_NAME)
+    }
+
+    fn name(&self, name: &str) -> String {
+        if self.host_prefix {
+            format!("{}{}", constants::HOST_COOKIE_PREFIX, name)
+        } else {
+            name.to_owned()
+        }
+    }
+
+    pub fn session_cookie(&self, id: &str, expires: i64) -> Cookie<'static> {
+        let mut cookie = self.cookie(self.session_name(), id.to_owned());
+        cookie.set_http_only(true);
+        set_expires(&mut cookie, expires);
+        cookie
+    }
+
+    // The client has to read this one to echo it back, so it cannot be HttpOnly
+    pub fn csrf_cookie(&self, id: &str, expires: i64) -> Cookie<'static> {
+        let mut cookie = self.cookie(self.csrf_name(), csrf::token(id));
+        set_expires(&mut cookie, expires);
+        cookie
+    }
+
+    // Browsers only match a removal against a cookie with the same path and domain
+    pub fn removal(&self, name: String) -> Cookie<'static> {
+        self.cookie(name, String::new())
+    }
+
+    fn cookie(&self, name: String, value: String) -> Cookie<'static> {
+        // __Host- cookies are rejected unless they are secure, host-only and scoped to the whole site
+        let builder = Cookie::build(name, value).same_site(self.same_site);
+        if self.host_prefix {
+            return builder.secure(true).path("/").finish();
+        }
+
+        let builder = builder.secure(self.secure).path(self.path.clone());
+        match &self.domain {
+            Some(domain) => builder.domain(domain.clone()).finish(),
+            None => builder.finish()
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct AuthLayer<T: SessionService + Clone, K: TokenService + Clone> {
+    service: T,
+    token_service: K,
+    cookies: CookieConfig,
+    write_scope: Option<TokenScope>
+}
+
+impl<T: SessionService + Clone, K: TokenService + Clone> AuthLayer<T, K> {
+    pub fn new(session_service: T, token_service: K, cookies: CookieConfig) -> Self {
+        Self { service: session_service, token_service, cookies, write_scope: Some(TokenScope::Write) }
+    }
+
+    // Scope an API token needs for requests other than GET, HEAD and OPTIONS
+    pub fn with_write_scope(self, scope: TokenScope) -> Self {
+        Self { write_scope: Some(scope), ..self }
+    }
+
+    // For routes that only make sense in the browser, like managing the tokens themselves
+    pub fn without_tokens(self) -> Self {
+        Self { write_scope: None, ..self }
+    }
+}
+
+impl<S, T: SessionService + Clone, K: TokenService + Clone> Layer<S> for AuthLayer<T, K> {
+    type Service = AuthMiddleware<S, T, K>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AuthMiddleware {
+            inner,
+            session_service: self.service.clone(),
+            token_service: self.token_service.clone(),
+            cookies: self.cookies.clone(),
+            write_scope: self.write_scope
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct AuthMiddleware<S, T: SessionService, K: TokenService> {
+    inner: S,
+    session_service: T,
+    token_service: K,
+    cookies: CookieConfig,
+    write_scope: Option<TokenScope>
+}
+
+impl<S, T, K> Service<Request<Body>> for AuthMiddleware<S, T, K>
+where
+    S: Service<Request<Body>, Response = Response> + Send + Clone + 'static,
+    S::Future: Send + 'static,
+    T: SessionService + Send + Sync + Clone + 'static,
+    K: TokenService + Send + Sync + Clone + 'static
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        if let Some(secret) = bearer_token(request.headers()) {
+            return self.call_with_token(secret, request);
+        }
+
+        let session_cookie = match CookieJar::from_headers(request.headers()).get(&self.cookies.session_name()) {
+            Some(cookie) => cookie.clone(),
+            None => return Box::pin(async move { Ok(status_response(StatusCode::UNAUTHORIZED)) })
+        };
+        
+        let session_service = self.session_service.clone();
+        let cookies = self.cookies.clone();
+        let mut inner = self.inner.clone();
+        let mut request = request;
+
+        Box::pin(async move {
+            let session = match session_service.verify(session_cookie.value()).await {
+                Ok(session) => session,
+                Err(SessionVerifyError::Missing) => return Ok(status_response(StatusCode::UNAUTHORIZED)),
+                Err(SessionVerifyError::Unknown) => return Ok(status_response(StatusCode::INTERNAL_SERVER_ERROR))
+            };
+            
+            request.extensions_mut().insert(session.user);
+            let mut response = inner.call(request).await?;
+
+            // The browser would otherwise drop the cookie at its original expiry
+            if session.extended {
+                let id = session_cookie.value();
+                for cookie in [cookies.session_cookie(id, session.expires), cookies.csrf_cookie(id, session.expires)] {
+                    if let Ok(value) = HeaderValue::from_str(&cookie.to_string()) {
+                        response.headers_mut().append(SET_COOKIE, value);
+                    }
+                }
+            }
+            Ok(response)
+        })
+    }
+}
+
+impl<S, T, K> AuthMiddleware<S, T, K>
+where
+    S: Service<Request<Body>, Response = Response> + Send + Clone + 'static,
+    S::Future: Send + 'static,
+    T: SessionService + Send + Sync + Clone + 'static,
+    K: TokenService + Send + Sync + Clone + 'static
+{
+    fn call_with_token(&mut self, secret: String, mut request: Request<Body>) -> BoxFuture<'static, Result<Response, S::Error>> {
+        let scope = match self.write_scope {
+            Some(_) if matches!(*request.method(), Method::GET | Method::HEAD | Method::OPTIONS) => TokenScope::Read,
+            Some(write_scope) => write_scope,
+            None => return Box::pin(async move { Ok(status_response(StatusCode::FORBIDDEN)) })
+        };
+
+        let token_service = self.token_service.clone();
+        let mut inner = self.inner.clone();
+
+        Box::pin(async move {
+            let owner = match token_service.verify(&secret).await {
+                Ok(owner) => owner,
+                Err(TokenVerifyError::Missing) => return Ok(status_response(StatusCode::UNAUTHORIZED)),
+                Err(TokenVerifyError::Unknown) => return Ok(status_response(StatusCode::INTERNAL_SERVER_ERROR))
+            };
+
+            if !owner.token.scopes.grants(scope) {
+                warn!(token_id = owner.token.id, scope = scope.as_str(), "API token is missing the required scope");
+                return Ok(status_response(StatusCode::FORBIDDEN));
+            }
+
+            // Lets handlers check scopes that depend on the request body
+            request.extensions_mut().insert(owner.token.scopes);
+            request.extensions_mut().insert(owner.user);
+            inner.call(request).await
+        })
+    }
+}
+
+// The scheme name is case-insensitive (RFC 7235)
+fn bearer_token(headers: &HeaderMap) -> Option<String> {
+    let (scheme, token) = headers.get(AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .split_once(' ')?;
+    match scheme.eq_ignore_ascii_case(constants::BEARER_SCHEME) {
+        true => Some(token.trim().to_owned()),
+        false => None
+    }
+}
+
+fn status_response(status: StatusCode) -> Response {
+    Response::builder()
+        .status(status)
+        .body(BoxBody::default())
+        .unwrap()
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for User
+where
+    S: Send + Sync,
+{
+    type Rejection = (http::StatusCode, &'static str);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<User>()
+            .cloned()
+            .ok_or((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Can't extract User. Is `AuthLayer` enabled?",
+            ))
+    }
+}
+
+// An expiry out of range leaves the cookie without one, so it still ends with the browser session
+fn set_expires(cookie: &mut Cookie<'static>, expires: i64) {
+    match OffsetDateTime::from_unix_timestamp(expires) {
+        Ok(expires) => cookie.set_expires(expires),
+        Err(err) => warn!(%err, expires, "Cookie expiry out of range")
+    }
+}
+
+// Only proxies in this set may name the client in X-Forwarded-For, any other peer could claim to be anyone
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TrustedProxies {
+    proxies: HashSet<IpAddr>
+}
+
+impl TrustedProxies {
+    pub fn new(proxies: impl IntoIterator<Item = IpAddr>) -> Self {
+        Self { proxies: proxies.into_iter().collect() }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.proxies.is_empty()
+    }
+
+    // Every proxy appends the address it received the request from, so the client is the last address that is not a trusted proxy
+    fn client_ip(&self, peer: Option<IpAddr>, headers: &HeaderMap) -> Option<IpAddr> {
+        let peer = peer?;
+        if !self.proxies.contains(&peer) {
+            return Some(peer);
+        }
+
+        let forwarded: Vec<&str> = headers.get_all(&*constants::FORWARDED_FOR_HEADER)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .collect();
+        let mut client = peer;
+        for address in forwarded.into_iter().rev() {
+            match address.trim().parse() {
+                Ok(ip) if self.proxies.contains(&ip) => client = ip,
+                Ok(ip) => return Some(ip),
+                Err(_) => break
+            }
+        }
+        Some(client)
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for ClientInfo
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let peer = parts.extensions.get::<ConnectInfo<SocketAddr>>().map(|ConnectInfo(addr)| addr.ip());
+        let ip = match parts.extensions.get::<TrustedProxies>() {
+            Some(proxies) => proxies.client_ip(peer, &parts.headers),
+            None => peer
+        };
+        let ip_address = ip.map(|ip| ip.to_string().chars().take(constants::MAX_IP_ADDRESS_LENGTH).collect());
+        let user_agent = parts.headers
+            .get(USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(|agent| agent.chars().take(constants::MAX_USER_AGENT_LENGTH).collect());
+
+        Ok(ClientInfo { ip_address, user_agent })
+    }
+}
+
+pub fn generate_token() -> String {
+    let mut bytes = [0u8; constants::TOKEN_BYTES];
+    OsRng.fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,210 @@
+use axum::{Extension, Router, routing::get};
+use tower::ServiceExt;
+
+use crate::{domain::{sessions::{Session, SessionInfo, VerifiedSession}, tokens::{ApiToken, CreatedApiToken, TokenOwner, TokenRequest, TokenScopes}, users::Credentials}, service::{sessions::{LoginError, LogoutError, SessionListError, SessionRevokeError}, tokens::{TokenCreateError, TokenListError, TokenRevokeError}}};
+
+use super::*;
+
+// The layer clones its services, which mocks cannot do, so these only answer what the middleware asks and fail otherwise
+#[derive(Clone)]
+struct StubSessionService;
+
+#[async_trait]
+impl SessionService for StubSessionService {
+    async fn login(&self, _: Credentials, _: ClientInfo) -> Result<Session, LoginError> {
+        Err(LoginError::Unknown)
+    }
+
+    async fn verify(&self, _: &str) -> Result<VerifiedSession, SessionVerifyError> {
+        Err(SessionVerifyError::Missing)
+    }
+
+    async fn logout(&self, _: &str) -> Result<(), LogoutError> {
+        Err(LogoutError::Unknown)
+    }
+
+    async fn logout_all(&self, _: i32) -> Result<u64, LogoutError> {
+        Err(LogoutError::Unknown)
+    }
+
+    async fn list(&self, _: i32, _: &str) -> Result<Vec<SessionInfo>, SessionListError> {
+        Err(SessionListError::Unknown)
+    }
+
+    async fn revoke(&self, _: i32, _: i32) -> Result<(), SessionRevokeError> {
+        Err(SessionRevokeError::Unknown)
+    }
+}
+
+#[derive(Clone)]
+struct StubTokenService(Vec<TokenScope>);
+
+#[async_trait]
+impl TokenService for StubTokenService {
+    async fn create(&self, _: i32, _: TokenRequest) -> Result<CreatedApiToken, TokenCreateError> {
+        Err(TokenCreateError::Unknown)
+    }
+
+    async fn list(&self, _: i32) -> Result<Vec<ApiToken>, TokenListError> {
+        Err(TokenListError::Unknown)
+    }
+
+    async fn revoke(&self, _: i32, _: i32) -> Result<(), TokenRevokeError> {
+        Err(TokenRevokeError::Unknown)
+    }
+
+    async fn verify(&self, secret: &str) -> Result<TokenOwner, TokenVerifyError> {
+        if secret != mock_secret() {
+            return Err(TokenVerifyError::Missing);
+        }
+        Ok(TokenOwner {
+            user: User { id: 1, email: String::from("email"), password_hash: String::from("password_hash") },
+            token: ApiToken {
+                id: 2,
+                name: String::from("ci"),
+                scopes: TokenScopes(self.0.clone()),
+                created_at: 0,
+                expires: None,
+                last_used: None
+            }
+        })
+    }
+}
+
+fn mock_secret() -> String {
+    String::from("agartex_secret")
+}
+
+// Stands in for a router whose writes need the given scope, like /compile or /projects
+fn mock_router(scopes: &[TokenScope], write_scope: TokenScope) -> Router {
+    let auth = AuthLayer::new(StubSessionService, StubTokenService(scopes.to_vec()), CookieConfig::default())
+        .with_write_scope(write_scope);
+    Router::new()
+        .route("/jobs/:id", get(|user: User| async move { user.id.to_string() }).post(|| async { StatusCode::ACCEPTED }))
+        .route_layer(auth)
+}
+
+async fn mock_request(router: Router, method: Method, authorization: &str) -> StatusCode {
+    let request = Request::builder()
+        .method(method)
+        .uri("/jobs/1")
+        .header(AUTHORIZATION, authorization)
+        .body(Body::empty())
+        .unwrap();
+    router.oneshot(request).await.unwrap().status()
+}
+
+fn mock_proxies() -> TrustedProxies {
+    TrustedProxies::new(["10.0.0.1".parse().unwrap(), "10.0.0.2".parse().unwrap()])
+}
+
+fn mock_headers(forwarded: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(&*constants::FORWARDED_FOR_HEADER, HeaderValue::from_str(forwarded).unwrap());
+    headers
+}
+
+#[test]
+fn client_ip_ignores_header_from_untrusted_peer() {
+    let peer = "203.0.113.7".parse().unwrap();
+
+    assert_eq!(Some(peer), mock_proxies().client_ip(Some(peer), &mock_headers("198.51.100.1")));
+}
+
+#[test]
+fn client_ip_from_trusted_proxy() {
+    let peer = "10.0.0.1".parse().unwrap();
+
+    assert_eq!(Some("198.51.100.1".parse().unwrap()), mock_proxies().client_ip(Some(peer), &mock_headers("198.51.100.1")));
+}
+
+#[test]
+fn client_ip_skips_trusted_proxies_only() {
+    let peer = "10.0.0.1".parse().unwrap();
+    let headers = mock_headers("192.0.2.66, 198.51.100.1, 10.0.0.2");
+
+    assert_eq!(Some("198.51.100.1".parse().unwrap()), mock_proxies().client_ip(Some(peer), &headers));
+}
+
+#[test]
+fn client_ip_without_header() {
+    let peer = "10.0.0.1".parse().unwrap();
+
+    assert_eq!(Some(peer), mock_proxies().client_ip(Some(peer), &HeaderMap::new()));
+}
+
+#[test]
+fn client_ip_without_peer() {
+    assert_eq!(None, mock_proxies().client_ip(None, &mock_headers("198.51.100.1")));
+}
+
+#[test]
+fn session_cookie_secure_by_default() {
+    let cookie = CookieConfig::default().session_cookie("id", 1680000000);
+
+    assert_eq!(Some(true), cookie.secure());
+    assert_eq!(Some(1680000000), cookie.expires_datetime().map(OffsetDateTime::unix_timestamp));
+}
+
+#[test]
+fn session_cookie_expiry_out_of_range() {
+    let cookie = CookieConfig::default().session_cookie("id", i64::MAX);
+
+    assert_eq!(None, cookie.expires_datetime());
+}
+
+#[tokio::test]
+async fn token_compile_scope_reads_jobs() {
+    let bearer = format!("Bearer {}", mock_secret());
+
+    assert_eq!(StatusCode::ACCEPTED, mock_request(mock_router(&[TokenScope::Compile], TokenScope::Compile), Method::POST, &bearer).await);
+    assert_eq!(StatusCode::OK, mock_request(mock_router(&[TokenScope::Compile], TokenScope::Compile), Method::GET, &bearer).await);
+}
+
+#[tokio::test]
+async fn token_write_scope_implies_compile_and_read() {
+    let bearer = format!("Bearer {}", mock_secret());
+
+    assert_eq!(StatusCode::OK, mock_request(mock_router(&[TokenScope::Write], TokenScope::Write), Method::GET, &bearer).await);
+    assert_eq!(StatusCode::ACCEPTED, mock_request(mock_router(&[TokenScope::Write], TokenScope::Compile), Method::POST, &bearer).await);
+}
+
+#[tokio::test]
+async fn token_missing_scope() {
+    let bearer = format!("Bearer {}", mock_secret());
+
+    assert_eq!(StatusCode::FORBIDDEN, mock_request(mock_router(&[TokenScope::Read], TokenScope::Compile), Method::POST, &bearer).await);
+    assert_eq!(StatusCode::FORBIDDEN, mock_request(mock_router(&[TokenScope::Compile], TokenScope::Write), Method::POST, &bearer).await);
+}
+
+#[tokio::test]
+async fn token_scheme_case_insensitive() {
+    let bearer = format!("bEaReR {}", mock_secret());
+
+    assert_eq!(StatusCode::OK, mock_request(mock_router(&[TokenScope::Read], TokenScope::Write), Method::GET, &bearer).await);
+}
+
+#[tokio::test]
+async fn token_other_scheme() {
+    let basic = format!("Basic {}", mock_secret());
+
+    assert_eq!(StatusCode::UNAUTHORIZED, mock_request(mock_router(&[TokenScope::Write], TokenScope::Write), Method::GET, &basic).await);
+}
+
+#[tokio::test]
+async fn token_scopes_reach_handler() {
+    let auth = AuthLayer::new(StubSessionService, StubTokenService(vec![TokenScope::Compile]), CookieConfig::default());
+    let router = Router::new()
+        .route("/jobs/:id", get(|Extension(scopes): Extension<TokenScopes>| async move { scopes.as_string() }))
+        .route_layer(auth);
+    let request = Request::builder()
+        .uri("/jobs/1")
+        .header(AUTHORIZATION, format!("Bearer {}", mock_secret()))
+        .body(Body::empty())
+        .unwrap();
+
+    let response = router.oneshot(request).await.unwrap();
+
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    assert_eq!(&b"compile"[..], &body[..]);
+}
@@ -1,17 +1,94 @@
-use std::{net::SocketAddr, str::FromStr, path::Path};
+use std::{net::SocketAddr, str::FromStr, path::{Path, PathBuf}};
 
+use http::HeaderName;
 use lazy_static::lazy_static;
 use regex::Regex;
 
 pub const DB_URL: &str  = "postgres://localhost:5432/agartex-db";
 pub const HASH_COST: u32 = 12;
 pub const SESSION_COOKIE_NAME: &str = "RSESSID";
+pub const CSRF_COOKIE_NAME: &str = "CSRF-TOKEN";
+pub const CSRF_TOKEN_CONTEXT: &[u8] = b"csrf:";
+pub const HOST_COOKIE_PREFIX: &str = "__Host-";
+pub const COOKIE_SECURE_ENV_VAR: &str = "COOKIE_SECURE";
+pub const COOKIE_SAME_SITE_ENV_VAR: &str = "COOKIE_SAME_SITE";
+pub const COOKIE_DOMAIN_ENV_VAR: &str = "COOKIE_DOMAIN";
+pub const COOKIE_PATH_ENV_VAR: &str = "COOKIE_PATH";
+pub const COOKIE_HOST_PREFIX_ENV_VAR: &str = "COOKIE_HOST_PREFIX";
 pub const CLIENT_URL_ENV_VAR: &str = "CLIENT_URL";
-pub const SESSION_LENGTH_SECONDS: i64 = 30 * 24 * 60 * 60; // 3 months
+pub const TRUSTED_PROXIES_ENV_VAR: &str = "TRUSTED_PROXIES";
+pub const SESSION_LENGTH_SECONDS: i64 = 30 * 24 * 60 * 60; // 30 days
+pub const SESSION_LENGTH_ENV_VAR: &str = "SESSION_LENGTH_SECONDS";
+pub const SESSION_IDLE_SECONDS: i64 = 7 * 24 * 60 * 60;
+pub const SESSION_IDLE_ENV_VAR: &str = "SESSION_IDLE_SECONDS";
+pub const SESSION_REFRESH_SECONDS: i64 = 5 * 60;
+pub const TOKEN_BYTES: usize = 32;
+pub const API_TOKEN_PREFIX: &str = "agartex_";
+pub const BEARER_SCHEME: &str = "Bearer";
+pub const API_TOKEN_REFRESH_SECONDS: i64 = 5 * 60;
+pub const SESSION_PURGE_INTERVAL_SECONDS: u64 = 60 * 60;
+pub const SESSION_PURGE_INTERVAL_ENV_VAR: &str = "SESSION_PURGE_INTERVAL_SECONDS";
+pub const MAX_IP_ADDRESS_LENGTH: usize = 64;
+pub const MAX_USER_AGENT_LENGTH: usize = 512;
 pub const PASSWORD_SPECIAL_CHARS: &str = "!@#$%^&*";
 pub const LATEXMK_PATH: &str = "latexmk";
+pub const MAKE4HT_PATH: &str = "make4ht";
+pub const ENV_PATH: &str = "env";
+pub const BWRAP_PATH: &str = "bwrap";
+pub const DEFAULT_ENTRYPOINT: &str = "main.tex";
+pub const MAX_UPLOAD_BYTES: usize = 16 * 1024 * 1024;
+pub const MAX_UNPACKED_BYTES: u64 = 64 * 1024 * 1024;
+pub const MAX_UPLOAD_ENTRIES: usize = 256;
+pub const FILE_STORAGE_ENV_VAR: &str = "FILE_STORAGE";
+pub const FILES_DIR_ENV_VAR: &str = "FILES_DIR";
+// Length of the files.path column
+pub const MAX_FILE_PATH_LENGTH: usize = 256;
+pub const DEFAULT_FILES_DIR: &str = "files";
+pub const COMPILE_WORKERS_ENV_VAR: &str = "COMPILE_WORKERS";
+pub const DEFAULT_COMPILE_WORKERS: usize = 2;
+pub const MAX_QUEUED_JOBS: usize = 64;
+pub const MAX_ACTIVE_JOBS_PER_USER: usize = 4;
+pub const MAX_FINISHED_JOBS: usize = 1024;
+pub const JOB_EVENTS_CAPACITY: usize = 256;
+pub const MAX_CACHE_BYTES: u64 = 1024 * 1024 * 1024;
+pub const JOB_RETENTION_SECONDS: u64 = 60 * 60;
+pub const JANITOR_INTERVAL_SECONDS: u64 = 10 * 60;
+pub const EXECUTION_TIMEOUT_SECONDS: u64 = 60;
+pub const EXECUTION_CPU_SECONDS: u64 = 45;
+pub const EXECUTION_ADDRESS_SPACE_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+pub const EXECUTION_FILE_SIZE_BYTES: u64 = 128 * 1024 * 1024;
+pub const EXECUTION_MEMORY_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+pub const EXECUTION_PROCESSES: u64 = 256;
+pub const EXECUTION_USER_PROCESSES: u64 = 4096;
+pub const EXECUTION_OUTPUT_BYTES: usize = 64 * 1024;
+pub const CGROUP_REMOVE_ATTEMPTS: usize = 50;
+pub const EXECUTION_CGROUP_ENV_VAR: &str = "EXECUTION_CGROUP";
+pub const DEFAULT_EXECUTION_PATH: &str = "/usr/local/bin:/usr/bin:/bin";
+pub const EXECUTOR_ENV_VAR: &str = "EXECUTOR";
+pub const SANDBOX_READ_ONLY_PATHS: [&str; 8] = [
+    "/usr",
+    "/bin",
+    "/lib",
+    "/lib64",
+    "/etc/texmf",
+    "/etc/fonts",
+    "/etc/ld.so.cache",
+    "/var/lib/texmf"
+];
+pub const EXECUTION_ENV: [(&str, &str); 3] = [
+    ("openin_any", "p"),
+    ("openout_any", "p"),
+    ("shell_escape", "f")
+];
 lazy_static! {
     pub static ref COMPILE_DIR: &'static Path = Path::new("/tmp/agar_service/");
+    pub static ref PROJECTS_DIR: PathBuf = COMPILE_DIR.join("projects");
+    pub static ref CACHE_DIR: PathBuf = COMPILE_DIR.join("cache");
+    pub static ref JOBS_DIR: PathBuf = COMPILE_DIR.join("jobs");
+    pub static ref RUN_DIR: PathBuf = COMPILE_DIR.join("run");
     pub static ref SERVER_URL: SocketAddr = SocketAddr::from_str("0.0.0.0:3000").unwrap();
+    pub static ref CSRF_HEADER: HeaderName = HeaderName::from_static("x-csrf-token");
+    pub static ref FORWARDED_FOR_HEADER: HeaderName = HeaderName::from_static("x-forwarded-for");
+    pub static ref DIAGNOSTICS_COUNT_HEADER: HeaderName = HeaderName::from_static("x-diagnostics-count");
     pub static ref PASSWORD_REGEX: Regex = Regex::new(format!("^[A-Za-z0-9{}]*$", PASSWORD_SPECIAL_CHARS).as_str()).unwrap();
 }
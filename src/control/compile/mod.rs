@@ -1,40 +1,390 @@
-use std::fmt::Debug;
+use std::{fmt::Debug, path::Path as FsPath};
 
-use axum::{Extension, body::StreamBody, response::{IntoResponse, AppendHeaders}};
-use http::header::{CONTENT_TYPE, CONTENT_DISPOSITION};
+use axum::{Extension, Json, body::StreamBody, response::{IntoResponse, AppendHeaders, Response, sse::{Event, KeepAlive, Sse}}, extract::{Path, Query}};
+use futures::{Stream, StreamExt, future, stream};
+use http::header::{CONTENT_TYPE, CONTENT_DISPOSITION, CONTENT_SECURITY_POLICY, X_CONTENT_TYPE_OPTIONS};
 use hyper::StatusCode;
+use serde::Deserialize;
+use tokio::sync::broadcast::{self, error::RecvError};
 use tokio_util::io::ReaderStream;
-use tracing::{info, error};
+use tracing::{info, error, warn};
 
-use crate::service::compilation::CompilationService;
+use self::upload::CompileUpload;
 
-#[tracing::instrument]
-pub async fn post_compile<T>(Extension(service): Extension<T>, raw_text: String) -> Result<impl IntoResponse, impl IntoResponse>
-where 
-    T: CompilationService + Debug,
-    <T as CompilationService>::CompileOptions: From<String>,
-    <T as CompilationService>::CompilationError: Into<String>
-{
+use crate::{service::{compilation::{CompileInput, CompileOutput, diagnostics, synctex::SyncTex, queue::{JobService, JobSubmitError, JobResult}, janitor}, files::{FileService, FileAccessError}, projects::{ProjectService, ProjectAccessError}}, domain::{users::User, tokens::{TokenScope, TokenScopes}, compile::{CompileFailure, CompileOptions, Diagnostic, JobEvent, JobId, JobOptions, JobReport, JobStatus, OutputFormat, PdfBox, SourceLocation, SubmittedJob}, files::is_relative_path}, validation::ValidatedJson, constants};
+
+#[derive(Debug, Deserialize)]
+pub struct DiagnosticsQuery {
+    pub entrypoint: Option<String>
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ForwardSearchQuery {
+    pub file: String,
+    pub line: u32,
+    pub column: Option<u32>,
+    pub entrypoint: Option<String>
+}
+
+#[derive(Debug, Deserialize)]
+pub struct InverseSearchQuery {
+    pub page: u32,
+    pub x: f64,
+    pub y: f64,
+    pub entrypoint: Option<String>
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn post_compile<Q: JobService + Debug>(Extension(queue): Extension<Q>, CompileUpload(input, options): CompileUpload) -> Result<impl IntoResponse, Response> {
     info!("Received compilation attempt");
-    let path = match service.compile(raw_text.into()).await {
-        Ok(path) => path,
+    let id = queue.submit(None, options, input).map_err(submit_rejection)?;
+    let output = await_job(&queue, None, id).await?;
+
+    // The open file stays readable after its directory is removed
+    let response = stream_pdf(&output).await;
+    if let Some(job_dir) = &output.job_dir {
+        janitor::remove_job_dir(job_dir);
+    }
+    response
+}
+
+#[tracing::instrument(skip(queue, project_service, file_service, user, scopes, options), fields(user_id = user.id))]
+pub async fn post_project_pdf<Q, P, F>(
+    Extension(queue): Extension<Q>,
+    Extension(project_service): Extension<P>,
+    Extension(file_service): Extension<F>,
+    user: User,
+    scopes: Option<Extension<TokenScopes>>,
+    Path(project_id): Path<i32>,
+    ValidatedJson(options): ValidatedJson<CompileOptions>
+) -> Result<impl IntoResponse, Response>
+where
+    Q: JobService + Debug,
+    P: ProjectService + Debug,
+    F: FileService + Debug
+{
+    info!("Received project compilation attempt");
+    // The route only asks API tokens for the compile scope, but text overwrites the project's sources
+    if let (Some(_), Some(Extension(scopes))) = (&options.text, &scopes) {
+        if !scopes.grants(TokenScope::Write) {
+            warn!("API token is missing the write scope for compiling text");
+            return Err(StatusCode::FORBIDDEN.into_response());
+        }
+    }
+
+    let engine = match options.engine {
+        Some(engine) => engine,
+        None => match project_service.get(&user, project_id).await {
+            Ok(project) => project.engine,
+            Err(ProjectAccessError::Missing) => return Err(StatusCode::NOT_FOUND.into_response()),
+            Err(ProjectAccessError::Unknown) => return Err(StatusCode::INTERNAL_SERVER_ERROR.into_response())
+        }
+    };
+
+    if let Some(text) = &options.text {
+        let entrypoint = options.entrypoint.as_deref().unwrap_or(constants::DEFAULT_ENTRYPOINT);
+        if let Err(err) = file_service.write(&user, project_id, entrypoint, text.as_bytes().to_vec()).await {
+            return Err(file_error_status(err).into_response());
+        }
+    }
+
+    let dir = constants::PROJECTS_DIR.join(project_id.to_string());
+
+    // Written to disk by the build itself, once it holds the project's build lock
+    let files = file_service.snapshot(&user, project_id).await
+        .map_err(|err| file_error_status(err).into_response())?;
+
+    // The editor navigates with SyncTeX, so project builds always keep it
+    let mut formats = options.formats.clone();
+    formats.push(OutputFormat::Synctex);
+
+    let owner = Some(user.id);
+    let job_options = JobOptions::new(engine, formats);
+    let id = queue.submit(owner, job_options, CompileInput::Project { dir, files, options }).map_err(submit_rejection)?;
+    let output = await_job(&queue, owner, id).await?;
+
+    stream_pdf(&output).await
+}
+
+#[tracing::instrument(skip(queue, user, input), fields(user_id = user.id))]
+pub async fn post_job<Q: JobService + Debug>(
+    Extension(queue): Extension<Q>,
+    user: User,
+    CompileUpload(input, options): CompileUpload
+) -> Result<(StatusCode, Json<SubmittedJob>), Response> {
+    info!("Received compile job");
+    let id = queue.submit(Some(user.id), options, input).map_err(submit_rejection)?;
+
+    Ok((StatusCode::ACCEPTED, Json(SubmittedJob { id })))
+}
+
+#[tracing::instrument(skip(queue, user), fields(user_id = user.id))]
+pub async fn get_job<Q: JobService + Debug>(
+    Extension(queue): Extension<Q>,
+    user: User,
+    Path(id): Path<JobId>
+) -> Result<Json<JobReport>, StatusCode> {
+    match queue.status(Some(user.id), id) {
+        Some(status) => Ok(Json(status)),
+        None => Err(StatusCode::NOT_FOUND)
+    }
+}
+
+#[tracing::instrument(skip(queue, user), fields(user_id = user.id))]
+pub async fn get_job_pdf<Q: JobService + Debug>(
+    Extension(queue): Extension<Q>,
+    user: User,
+    Path(id): Path<JobId>
+) -> Result<impl IntoResponse, Response> {
+    let output = finished_result(&queue, Some(user.id), id)
+        .map_err(IntoResponse::into_response)?
+        .map_err(failure_response)?;
+    stream_pdf(&output).await
+}
+
+#[tracing::instrument(skip(queue, user), fields(user_id = user.id))]
+pub async fn get_job_artifact<Q: JobService + Debug>(
+    Extension(queue): Extension<Q>,
+    user: User,
+    Path((id, name)): Path<(JobId, String)>
+) -> Result<impl IntoResponse, Response> {
+    let output = finished_result(&queue, Some(user.id), id)
+        .map_err(IntoResponse::into_response)?
+        .map_err(failure_response)?;
+    let path = match output.artifact(&name) {
+        Some(path) => path,
+        None => return Err(StatusCode::NOT_FOUND.into_response())
+    };
+
+    let body = open_stream(path).await?;
+    // Documents can emit their own HTML and script, which must never run on the API's origin
+    let headers = AppendHeaders([
+        (CONTENT_TYPE, content_type(&name).to_owned()),
+        (CONTENT_DISPOSITION, "attachment".to_owned()),
+        (CONTENT_SECURITY_POLICY, "sandbox".to_owned()),
+        (X_CONTENT_TYPE_OPTIONS, "nosniff".to_owned())
+    ]);
+
+    info!("Streaming artifact {:?}", path);
+
+    Ok((headers, body))
+}
+
+#[tracing::instrument(skip(queue, user), fields(user_id = user.id))]
+pub async fn get_job_events<Q: JobService + Debug>(
+    Extension(queue): Extension<Q>,
+    user: User,
+    Path(id): Path<JobId>
+) -> Result<Sse<impl Stream<Item = Result<Event, axum::Error>>>, StatusCode> {
+    let (status, receiver) = queue.events(Some(user.id), id).ok_or(StatusCode::NOT_FOUND)?;
+
+    info!("Streaming job events");
+    let events = job_events(status, receiver)
+        .map(|event| Event::default().event(event.name()).json_data(&event).map_err(axum::Error::new));
+
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+#[tracing::instrument(skip(project_service, user), fields(user_id = user.id))]
+pub async fn get_project_diagnostics<P: ProjectService + Debug>(
+    Extension(project_service): Extension<P>,
+    user: User,
+    Path(project_id): Path<i32>,
+    Query(query): Query<DiagnosticsQuery>
+) -> Result<Json<Vec<Diagnostic>>, StatusCode> {
+    match project_service.get(&user, project_id).await {
+        Ok(_) => (),
+        Err(ProjectAccessError::Missing) => return Err(StatusCode::NOT_FOUND),
+        Err(ProjectAccessError::Unknown) => return Err(StatusCode::INTERNAL_SERVER_ERROR)
+    };
+
+    let entrypoint = query.entrypoint.unwrap_or_else(|| constants::DEFAULT_ENTRYPOINT.to_owned());
+    if is_relative_path(&entrypoint).is_err() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let log_name = FsPath::new(&entrypoint).with_extension("log");
+    let log_path = constants::PROJECTS_DIR
+        .join(project_id.to_string())
+        .join("out")
+        .join(log_name.file_name().unwrap());
+
+    match diagnostics::read_diagnostics(&log_path) {
+        Some(diagnostics) => Ok(Json(diagnostics)),
+        None => Err(StatusCode::NOT_FOUND)
+    }
+}
+
+#[tracing::instrument(skip(project_service, user), fields(user_id = user.id))]
+pub async fn get_project_synctex_forward<P: ProjectService + Debug>(
+    Extension(project_service): Extension<P>,
+    user: User,
+    Path(project_id): Path<i32>,
+    Query(query): Query<ForwardSearchQuery>
+) -> Result<Json<Vec<PdfBox>>, StatusCode> {
+    let synctex = read_project_synctex(&project_service, &user, project_id, query.entrypoint).await?;
+    Ok(Json(synctex.forward(&query.file, query.line, query.column)))
+}
+
+#[tracing::instrument(skip(project_service, user), fields(user_id = user.id))]
+pub async fn get_project_synctex_inverse<P: ProjectService + Debug>(
+    Extension(project_service): Extension<P>,
+    user: User,
+    Path(project_id): Path<i32>,
+    Query(query): Query<InverseSearchQuery>
+) -> Result<Json<SourceLocation>, StatusCode> {
+    let synctex = read_project_synctex(&project_service, &user, project_id, query.entrypoint).await?;
+    match synctex.inverse(query.page, query.x, query.y) {
+        Some(location) => Ok(Json(location)),
+        None => Err(StatusCode::NOT_FOUND)
+    }
+}
+
+async fn read_project_synctex<P: ProjectService>(project_service: &P, user: &User, project_id: i32, entrypoint: Option<String>) -> Result<SyncTex, StatusCode> {
+    match project_service.get(user, project_id).await {
+        Ok(_) => (),
+        Err(ProjectAccessError::Missing) => return Err(StatusCode::NOT_FOUND),
+        Err(ProjectAccessError::Unknown) => return Err(StatusCode::INTERNAL_SERVER_ERROR)
+    };
+
+    let entrypoint = entrypoint.unwrap_or_else(|| constants::DEFAULT_ENTRYPOINT.to_owned());
+    if is_relative_path(&entrypoint).is_err() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let project_dir = constants::PROJECTS_DIR.join(project_id.to_string());
+    let source_path = project_dir.join("src");
+    let input_path = source_path.join(&entrypoint);
+    let synctex_name = FsPath::new(&entrypoint).with_extension("synctex.gz");
+    let (base, synctex_path) = match (input_path.parent(), synctex_name.file_name()) {
+        (Some(base), Some(name)) => (base.to_path_buf(), project_dir.join("out").join(name)),
+        _ => return Err(StatusCode::BAD_REQUEST)
+    };
+
+    // Decompressing and parsing the whole file takes a while, so it does not run on the request's thread
+    match tokio::task::spawn_blocking(move || SyncTex::read(&synctex_path, &base, &source_path)).await {
+        Ok(Ok(synctex)) => Ok(synctex),
+        Ok(Err(err)) => {
+            warn!(%err, "SyncTeX data is not available");
+            Err(StatusCode::NOT_FOUND)
+        },
         Err(err) => {
-            error!(?err);
-            return Err((StatusCode::UNPROCESSABLE_ENTITY, err.into()));
+            error!(%err);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
         }
+    }
+}
+
+async fn await_job<Q: JobService>(queue: &Q, owner: Option<i32>, id: JobId) -> Result<CompileOutput, Response> {
+    match queue.wait(owner, id).await {
+        Some(Ok(output)) => Ok(output),
+        Some(Err(failure)) => {
+            error!(?failure.kind, "Compile job failed");
+            Err(failure_response(failure))
+        },
+        None => Err(StatusCode::INTERNAL_SERVER_ERROR.into_response())
+    }
+}
+
+fn finished_result<Q: JobService>(queue: &Q, owner: Option<i32>, id: JobId) -> Result<JobResult, StatusCode> {
+    match queue.status(owner, id) {
+        Some(report) if report.status.is_finished() => (),
+        Some(_) => return Err(StatusCode::CONFLICT),
+        None => return Err(StatusCode::NOT_FOUND)
     };
 
-    let file = tokio::fs::File::open(&path).await.unwrap();
+    queue.output(owner, id).ok_or(StatusCode::NOT_FOUND)
+}
 
-    let stream = ReaderStream::new(file);
-    let body = StreamBody::new(stream);
+// Starts with the current state, so clients that connect late still learn where the job is
+fn job_events(status: JobStatus, receiver: broadcast::Receiver<JobEvent>) -> impl Stream<Item = JobEvent> {
+    let finished = status.is_finished();
+    let current = match status {
+        JobStatus::Queued => JobEvent::Queued,
+        JobStatus::Running => JobEvent::Started,
+        status => JobEvent::Finished(status)
+    };
+
+    let live = stream::unfold((receiver, finished), |(mut receiver, finished)| async move {
+        if finished {
+            return None;
+        }
+        loop {
+            match receiver.recv().await {
+                Ok(event) => {
+                    let finished = matches!(event, JobEvent::Finished(_));
+                    return Some((event, (receiver, finished)));
+                },
+                Err(RecvError::Lagged(skipped)) => warn!(skipped, "Client fell behind on job events"),
+                Err(RecvError::Closed) => return None
+            }
+        }
+    });
+
+    stream::once(future::ready(current)).chain(live)
+}
+
+fn failure_response(failure: CompileFailure) -> Response {
+    (StatusCode::UNPROCESSABLE_ENTITY, Json(failure)).into_response()
+}
+
+fn submit_rejection(err: JobSubmitError) -> Response {
+    warn!(?err, "Compile job rejected");
+    match err {
+        JobSubmitError::UserLimit => StatusCode::TOO_MANY_REQUESTS.into_response(),
+        JobSubmitError::QueueFull => StatusCode::SERVICE_UNAVAILABLE.into_response()
+    }
+}
+
+fn file_error_status(err: FileAccessError) -> StatusCode {
+    match err {
+        FileAccessError::Missing => StatusCode::NOT_FOUND,
+        FileAccessError::Conflict => StatusCode::CONFLICT,
+        FileAccessError::Unknown => StatusCode::INTERNAL_SERVER_ERROR
+    }
+}
+
+fn content_type(name: &str) -> &'static str {
+    let extension = name.rsplit('.').next().unwrap_or_default();
+    match extension {
+        "pdf" => "application/pdf",
+        "dvi" => "application/x-dvi",
+        "ps" => "application/postscript",
+        "gz" => "application/gzip",
+        "log" | "blg" => "text/plain; charset=utf-8",
+        "html" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        _ => "application/octet-stream"
+    }
+}
+
+async fn open_stream(path: &FsPath) -> Result<StreamBody<ReaderStream<tokio::fs::File>>, Response> {
+    match tokio::fs::File::open(path).await {
+        Ok(file) => Ok(StreamBody::new(ReaderStream::new(file))),
+        Err(err) => {
+            error!(%err, "Compiled file is no longer available");
+            Err(StatusCode::NOT_FOUND.into_response())
+        }
+    }
+}
+
+async fn stream_pdf(output: &CompileOutput) -> Result<impl IntoResponse, Response> {
+    let body = open_stream(&output.pdf).await?;
 
     let headers = AppendHeaders([
-        (CONTENT_TYPE, "application/pdf"),
-        (CONTENT_DISPOSITION, "inline")
+        (CONTENT_TYPE, "application/pdf".to_owned()),
+        (CONTENT_DISPOSITION, "inline".to_owned()),
+        (constants::DIAGNOSTICS_COUNT_HEADER.clone(), output.diagnostics.len().to_string())
     ]);
 
-    info!("Compiled file {:?}", path);
+    info!("Compiled file {:?}", output.pdf);
 
     Ok((headers, body))
 }
+
+mod upload;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,272 @@
+use chrono::Utc;
+use mockall::predicate;
+use uuid::Uuid;
+
+use crate::{service::{compilation::queue::MockJobService, files::MockFileService, projects::MockProjectService}, domain::compile::{CompileFailure, CompileJob, Engine, FailureKind, JobOptions, JobStatus}};
+
+use super::*;
+
+fn mock_user() -> User {
+    User {
+        id: 1,
+        email: String::from("email"),
+        password_hash: String::from("password_hash")
+    }
+}
+
+fn mock_job_id() -> JobId {
+    Uuid::from_u128(10)
+}
+
+fn mock_report(status: JobStatus) -> JobReport {
+    JobReport {
+        job: CompileJob {
+            id: mock_job_id(),
+            owner_id: Some(mock_user().id),
+            options: JobOptions::default(),
+            created_at: Utc::now(),
+            started_at: None,
+            finished_at: None,
+            dir: constants::JOBS_DIR.join(mock_job_id().to_string())
+        },
+        status
+    }
+}
+
+fn mock_upload() -> CompileUpload {
+    CompileUpload(CompileInput::Text(String::from("text")), JobOptions::new(Engine::Xelatex, Vec::new()))
+}
+
+#[tokio::test]
+async fn post_job_normal() {
+    let mut queue = MockJobService::new();
+
+    queue
+        .expect_submit()
+        .with(predicate::eq(Some(mock_user().id)), predicate::eq(JobOptions::new(Engine::Xelatex, Vec::new())), predicate::always())
+        .times(1)
+        .returning(|_, _, _| Ok(mock_job_id()));
+
+    let (status, Json(job)) = post_job(Extension(queue), mock_user(), mock_upload()).await.unwrap();
+
+    assert_eq!(StatusCode::ACCEPTED, status);
+    assert_eq!(SubmittedJob { id: mock_job_id() }, job);
+}
+
+#[tokio::test]
+async fn post_job_user_limit() {
+    let mut queue = MockJobService::new();
+
+    queue
+        .expect_submit()
+        .times(1)
+        .returning(|_, _, _| Err(JobSubmitError::UserLimit));
+
+    let response = post_job(Extension(queue), mock_user(), mock_upload()).await.unwrap_err();
+
+    assert_eq!(StatusCode::TOO_MANY_REQUESTS, response.status());
+}
+
+#[tokio::test]
+async fn post_compile_queue_full() {
+    let mut queue = MockJobService::new();
+
+    queue
+        .expect_submit()
+        .with(predicate::eq(None), predicate::always(), predicate::always())
+        .times(1)
+        .returning(|_, _, _| Err(JobSubmitError::QueueFull));
+
+    let response = post_compile(Extension(queue), mock_upload()).await.err().unwrap();
+
+    assert_eq!(StatusCode::SERVICE_UNAVAILABLE, response.status());
+}
+
+#[tokio::test]
+async fn get_job_missing() {
+    let mut queue = MockJobService::new();
+
+    queue
+        .expect_status()
+        .with(predicate::eq(Some(mock_user().id)), predicate::eq(mock_job_id()))
+        .times(1)
+        .returning(|_, _| None);
+
+    assert_eq!(StatusCode::NOT_FOUND, get_job(Extension(queue), mock_user(), Path(mock_job_id())).await.unwrap_err());
+}
+
+#[tokio::test]
+async fn get_job_pdf_running() {
+    let mut queue = MockJobService::new();
+
+    queue
+        .expect_status()
+        .times(1)
+        .returning(|_, _| Some(mock_report(JobStatus::Running)));
+
+    let response = get_job_pdf(Extension(queue), mock_user(), Path(mock_job_id())).await.err().unwrap();
+
+    assert_eq!(StatusCode::CONFLICT, response.status());
+}
+
+#[tokio::test]
+async fn get_job_pdf_failed() {
+    let mut queue = MockJobService::new();
+    let failure = CompileFailure {
+        kind: FailureKind::Timeout,
+        message: String::from("timeout"),
+        diagnostics: Vec::new()
+    };
+    let report = mock_report(JobStatus::Failed(failure.clone()));
+
+    queue
+        .expect_status()
+        .times(1)
+        .returning(move |_, _| Some(report.clone()));
+    queue
+        .expect_output()
+        .times(1)
+        .returning(move |_, _| Some(Err(failure.clone())));
+
+    let response = get_job_pdf(Extension(queue), mock_user(), Path(mock_job_id())).await.err().unwrap();
+
+    assert_eq!(StatusCode::UNPROCESSABLE_ENTITY, response.status());
+}
+
+#[tokio::test]
+async fn get_job_artifact_missing() {
+    let mut queue = MockJobService::new();
+    let output = CompileOutput {
+        pdf: std::path::PathBuf::from("main.pdf"),
+        diagnostics: Vec::new(),
+        artifacts: vec![std::path::PathBuf::from("main.dvi")],
+        job_dir: None
+    };
+    let report = mock_report(JobStatus::Succeeded { diagnostics: Vec::new(), artifacts: output.artifact_names() });
+
+    queue
+        .expect_status()
+        .times(1)
+        .returning(move |_, _| Some(report.clone()));
+    queue
+        .expect_output()
+        .times(1)
+        .returning(move |_, _| Some(Ok(output.clone())));
+
+    let response = get_job_artifact(Extension(queue), mock_user(), Path((mock_job_id(), String::from("main.ps")))).await.err().unwrap();
+
+    assert_eq!(StatusCode::NOT_FOUND, response.status());
+}
+
+#[tokio::test]
+async fn get_job_artifact_nested_attachment() {
+    let dir = std::env::temp_dir().join(format!("agartex_artifact_{}", rand::random::<u32>()));
+    std::fs::create_dir_all(dir.join("html")).unwrap();
+    std::fs::write(dir.join("html/main.html"), "<script></script>").unwrap();
+    let mut queue = MockJobService::new();
+    let output = CompileOutput {
+        pdf: dir.join("main.pdf"),
+        diagnostics: Vec::new(),
+        artifacts: vec![dir.join("html/main.html")],
+        job_dir: None
+    };
+    let report = mock_report(JobStatus::Succeeded { diagnostics: Vec::new(), artifacts: output.artifact_names() });
+
+    queue
+        .expect_status()
+        .times(1)
+        .returning(move |_, _| Some(report.clone()));
+    queue
+        .expect_output()
+        .times(1)
+        .returning(move |_, _| Some(Ok(output.clone())));
+
+    let response = get_job_artifact(Extension(queue), mock_user(), Path((mock_job_id(), String::from("html/main.html")))).await.unwrap().into_response();
+
+    assert_eq!(StatusCode::OK, response.status());
+    assert_eq!("text/html; charset=utf-8", response.headers()[CONTENT_TYPE]);
+    assert_eq!("attachment", response.headers()[CONTENT_DISPOSITION]);
+    assert_eq!("sandbox", response.headers()[CONTENT_SECURITY_POLICY]);
+    assert_eq!("nosniff", response.headers()[X_CONTENT_TYPE_OPTIONS]);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn get_job_events_missing() {
+    let mut queue = MockJobService::new();
+
+    queue
+        .expect_events()
+        .with(predicate::eq(Some(mock_user().id)), predicate::eq(mock_job_id()))
+        .times(1)
+        .returning(|_, _| None);
+
+    assert_eq!(StatusCode::NOT_FOUND, get_job_events(Extension(queue), mock_user(), Path(mock_job_id())).await.err().unwrap());
+}
+
+#[tokio::test]
+async fn job_events_until_finished() {
+    let (sender, receiver) = broadcast::channel(16);
+    let finished = JobStatus::Succeeded { diagnostics: Vec::new(), artifacts: Vec::new() };
+
+    sender.send(JobEvent::Started).unwrap();
+    sender.send(JobEvent::Finished(finished.clone())).unwrap();
+    sender.send(JobEvent::Started).unwrap();
+
+    let events: Vec<JobEvent> = job_events(JobStatus::Queued, receiver).collect().await;
+
+    assert_eq!(vec![JobEvent::Queued, JobEvent::Started, JobEvent::Finished(finished)], events);
+}
+
+#[tokio::test]
+async fn job_events_already_finished() {
+    let (_sender, receiver) = broadcast::channel(16);
+    let failure = CompileFailure {
+        kind: FailureKind::Compilation,
+        message: String::from("failed"),
+        diagnostics: Vec::new()
+    };
+
+    let events: Vec<JobEvent> = job_events(JobStatus::Failed(failure.clone()), receiver).collect().await;
+
+    assert_eq!(vec![JobEvent::Finished(JobStatus::Failed(failure))], events);
+}
+
+#[test]
+fn content_type_by_extension() {
+    assert_eq!("application/x-dvi", content_type("main.dvi"));
+    assert_eq!("application/gzip", content_type("main.synctex.gz"));
+    assert_eq!("text/html; charset=utf-8", content_type("main.html"));
+    assert_eq!("application/octet-stream", content_type("main"));
+}
+
+#[test]
+fn job_event_serializes_finished_status() {
+    let event = JobEvent::Finished(JobStatus::Succeeded { diagnostics: Vec::new(), artifacts: Vec::new() });
+
+    assert!(Event::default().event(event.name()).json_data(&event).is_ok());
+}
+
+#[tokio::test]
+async fn post_project_pdf_text_without_write_scope() {
+    let options = CompileOptions {
+        entrypoint: None,
+        force: false,
+        text: Some(String::from("text")),
+        engine: Some(Engine::Pdflatex),
+        formats: Vec::new()
+    };
+    let scopes = Some(Extension(TokenScopes(vec![TokenScope::Compile])));
+
+    let response = post_project_pdf(
+        Extension(MockJobService::new()),
+        Extension(MockProjectService::new()),
+        Extension(MockFileService::new()),
+        mock_user(),
+        scopes,
+        Path(1),
+        ValidatedJson(options)
+    ).await.err().unwrap();
+
+    assert_eq!(StatusCode::FORBIDDEN, response.status());
+}
@@ -0,0 +1,163 @@
+use axum::{async_trait, extract::{FromRequest, FromRequestParts, Multipart, Query}, body::{Body, Bytes}, response::{IntoResponse, Response}};
+use http::{Request, StatusCode, header::CONTENT_TYPE};
+use serde::Deserialize;
+use tracing::warn;
+
+use crate::{constants, domain::{compile::{Engine, JobOptions, OutputFormat, SourceFile}, files::is_relative_path}, service::compilation::{CompileInput, upload::{self, UploadError}}};
+
+#[derive(Debug, Deserialize)]
+pub struct UploadQuery {
+    pub entrypoint: Option<String>,
+    pub engine: Option<Engine>,
+    pub formats: Option<String>
+}
+
+pub struct CompileUpload(pub CompileInput, pub JobOptions);
+
+fn upload_rejection(err: UploadError) -> Response {
+    let status = match err {
+        UploadError::TooLarge | UploadError::TooManyEntries => StatusCode::PAYLOAD_TOO_LARGE,
+        UploadError::InvalidArchive | UploadError::InvalidPath(_) => StatusCode::BAD_REQUEST
+    };
+    (status, String::from(err)).into_response()
+}
+
+fn parse_formats(formats: Option<&str>) -> Result<Vec<OutputFormat>, String> {
+    formats.unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|format| !format.is_empty())
+        .map(|format| OutputFormat::try_from(format.to_owned()))
+        .collect()
+}
+
+fn formats_rejection(format: String) -> Response {
+    (StatusCode::BAD_REQUEST, format!("Unknown output format {}", format)).into_response()
+}
+
+fn validated_entrypoint(entrypoint: Option<String>) -> Result<String, UploadError> {
+    let entrypoint = entrypoint.unwrap_or_else(|| constants::DEFAULT_ENTRYPOINT.to_owned());
+    match is_relative_path(&entrypoint) {
+        Ok(()) => Ok(entrypoint),
+        Err(_) => Err(UploadError::InvalidPath(entrypoint))
+    }
+}
+
+async fn read_multipart(mut multipart: Multipart, query: UploadQuery) -> Result<CompileUpload, Response> {
+    let mut entrypoint = query.entrypoint;
+    let mut engine = query.engine;
+    let mut formats = query.formats;
+    let mut files = Vec::new();
+
+    loop {
+        let field = match multipart.next_field().await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            Err(err) => {
+                warn!(%err);
+                return Err((StatusCode::BAD_REQUEST, err.to_string()).into_response());
+            }
+        };
+
+        if field.name() == Some("entrypoint") {
+            match field.text().await {
+                Ok(text) => entrypoint = Some(text),
+                Err(err) => return Err((StatusCode::BAD_REQUEST, err.to_string()).into_response())
+            };
+            continue;
+        }
+
+        if field.name() == Some("engine") {
+            let text = match field.text().await {
+                Ok(text) => text,
+                Err(err) => return Err((StatusCode::BAD_REQUEST, err.to_string()).into_response())
+            };
+            match Engine::try_from(text) {
+                Ok(value) => engine = Some(value),
+                Err(err) => return Err((StatusCode::BAD_REQUEST, err).into_response())
+            };
+            continue;
+        }
+
+        if field.name() == Some("formats") {
+            match field.text().await {
+                Ok(text) => formats = Some(text),
+                Err(err) => return Err((StatusCode::BAD_REQUEST, err.to_string()).into_response())
+            };
+            continue;
+        }
+
+        let path = match field.file_name() {
+            Some(path) => path.to_owned(),
+            None => continue
+        };
+
+        if files.len() >= constants::MAX_UPLOAD_ENTRIES {
+            return Err(upload_rejection(UploadError::TooManyEntries));
+        }
+
+        match field.bytes().await {
+            Ok(content) => files.push(SourceFile { path, content: content.to_vec() }),
+            Err(err) => return Err((StatusCode::BAD_REQUEST, err.to_string()).into_response())
+        };
+    }
+
+    upload::check_files(&files).map_err(upload_rejection)?;
+
+    let input = CompileInput::Files {
+        files,
+        entrypoint: validated_entrypoint(entrypoint).map_err(upload_rejection)?
+    };
+    let formats = parse_formats(formats.as_deref()).map_err(formats_rejection)?;
+    Ok(CompileUpload(input, JobOptions::new(engine.unwrap_or_default(), formats)))
+}
+
+#[async_trait]
+impl<S> FromRequest<S, Body> for CompileUpload
+where
+    S: Send + Sync
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request<Body>, state: &S) -> Result<Self, Self::Rejection> {
+        let content_type = req.headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+            .to_owned();
+
+        let (mut parts, body) = req.into_parts();
+        let Query(query) = Query::<UploadQuery>::from_request_parts(&mut parts, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        let req = Request::from_parts(parts, body);
+
+        if content_type.starts_with("multipart/form-data") {
+            let multipart = Multipart::from_request(req, state)
+                .await
+                .map_err(IntoResponse::into_response)?;
+            return read_multipart(multipart, query).await;
+        }
+
+        let formats = parse_formats(query.formats.as_deref()).map_err(formats_rejection)?;
+        let options = JobOptions::new(query.engine.unwrap_or_default(), formats);
+
+        if content_type.starts_with("application/zip") {
+            let archive = Bytes::from_request(req, state)
+                .await
+                .map_err(IntoResponse::into_response)?;
+
+            let files = upload::unpack_zip(&archive).map_err(upload_rejection)?;
+            let input = CompileInput::Files {
+                files,
+                entrypoint: validated_entrypoint(query.entrypoint).map_err(upload_rejection)?
+            };
+            return Ok(Self(input, options));
+        }
+
+        let raw_text = String::from_request(req, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        Ok(Self(CompileInput::Text(raw_text), options))
+    }
+}
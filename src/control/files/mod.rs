@@ -0,0 +1,85 @@
+use std::fmt::Debug;
+
+use axum::{Extension, Json, extract::Path, body::Bytes, response::{IntoResponse, AppendHeaders}};
+use http::header::CONTENT_TYPE;
+use hyper::StatusCode;
+use tracing::{info, warn};
+
+use crate::{domain::{files::{ProjectFile, is_relative_path}, users::User}, service::files::{FileService, FileAccessError}, constants};
+
+fn status_of(err: FileAccessError) -> StatusCode {
+    match err {
+        FileAccessError::Missing => StatusCode::NOT_FOUND,
+        FileAccessError::Conflict => StatusCode::CONFLICT,
+        FileAccessError::Unknown => StatusCode::INTERNAL_SERVER_ERROR
+    }
+}
+
+fn validate_path(path: &str) -> Result<(), StatusCode> {
+    if path.len() > constants::MAX_FILE_PATH_LENGTH || is_relative_path(path).is_err() {
+        warn!("Rejected file path");
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    Ok(())
+}
+
+#[tracing::instrument(skip(service, user), fields(user_id = user.id))]
+pub async fn get_files<T: FileService + Debug>(
+    Extension(service): Extension<T>,
+    user: User,
+    Path(project_id): Path<i32>
+) -> Result<Json<Vec<ProjectFile>>, StatusCode> {
+    match service.list(&user, project_id).await {
+        Ok(files) => Ok(Json(files)),
+        Err(err) => Err(status_of(err))
+    }
+}
+
+#[tracing::instrument(skip(service, user), fields(user_id = user.id))]
+pub async fn get_file<T: FileService + Debug>(
+    Extension(service): Extension<T>,
+    user: User,
+    Path((project_id, path)): Path<(i32, String)>
+) -> Result<impl IntoResponse, StatusCode> {
+    validate_path(&path)?;
+    match service.read(&user, project_id, &path).await {
+        Ok(content) => Ok((AppendHeaders([(CONTENT_TYPE, "application/octet-stream")]), content)),
+        Err(err) => Err(status_of(err))
+    }
+}
+
+#[tracing::instrument(skip(service, user, content), fields(user_id = user.id))]
+pub async fn put_file<T: FileService + Debug>(
+    Extension(service): Extension<T>,
+    user: User,
+    Path((project_id, path)): Path<(i32, String)>,
+    content: Bytes
+) -> StatusCode {
+    info!("Received file upload");
+    if let Err(status) = validate_path(&path) {
+        return status;
+    }
+    match service.write(&user, project_id, &path, content.to_vec()).await {
+        Ok(()) => StatusCode::NO_CONTENT,
+        Err(err) => status_of(err)
+    }
+}
+
+#[tracing::instrument(skip(service, user), fields(user_id = user.id))]
+pub async fn delete_file<T: FileService + Debug>(
+    Extension(service): Extension<T>,
+    user: User,
+    Path((project_id, path)): Path<(i32, String)>
+) -> StatusCode {
+    info!("Received file deletion attempt");
+    if let Err(status) = validate_path(&path) {
+        return status;
+    }
+    match service.delete(&user, project_id, &path).await {
+        Ok(()) => StatusCode::NO_CONTENT,
+        Err(err) => status_of(err)
+    }
+}
+
+#[cfg(test)]
+mod tests;
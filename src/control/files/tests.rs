@@ -0,0 +1,116 @@
+use mockall::predicate;
+
+use crate::service::files::MockFileService;
+
+use super::*;
+
+fn mock_user() -> User {
+    User {
+        id: 1,
+        email: String::from("email"),
+        password_hash: String::from("password_hash")
+    }
+}
+
+fn mock_project_id() -> i32 {
+    10
+}
+
+fn mock_path() -> String {
+    String::from("main.tex")
+}
+
+fn mock_content() -> Vec<u8> {
+    b"\\documentclass{article}".to_vec()
+}
+
+#[tokio::test]
+async fn put_file_normal() {
+    let mut file_service = MockFileService::new();
+
+    file_service
+        .expect_write()
+        .with(predicate::eq(mock_user()), predicate::eq(mock_project_id()), predicate::eq(mock_path()), predicate::eq(mock_content()))
+        .times(1)
+        .returning(|_, _, _, _| Ok(()));
+
+    let status = put_file(Extension(file_service), mock_user(), Path((mock_project_id(), mock_path())), Bytes::from(mock_content())).await;
+    assert_eq!(StatusCode::NO_CONTENT, status);
+}
+
+#[tokio::test]
+async fn put_file_path_traversal() {
+    let mut file_service = MockFileService::new();
+
+    file_service
+        .expect_write()
+        .never();
+
+    let status = put_file(Extension(file_service), mock_user(), Path((mock_project_id(), String::from("../../etc/passwd"))), Bytes::from(mock_content())).await;
+    assert_eq!(StatusCode::BAD_REQUEST, status);
+}
+
+#[tokio::test]
+async fn put_file_path_too_long() {
+    let mut file_service = MockFileService::new();
+
+    file_service
+        .expect_write()
+        .never();
+
+    let status = put_file(Extension(file_service), mock_user(), Path((mock_project_id(), "a".repeat(257))), Bytes::from(mock_content())).await;
+    assert_eq!(StatusCode::BAD_REQUEST, status);
+}
+
+#[tokio::test]
+async fn put_file_conflict() {
+    let mut file_service = MockFileService::new();
+
+    file_service
+        .expect_write()
+        .times(1)
+        .returning(|_, _, _, _| Err(FileAccessError::Conflict));
+
+    let status = put_file(Extension(file_service), mock_user(), Path((mock_project_id(), mock_path())), Bytes::from(mock_content())).await;
+    assert_eq!(StatusCode::CONFLICT, status);
+}
+
+#[tokio::test]
+async fn get_file_missing() {
+    let mut file_service = MockFileService::new();
+
+    file_service
+        .expect_read()
+        .with(predicate::eq(mock_user()), predicate::eq(mock_project_id()), predicate::eq(mock_path()))
+        .times(1)
+        .returning(|_, _, _| Err(FileAccessError::Missing));
+
+    let result = get_file(Extension(file_service), mock_user(), Path((mock_project_id(), mock_path()))).await;
+    assert_eq!(StatusCode::NOT_FOUND, result.err().unwrap());
+}
+
+#[tokio::test]
+async fn delete_file_absolute_path() {
+    let mut file_service = MockFileService::new();
+
+    file_service
+        .expect_delete()
+        .never();
+
+    let status = delete_file(Extension(file_service), mock_user(), Path((mock_project_id(), String::from("/etc/passwd")))).await;
+    assert_eq!(StatusCode::BAD_REQUEST, status);
+}
+
+#[tokio::test]
+async fn delete_file_normal() {
+    let mut file_service = MockFileService::new();
+
+    file_service
+        .expect_delete()
+        .with(predicate::eq(mock_user()), predicate::eq(mock_project_id()), predicate::eq(mock_path()))
+        .times(1)
+        .returning(|_, _, _| Ok(()));
+
+    let status = delete_file(Extension(file_service), mock_user(), Path((mock_project_id(), mock_path()))).await;
+    assert_eq!(StatusCode::NO_CONTENT, status);
+}
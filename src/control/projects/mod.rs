@@ -0,0 +1,75 @@
+use std::fmt::Debug;
+
+use axum::{Extension, Json, extract::Path};
+use hyper::StatusCode;
+use tracing::info;
+
+use crate::{domain::{projects::{Project, ProjectMetadata}, users::User}, service::projects::{ProjectService, ProjectAccessError, ProjectCreationError}, validation::ValidatedJson};
+
+#[tracing::instrument(skip_all, fields(user_id = user.id))]
+pub async fn get_projects<T: ProjectService + Debug>(Extension(service): Extension<T>, user: User) -> Result<Json<Vec<Project>>, StatusCode> {
+    info!("Received project list request");
+    match service.list(&user).await {
+        Ok(projects) => Ok(Json(projects)),
+        Err(ProjectAccessError::Missing) => Err(StatusCode::NOT_FOUND),
+        Err(ProjectAccessError::Unknown) => Err(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+}
+
+#[tracing::instrument(skip_all, fields(user_id = user.id, name = metadata.name))]
+pub async fn post_projects<T: ProjectService + Debug>(
+    Extension(service): Extension<T>,
+    user: User,
+    ValidatedJson(metadata): ValidatedJson<ProjectMetadata>
+) -> Result<(StatusCode, Json<Project>), StatusCode> {
+    info!("Received project creation attempt");
+    match service.create(&user, metadata).await {
+        Ok(project) => Ok((StatusCode::CREATED, Json(project))),
+        Err(ProjectCreationError::Unknown) => Err(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+}
+
+#[tracing::instrument(skip(service, user), fields(user_id = user.id))]
+pub async fn get_project<T: ProjectService + Debug>(
+    Extension(service): Extension<T>,
+    user: User,
+    Path(project_id): Path<i32>
+) -> Result<Json<Project>, StatusCode> {
+    match service.get(&user, project_id).await {
+        Ok(project) => Ok(Json(project)),
+        Err(ProjectAccessError::Missing) => Err(StatusCode::NOT_FOUND),
+        Err(ProjectAccessError::Unknown) => Err(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+}
+
+#[tracing::instrument(skip(service, user, metadata), fields(user_id = user.id))]
+pub async fn patch_project<T: ProjectService + Debug>(
+    Extension(service): Extension<T>,
+    user: User,
+    Path(project_id): Path<i32>,
+    ValidatedJson(metadata): ValidatedJson<ProjectMetadata>
+) -> Result<Json<Project>, StatusCode> {
+    info!("Received project update attempt");
+    match service.update(&user, project_id, metadata).await {
+        Ok(project) => Ok(Json(project)),
+        Err(ProjectAccessError::Missing) => Err(StatusCode::NOT_FOUND),
+        Err(ProjectAccessError::Unknown) => Err(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+}
+
+#[tracing::instrument(skip(service, user), fields(user_id = user.id))]
+pub async fn delete_project<T: ProjectService + Debug>(
+    Extension(service): Extension<T>,
+    user: User,
+    Path(project_id): Path<i32>
+) -> StatusCode {
+    info!("Received project deletion attempt");
+    match service.delete(&user, project_id).await {
+        Ok(()) => StatusCode::NO_CONTENT,
+        Err(ProjectAccessError::Missing) => StatusCode::NOT_FOUND,
+        Err(ProjectAccessError::Unknown) => StatusCode::INTERNAL_SERVER_ERROR
+    }
+}
+
+#[cfg(test)]
+mod tests;
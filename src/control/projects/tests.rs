@@ -0,0 +1,131 @@
+use chrono::NaiveDateTime;
+use mockall::predicate;
+
+use crate::{service::projects::MockProjectService, domain::compile::Engine};
+
+use super::*;
+
+fn mock_user() -> User {
+    User {
+        id: 1,
+        email: String::from("email"),
+        password_hash: String::from("password_hash")
+    }
+}
+
+fn mock_project_id() -> i32 {
+    10
+}
+
+fn mock_metadata() -> ProjectMetadata {
+    ProjectMetadata {
+        name: String::from("project"),
+        engine: None
+    }
+}
+
+fn mock_project() -> Project {
+    Project {
+        id: mock_project_id(),
+        owner_id: mock_user().id,
+        name: mock_metadata().name,
+        engine: Engine::Pdflatex,
+        created_at: NaiveDateTime::from_timestamp_opt(0, 0).unwrap(),
+        updated_at: NaiveDateTime::from_timestamp_opt(0, 0).unwrap()
+    }
+}
+
+#[tokio::test]
+async fn get_projects_normal() {
+    let mut project_service = MockProjectService::new();
+
+    project_service
+        .expect_list()
+        .with(predicate::eq(mock_user()))
+        .times(1)
+        .returning(|_| Ok(vec![mock_project()]));
+
+    let Json(projects) = get_projects(Extension(project_service), mock_user()).await.unwrap();
+    assert_eq!(vec![mock_project()], projects);
+}
+
+#[tokio::test]
+async fn post_projects_normal() {
+    let mut project_service = MockProjectService::new();
+
+    project_service
+        .expect_create()
+        .with(predicate::eq(mock_user()), predicate::eq(mock_metadata()))
+        .times(1)
+        .returning(|_, _| Ok(mock_project()));
+
+    let (status, Json(project)) = post_projects(Extension(project_service), mock_user(), ValidatedJson(mock_metadata())).await.unwrap();
+    assert_eq!(StatusCode::CREATED, status);
+    assert_eq!(mock_project(), project);
+}
+
+#[tokio::test]
+async fn post_projects_unknown_error() {
+    let mut project_service = MockProjectService::new();
+
+    project_service
+        .expect_create()
+        .with(predicate::eq(mock_user()), predicate::eq(mock_metadata()))
+        .times(1)
+        .returning(|_, _| Err(ProjectCreationError::Unknown));
+
+    assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, post_projects(Extension(project_service), mock_user(), ValidatedJson(mock_metadata())).await.err().unwrap());
+}
+
+#[tokio::test]
+async fn get_project_missing() {
+    let mut project_service = MockProjectService::new();
+
+    project_service
+        .expect_get()
+        .with(predicate::eq(mock_user()), predicate::eq(mock_project_id()))
+        .times(1)
+        .returning(|_, _| Err(ProjectAccessError::Missing));
+
+    assert_eq!(StatusCode::NOT_FOUND, get_project(Extension(project_service), mock_user(), Path(mock_project_id())).await.err().unwrap());
+}
+
+#[tokio::test]
+async fn patch_project_normal() {
+    let mut project_service = MockProjectService::new();
+
+    project_service
+        .expect_update()
+        .with(predicate::eq(mock_user()), predicate::eq(mock_project_id()), predicate::eq(mock_metadata()))
+        .times(1)
+        .returning(|_, _, _| Ok(mock_project()));
+
+    let Json(project) = patch_project(Extension(project_service), mock_user(), Path(mock_project_id()), ValidatedJson(mock_metadata())).await.unwrap();
+    assert_eq!(mock_project(), project);
+}
+
+#[tokio::test]
+async fn delete_project_normal() {
+    let mut project_service = MockProjectService::new();
+
+    project_service
+        .expect_delete()
+        .with(predicate::eq(mock_user()), predicate::eq(mock_project_id()))
+        .times(1)
+        .returning(|_, _| Ok(()));
+
+    assert_eq!(StatusCode::NO_CONTENT, delete_project(Extension(project_service), mock_user(), Path(mock_project_id())).await);
+}
+
+#[tokio::test]
+async fn delete_project_missing() {
+    let mut project_service = MockProjectService::new();
+
+    project_service
+        .expect_delete()
+        .with(predicate::eq(mock_user()), predicate::eq(mock_project_id()))
+        .times(1)
+        .returning(|_, _| Err(ProjectAccessError::Missing));
+
+    assert_eq!(StatusCode::NOT_FOUND, delete_project(Extension(project_service), mock_user(), Path(mock_project_id())).await);
+}
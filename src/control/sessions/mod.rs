@@ -1,21 +1,22 @@
 use std::fmt::Debug;
 
-use axum::{Extension, Json};
+use axum::{Extension, Json, extract::Path};
 use axum_extra::extract::{CookieJar, cookie::Cookie};
-use cookie::time::OffsetDateTime;
 use hyper::StatusCode;
 use tracing::info;
 
-use crate::{service::sessions::{SessionService, LoginError}, domain::users::Credentials, constants::SESSION_COOKIE_NAME};
+use crate::{service::sessions::{SessionService, LoginError, LogoutError, SessionListError, SessionRevokeError}, domain::{users::{Credentials, User}, sessions::{ClientInfo, SessionInfo}}, auth::CookieConfig};
 
 #[tracing::instrument(skip_all, fields(email = credentials.email))]
 pub async fn post_sessions<T: SessionService + Debug>(
     Extension(service): Extension<T>,
+    Extension(cookies): Extension<CookieConfig>,
     jar: CookieJar,
+    client: ClientInfo,
     Json(credentials): Json<Credentials>
 ) -> Result<(CookieJar, StatusCode), StatusCode> {
     info!("Received login attempt");
-    let session = match service.login(credentials).await {
+    let session = match service.login(credentials, client).await {
         Err(LoginError::NoUser) =>  {
             return Err(StatusCode::UNAUTHORIZED);
         },
@@ -25,13 +26,74 @@ pub async fn post_sessions<T: SessionService + Debug>(
         Ok(session) => session
     };
 
-    let cookie = Cookie::build(SESSION_COOKIE_NAME, session.id)
-        .expires(OffsetDateTime::from_unix_timestamp(session.expires).unwrap())
-        .http_only(true)
-        // .secure(true) <-- add this when TLS is set up
-        .finish();
+    let jar = jar
+        .add(cookies.session_cookie(&session.id, session.expires))
+        .add(cookies.csrf_cookie(&session.id, session.expires));
+    Ok((jar, StatusCode::CREATED))
+}
+
+#[tracing::instrument(skip_all, fields(user_id = user.id))]
+pub async fn get_sessions<T: SessionService + Debug>(
+    Extension(service): Extension<T>,
+    Extension(cookies): Extension<CookieConfig>,
+    user: User,
+    jar: CookieJar
+) -> Result<Json<Vec<SessionInfo>>, StatusCode> {
+    let current_id = jar.get(&cookies.session_name()).map(Cookie::value).unwrap_or_default();
+    match service.list(user.id, current_id).await {
+        Ok(sessions) => Ok(Json(sessions)),
+        Err(SessionListError::Unknown) => Err(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn delete_sessions<T: SessionService + Debug>(
+    Extension(service): Extension<T>,
+    Extension(cookies): Extension<CookieConfig>,
+    jar: CookieJar
+) -> Result<(CookieJar, StatusCode), StatusCode> {
+    info!("Received logout");
+    let session_id = match jar.get(&cookies.session_name()) {
+        Some(cookie) => cookie.value().to_owned(),
+        None => return Err(StatusCode::UNAUTHORIZED)
+    };
+
+    match service.logout(&session_id).await {
+        Ok(()) => Ok((remove_cookies(jar, &cookies), StatusCode::NO_CONTENT)),
+        Err(LogoutError::Unknown) => Err(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+}
+
+#[tracing::instrument(skip_all, fields(user_id = user.id))]
+pub async fn delete_all_sessions<T: SessionService + Debug>(
+    Extension(service): Extension<T>,
+    Extension(cookies): Extension<CookieConfig>,
+    user: User,
+    jar: CookieJar
+) -> Result<(CookieJar, StatusCode), StatusCode> {
+    info!("Received logout of every session");
+    match service.logout_all(user.id).await {
+        Ok(_) => Ok((remove_cookies(jar, &cookies), StatusCode::NO_CONTENT)),
+        Err(LogoutError::Unknown) => Err(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+}
+
+#[tracing::instrument(skip(service, user), fields(user_id = user.id))]
+pub async fn delete_session<T: SessionService + Debug>(
+    Extension(service): Extension<T>,
+    user: User,
+    Path(public_id): Path<i32>
+) -> StatusCode {
+    match service.revoke(user.id, public_id).await {
+        Ok(()) => StatusCode::NO_CONTENT,
+        Err(SessionRevokeError::Missing) => StatusCode::NOT_FOUND,
+        Err(SessionRevokeError::Unknown) => StatusCode::INTERNAL_SERVER_ERROR
+    }
+}
 
-    Ok((jar.add(cookie), StatusCode::CREATED))
+fn remove_cookies(jar: CookieJar, cookies: &CookieConfig) -> CookieJar {
+    jar.remove(cookies.removal(cookies.session_name()))
+        .remove(cookies.removal(cookies.csrf_name()))
 }
 
 #[cfg(test)]
@@ -1,7 +1,8 @@
+use axum_extra::extract::cookie::SameSite;
 use mockall::predicate;
 use sqlx::types::chrono::Utc;
 
-use crate::{service::sessions::MockSessionService, domain::{sessions::Session, users::User}};
+use crate::{service::sessions::MockSessionService, domain::sessions::Session, constants::{SESSION_COOKIE_NAME, CSRF_COOKIE_NAME}, csrf};
 
 use super::*;
 
@@ -32,31 +33,77 @@ fn mock_user() -> User {
     }
 }
 
+fn mock_client() -> ClientInfo {
+    ClientInfo {
+        ip_address: Some(String::from("127.0.0.1")),
+        user_agent: Some(String::from("user_agent"))
+    }
+}
+
 fn mock_session() -> Session {
     Session {
         id: mock_session_id(),
         user: mock_user(),
-        expires: Utc::now().timestamp()
+        expires: Utc::now().timestamp(),
+        created_at: Utc::now().timestamp(),
+        last_seen: Utc::now().timestamp(),
+        client: mock_client()
     }
 }
 
+fn mock_jar() -> CookieJar {
+    CookieJar::new().add(Cookie::new(SESSION_COOKIE_NAME, mock_session_id()))
+}
+
 #[tokio::test]
 async fn post_sessions_normal() {
     let mut session_service = MockSessionService::new();
 
     session_service
         .expect_login()
-        .with(predicate::eq(mock_credentials()))
+        .with(predicate::eq(mock_credentials()), predicate::eq(mock_client()))
         .times(1)
-        .returning(|_| Ok(mock_session()));
+        .returning(|_, _| Ok(mock_session()));
 
-    let (jar, status) = post_sessions(Extension(session_service), CookieJar::new(), Json(mock_credentials())).await.unwrap();
+    let (jar, status) = post_sessions(Extension(session_service), Extension(CookieConfig::default()), CookieJar::new(), mock_client(), Json(mock_credentials())).await.unwrap();
     assert_eq!(StatusCode::CREATED, status);
 
     let cookie = jar.get(SESSION_COOKIE_NAME).unwrap();
     assert_eq!(mock_session().id, cookie.value());
     assert_eq!(mock_session().expires, cookie.expires().unwrap().datetime().unwrap().unix_timestamp());
     assert!(cookie.http_only().unwrap());
+    assert_eq!(Some(SameSite::Lax), cookie.same_site());
+    assert_eq!(Some("/"), cookie.path());
+
+    let csrf_cookie = jar.get(CSRF_COOKIE_NAME).unwrap();
+    assert_eq!(csrf::token(&mock_session().id), csrf_cookie.value());
+    assert_eq!(None, csrf_cookie.http_only());
+}
+
+#[tokio::test]
+async fn post_sessions_host_prefix() {
+    let mut session_service = MockSessionService::new();
+    let cookies = CookieConfig {
+        host_prefix: true,
+        domain: Some(String::from("example.com")),
+        path: String::from("/api"),
+        same_site: SameSite::Strict,
+        ..CookieConfig::default()
+    };
+
+    session_service
+        .expect_login()
+        .times(1)
+        .returning(|_, _| Ok(mock_session()));
+
+    let (jar, _) = post_sessions(Extension(session_service), Extension(cookies), CookieJar::new(), mock_client(), Json(mock_credentials())).await.unwrap();
+
+    let cookie = jar.get("__Host-RSESSID").unwrap();
+    assert!(cookie.secure().unwrap());
+    assert_eq!(Some("/"), cookie.path());
+    assert_eq!(None, cookie.domain());
+    assert_eq!(Some(SameSite::Strict), cookie.same_site());
+    assert!(jar.get("__Host-CSRF-TOKEN").is_some());
 }
 
 #[tokio::test]
@@ -65,11 +112,11 @@ async fn post_sessions_no_user_error() {
 
     session_service
         .expect_login()
-        .with(predicate::eq(mock_credentials()))
+        .with(predicate::eq(mock_credentials()), predicate::eq(mock_client()))
         .times(1)
-        .returning(|_| Err(LoginError::NoUser));
+        .returning(|_, _| Err(LoginError::NoUser));
 
-    assert_eq!(StatusCode::UNAUTHORIZED, post_sessions(Extension(session_service), CookieJar::new(), Json(mock_credentials())).await.err().unwrap())
+    assert_eq!(StatusCode::UNAUTHORIZED, post_sessions(Extension(session_service), Extension(CookieConfig::default()), CookieJar::new(), mock_client(), Json(mock_credentials())).await.err().unwrap())
 }
 
 #[tokio::test]
@@ -78,9 +125,85 @@ async fn post_sessions_unknown_error() {
 
     session_service
         .expect_login()
-        .with(predicate::eq(mock_credentials()))
+        .with(predicate::eq(mock_credentials()), predicate::eq(mock_client()))
+        .times(1)
+        .returning(|_, _| Err(LoginError::Unknown));
+
+    assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, post_sessions(Extension(session_service), Extension(CookieConfig::default()), CookieJar::new(), mock_client(), Json(mock_credentials())).await.err().unwrap())
+}
+
+#[tokio::test]
+async fn get_sessions_normal() {
+    let mut session_service = MockSessionService::new();
+    let info = SessionInfo {
+        id: 1,
+        created_at: 0,
+        last_seen: 0,
+        expires: 0,
+        client: mock_client(),
+        current: true
+    };
+    let expected = vec![info.clone()];
+
+    session_service
+        .expect_list()
+        .with(predicate::eq(mock_user().id), predicate::eq(mock_session_id()))
+        .times(1)
+        .returning(move |_, _| Ok(vec![info.clone()]));
+
+    let Json(sessions) = get_sessions(Extension(session_service), Extension(CookieConfig::default()), mock_user(), mock_jar()).await.unwrap();
+    assert_eq!(expected, sessions);
+}
+
+#[tokio::test]
+async fn delete_sessions_normal() {
+    let mut session_service = MockSessionService::new();
+
+    session_service
+        .expect_logout()
+        .with(predicate::eq(mock_session_id()))
+        .times(1)
+        .returning(|_| Ok(()));
+
+    let (jar, status) = delete_sessions(Extension(session_service), Extension(CookieConfig::default()), mock_jar()).await.unwrap();
+    assert_eq!(StatusCode::NO_CONTENT, status);
+    assert!(jar.get(SESSION_COOKIE_NAME).is_none());
+    assert!(jar.get(CSRF_COOKIE_NAME).is_none());
+}
+
+#[tokio::test]
+async fn delete_sessions_missing_cookie() {
+    let mut session_service = MockSessionService::new();
+
+    session_service
+        .expect_logout()
+        .never();
+
+    assert_eq!(StatusCode::UNAUTHORIZED, delete_sessions(Extension(session_service), Extension(CookieConfig::default()), CookieJar::new()).await.err().unwrap());
+}
+
+#[tokio::test]
+async fn delete_all_sessions_unknown_error() {
+    let mut session_service = MockSessionService::new();
+
+    session_service
+        .expect_logout_all()
+        .with(predicate::eq(mock_user().id))
+        .times(1)
+        .returning(|_| Err(LogoutError::Unknown));
+
+    assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, delete_all_sessions(Extension(session_service), Extension(CookieConfig::default()), mock_user(), mock_jar()).await.err().unwrap());
+}
+
+#[tokio::test]
+async fn delete_session_missing() {
+    let mut session_service = MockSessionService::new();
+
+    session_service
+        .expect_revoke()
+        .with(predicate::eq(mock_user().id), predicate::eq(7))
         .times(1)
-        .returning(|_| Err(LoginError::Unknown));
+        .returning(|_, _| Err(SessionRevokeError::Missing));
 
-    assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, post_sessions(Extension(session_service), CookieJar::new(), Json(mock_credentials())).await.err().unwrap())
+    assert_eq!(StatusCode::NOT_FOUND, delete_session(Extension(session_service), mock_user(), Path(7)).await);
 }
@@ -0,0 +1,47 @@
+use std::fmt::Debug;
+
+use axum::{Extension, Json, extract::Path};
+use hyper::StatusCode;
+use tracing::info;
+
+use crate::{domain::{tokens::{ApiToken, CreatedApiToken, TokenRequest}, users::User}, service::tokens::{TokenService, TokenCreateError, TokenListError, TokenRevokeError}, validation::ValidatedJson};
+
+#[tracing::instrument(skip_all, fields(user_id = user.id, name = request.name))]
+pub async fn post_tokens<T: TokenService + Debug>(
+    Extension(service): Extension<T>,
+    user: User,
+    ValidatedJson(request): ValidatedJson<TokenRequest>
+) -> Result<(StatusCode, Json<CreatedApiToken>), StatusCode> {
+    info!("Received API token creation attempt");
+    match service.create(user.id, request).await {
+        Ok(token) => Ok((StatusCode::CREATED, Json(token))),
+        Err(TokenCreateError::Unknown) => Err(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+}
+
+#[tracing::instrument(skip_all, fields(user_id = user.id))]
+pub async fn get_tokens<T: TokenService + Debug>(
+    Extension(service): Extension<T>,
+    user: User
+) -> Result<Json<Vec<ApiToken>>, StatusCode> {
+    match service.list(user.id).await {
+        Ok(tokens) => Ok(Json(tokens)),
+        Err(TokenListError::Unknown) => Err(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+}
+
+#[tracing::instrument(skip(service, user), fields(user_id = user.id))]
+pub async fn delete_token<T: TokenService + Debug>(
+    Extension(service): Extension<T>,
+    user: User,
+    Path(token_id): Path<i32>
+) -> StatusCode {
+    match service.revoke(user.id, token_id).await {
+        Ok(()) => StatusCode::NO_CONTENT,
+        Err(TokenRevokeError::Missing) => StatusCode::NOT_FOUND,
+        Err(TokenRevokeError::Unknown) => StatusCode::INTERNAL_SERVER_ERROR
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,100 @@
+use mockall::predicate;
+
+use crate::{service::tokens::MockTokenService, domain::tokens::{TokenScope, TokenScopes}};
+
+use super::*;
+
+fn mock_user() -> User {
+    User {
+        id: 1,
+        email: String::from("email"),
+        password_hash: String::from("password_hash")
+    }
+}
+
+fn mock_request() -> TokenRequest {
+    TokenRequest {
+        name: String::from("ci"),
+        scopes: vec![TokenScope::Compile],
+        expires_in_days: None
+    }
+}
+
+fn mock_token() -> ApiToken {
+    ApiToken {
+        id: 5,
+        name: String::from("ci"),
+        scopes: TokenScopes(vec![TokenScope::Compile]),
+        created_at: 0,
+        expires: None,
+        last_used: None
+    }
+}
+
+#[tokio::test]
+async fn post_tokens_normal() {
+    let mut token_service = MockTokenService::new();
+    let expected = CreatedApiToken { token: mock_token(), secret: String::from("agartex_secret") };
+    let created = expected.clone();
+
+    token_service
+        .expect_create()
+        .with(predicate::eq(mock_user().id), predicate::eq(mock_request()))
+        .times(1)
+        .returning(move |_, _| Ok(created.clone()));
+
+    let (status, Json(token)) = post_tokens(Extension(token_service), mock_user(), ValidatedJson(mock_request())).await.unwrap();
+    assert_eq!(StatusCode::CREATED, status);
+    assert_eq!(expected, token);
+}
+
+#[tokio::test]
+async fn post_tokens_unknown_error() {
+    let mut token_service = MockTokenService::new();
+
+    token_service
+        .expect_create()
+        .times(1)
+        .returning(|_, _| Err(TokenCreateError::Unknown));
+
+    assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, post_tokens(Extension(token_service), mock_user(), ValidatedJson(mock_request())).await.err().unwrap());
+}
+
+#[tokio::test]
+async fn get_tokens_normal() {
+    let mut token_service = MockTokenService::new();
+
+    token_service
+        .expect_list()
+        .with(predicate::eq(mock_user().id))
+        .times(1)
+        .returning(|_| Ok(vec![mock_token()]));
+
+    let Json(tokens) = get_tokens(Extension(token_service), mock_user()).await.unwrap();
+    assert_eq!(vec![mock_token()], tokens);
+}
+
+#[tokio::test]
+async fn delete_token_normal() {
+    let mut token_service = MockTokenService::new();
+
+    token_service
+        .expect_revoke()
+        .with(predicate::eq(mock_user().id), predicate::eq(5))
+        .times(1)
+        .returning(|_, _| Ok(()));
+
+    assert_eq!(StatusCode::NO_CONTENT, delete_token(Extension(token_service), mock_user(), Path(5)).await);
+}
+
+#[tokio::test]
+async fn delete_token_missing() {
+    let mut token_service = MockTokenService::new();
+
+    token_service
+        .expect_revoke()
+        .times(1)
+        .returning(|_, _| Err(TokenRevokeError::Missing));
+
+    assert_eq!(StatusCode::NOT_FOUND, delete_token(Extension(token_service), mock_user(), Path(5)).await);
+}
@@ -0,0 +1,100 @@
+use std::task::{Context, Poll};
+
+use axum::{http::Request, body::{Body, BoxBody}, response::Response};
+use axum_extra::extract::CookieJar;
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use futures::future::BoxFuture;
+use http::{HeaderMap, Method, StatusCode};
+use sha2::{Digest, Sha256};
+use tower::{Layer, Service};
+use tracing::warn;
+
+use crate::{auth::CookieConfig, constants};
+
+// Derived from the session token, so it needs no storage and changes with every login
+pub fn token(session_id: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(constants::CSRF_TOKEN_CONTEXT);
+    hasher.update(session_id.as_bytes());
+    URL_SAFE_NO_PAD.encode(hasher.finalize())
+}
+
+pub fn is_allowed(method: &Method, headers: &HeaderMap, cookies: &CookieConfig) -> bool {
+    if matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS) {
+        return true;
+    }
+
+    // Without a session cookie the browser adds no credentials, so there is nothing to forge
+    let jar = CookieJar::from_headers(headers);
+    let session_id = match jar.get(&cookies.session_name()) {
+        Some(cookie) => cookie.value(),
+        None => return true
+    };
+
+    match headers.get(&*constants::CSRF_HEADER).and_then(|value| value.to_str().ok()) {
+        Some(received) => constant_time_eq(received.as_bytes(), token(session_id).as_bytes()),
+        None => false
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |difference, (x, y)| difference | (x ^ y)) == 0
+}
+
+#[derive(Clone)]
+pub struct CsrfLayer {
+    cookies: CookieConfig
+}
+
+impl CsrfLayer {
+    pub fn new(cookies: CookieConfig) -> Self {
+        Self { cookies }
+    }
+}
+
+impl<S> Layer<S> for CsrfLayer {
+    type Service = CsrfMiddleware<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        CsrfMiddleware {
+            inner,
+            cookies: self.cookies.clone()
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct CsrfMiddleware<S> {
+    inner: S,
+    cookies: CookieConfig
+}
+
+impl<S> Service<Request<Body>> for CsrfMiddleware<S>
+where
+    S: Service<Request<Body>, Response = Response> + Send + Clone + 'static,
+    S::Future: Send + 'static
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        if !is_allowed(request.method(), request.headers(), &self.cookies) {
+            warn!(method = %request.method(), uri = %request.uri(), "Rejected request without a valid CSRF token");
+            let response = Response::builder()
+                .status(StatusCode::FORBIDDEN)
+                .body(BoxBody::default())
+                .unwrap();
+            return Box::pin(async move { Ok(response) });
+        }
+
+        Box::pin(self.inner.call(request))
+    }
+}
+
+#[cfg(test)]
+mod tests;
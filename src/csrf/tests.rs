@@ -0,0 +1,74 @@
+use http::{HeaderValue, header::COOKIE};
+
+use super::*;
+
+fn mock_session_id() -> String {
+    String::from("session_id")
+}
+
+fn mock_headers(cookie: Option<&str>, token: Option<&str>) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    if let Some(cookie) = cookie {
+        headers.insert(COOKIE, HeaderValue::from_str(cookie).unwrap());
+    }
+    if let Some(token) = token {
+        headers.insert(&*constants::CSRF_HEADER, HeaderValue::from_str(token).unwrap());
+    }
+    headers
+}
+
+fn mock_session_cookie() -> String {
+    format!("{}={}", constants::SESSION_COOKIE_NAME, mock_session_id())
+}
+
+#[test]
+fn token_depends_on_session() {
+    assert_eq!(token(&mock_session_id()), token(&mock_session_id()));
+    assert_ne!(token(&mock_session_id()), token("other_session_id"));
+    assert_eq!(43, token(&mock_session_id()).len());
+}
+
+#[test]
+fn is_allowed_safe_method() {
+    let headers = mock_headers(Some(&mock_session_cookie()), None);
+
+    assert!(is_allowed(&Method::GET, &headers, &CookieConfig::default()));
+    assert!(is_allowed(&Method::HEAD, &headers, &CookieConfig::default()));
+}
+
+#[test]
+fn is_allowed_valid_token() {
+    let headers = mock_headers(Some(&mock_session_cookie()), Some(&token(&mock_session_id())));
+
+    assert!(is_allowed(&Method::POST, &headers, &CookieConfig::default()));
+}
+
+#[test]
+fn is_allowed_missing_token() {
+    let headers = mock_headers(Some(&mock_session_cookie()), None);
+
+    assert!(!is_allowed(&Method::DELETE, &headers, &CookieConfig::default()));
+}
+
+#[test]
+fn is_allowed_wrong_token() {
+    let headers = mock_headers(Some(&mock_session_cookie()), Some(&token("other_session_id")));
+
+    assert!(!is_allowed(&Method::PUT, &headers, &CookieConfig::default()));
+}
+
+#[test]
+fn is_allowed_without_session_cookie() {
+    let headers = mock_headers(None, None);
+
+    assert!(is_allowed(&Method::POST, &headers, &CookieConfig::default()));
+}
+
+#[test]
+fn is_allowed_host_prefix() {
+    let cookies = CookieConfig { host_prefix: true, ..CookieConfig::default() };
+    let prefixed = format!("{}{}", constants::HOST_COOKIE_PREFIX, mock_session_cookie());
+
+    assert!(!is_allowed(&Method::POST, &mock_headers(Some(&prefixed), None), &cookies));
+    assert!(is_allowed(&Method::POST, &mock_headers(Some(&prefixed), Some(&token(&mock_session_id()))), &cookies));
+}
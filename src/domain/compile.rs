@@ -0,0 +1,301 @@
+use std::{fmt::{self, Debug, Formatter}, path::{Path, PathBuf}};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+
+use super::files::is_relative_path;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Engine {
+    #[default]
+    Pdflatex,
+    Xelatex,
+    Lualatex
+}
+
+impl Engine {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pdflatex => "pdflatex",
+            Self::Xelatex => "xelatex",
+            Self::Lualatex => "lualatex"
+        }
+    }
+
+    pub fn latexmk_flag(&self) -> &'static str {
+        match self {
+            Self::Pdflatex => "-pdf",
+            Self::Xelatex => "-pdfxe",
+            Self::Lualatex => "-pdflua"
+        }
+    }
+
+    pub fn make4ht_flag(&self) -> Option<&'static str> {
+        match self {
+            Self::Pdflatex => None,
+            Self::Xelatex => Some("-x"),
+            Self::Lualatex => Some("-l")
+        }
+    }
+}
+
+impl TryFrom<String> for Engine {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        match name.as_str() {
+            "pdflatex" => Ok(Self::Pdflatex),
+            "xelatex" => Ok(Self::Xelatex),
+            "lualatex" => Ok(Self::Lualatex),
+            _ => Err(name)
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    Dvi,
+    Ps,
+    Synctex,
+    Log,
+    Html
+}
+
+impl OutputFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Dvi => "dvi",
+            Self::Ps => "ps",
+            Self::Synctex => "synctex",
+            Self::Log => "log",
+            Self::Html => "html"
+        }
+    }
+
+    pub fn latexmk_flag(&self) -> Option<&'static str> {
+        match self {
+            Self::Dvi => Some("-dvi"),
+            Self::Ps => Some("-ps"),
+            Self::Synctex => Some("-synctex=1"),
+            Self::Log | Self::Html => None
+        }
+    }
+}
+
+impl TryFrom<String> for OutputFormat {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        match name.as_str() {
+            "dvi" => Ok(Self::Dvi),
+            "ps" => Ok(Self::Ps),
+            "synctex" => Ok(Self::Synctex),
+            "log" => Ok(Self::Log),
+            "html" => Ok(Self::Html),
+            _ => Err(name)
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate, PartialEq, Clone)]
+pub struct CompileOptions {
+    #[validate(length(min = 1, max = 256), custom = "is_relative_path")]
+    pub entrypoint: Option<String>,
+    #[serde(default)]
+    pub force: bool,
+    pub text: Option<String>,
+    pub engine: Option<Engine>,
+    #[serde(default)]
+    pub formats: Vec<OutputFormat>
+}
+
+#[derive(Clone, PartialEq)]
+pub struct SourceFile {
+    pub path: String,
+    pub content: Vec<u8>
+}
+
+impl Debug for SourceFile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SourceFile")
+            .field("path", &self.path)
+            .field("size", &self.content.len())
+            .finish()
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Info
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub message: String,
+    pub package: Option<String>,
+    pub context: Vec<String>
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct PdfBox {
+    pub page: u32,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SourceLocation {
+    pub file: String,
+    pub line: u32,
+    pub column: Option<u32>
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureKind {
+    Compilation,
+    Timeout,
+    ResourceLimit
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CompileFailure {
+    pub kind: FailureKind,
+    pub message: String,
+    pub diagnostics: Vec<Diagnostic>
+}
+
+pub type JobId = Uuid;
+
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct JobOptions {
+    pub engine: Engine,
+    pub formats: Vec<OutputFormat>
+}
+
+impl JobOptions {
+    // Formats are kept sorted, so that the same request always produces the same build
+    pub fn new(engine: Engine, mut formats: Vec<OutputFormat>) -> Self {
+        formats.sort();
+        formats.dedup();
+        Self { engine, formats }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CompileJob {
+    pub id: JobId,
+    pub owner_id: Option<i32>,
+    #[serde(flatten)]
+    pub options: JobOptions,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    #[serde(skip)]
+    pub dir: PathBuf
+}
+
+impl CompileJob {
+    pub fn new(owner_id: Option<i32>, options: JobOptions, jobs_dir: &Path) -> Self {
+        let id = Uuid::new_v4();
+        Self {
+            id,
+            owner_id,
+            options,
+            created_at: Utc::now(),
+            started_at: None,
+            finished_at: None,
+            dir: jobs_dir.join(id.to_string())
+        }
+    }
+
+    pub fn source_dir(&self) -> PathBuf {
+        self.dir.join("src")
+    }
+
+    pub fn output_dir(&self) -> PathBuf {
+        self.dir.join("out")
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct SubmittedJob {
+    pub id: JobId
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Succeeded {
+        diagnostics: Vec<Diagnostic>,
+        artifacts: Vec<String>
+    },
+    Failed(CompileFailure)
+}
+
+impl JobStatus {
+    pub fn is_finished(&self) -> bool {
+        matches!(self, Self::Succeeded { .. } | Self::Failed(_))
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogStream {
+    Stdout,
+    Stderr
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "event", rename_all = "lowercase")]
+pub enum JobEvent {
+    Queued,
+    Started,
+    Pass {
+        number: u32,
+        rule: String
+    },
+    Warning {
+        message: String
+    },
+    Log {
+        stream: LogStream,
+        line: String
+    },
+    Finished(JobStatus)
+}
+
+impl JobEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Queued => "queued",
+            Self::Started => "started",
+            Self::Pass { .. } => "pass",
+            Self::Warning { .. } => "warning",
+            Self::Log { .. } => "log",
+            Self::Finished(_) => "finished"
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct JobReport {
+    #[serde(flatten)]
+    pub job: CompileJob,
+    #[serde(flatten)]
+    pub status: JobStatus
+}
@@ -0,0 +1,21 @@
+use std::path::{Path, Component};
+
+use serde::Serialize;
+use validator::ValidationError;
+
+#[derive(sqlx::FromRow, Serialize, Debug, Clone, PartialEq)]
+pub struct ProjectFile {
+    pub path: String,
+    pub size: i64
+}
+
+pub fn is_relative_path(path: &str) -> Result<(), ValidationError> {
+    let is_relative = !path.is_empty() && Path::new(path)
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)));
+
+    if !is_relative {
+        return Err(ValidationError::new("relative_path"))
+    }
+    Ok(())
+}
@@ -1,2 +1,6 @@
 pub mod users;
 pub mod sessions;
+pub mod compile;
+pub mod projects;
+pub mod files;
+pub mod tokens;
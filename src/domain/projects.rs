@@ -0,0 +1,24 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use super::compile::Engine;
+
+#[derive(sqlx::FromRow, Serialize, Debug, Clone, PartialEq)]
+pub struct Project {
+    #[sqlx(rename = "project_id")]
+    pub id: i32,
+    pub owner_id: i32,
+    pub name: String,
+    #[sqlx(try_from = "String")]
+    pub engine: Engine,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime
+}
+
+#[derive(Debug, Deserialize, Validate, PartialEq, Clone)]
+pub struct ProjectMetadata {
+    #[validate(length(min = 1, max = 128))]
+    pub name: String,
+    pub engine: Option<Engine>
+}
@@ -1,12 +1,43 @@
+use serde::Serialize;
 use sqlx;
 
 use super::users::User;
 
+#[derive(sqlx::FromRow, Serialize, Debug, Clone, Default, PartialEq)]
+pub struct ClientInfo {
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>
+}
+
 #[derive(sqlx::FromRow, Debug, Clone, PartialEq)]
 pub struct Session {
     #[sqlx(rename = "session_id")]
     pub id: String,
     #[sqlx(flatten)]
     pub user: User,
-    pub expires: i64
+    pub expires: i64,
+    pub created_at: i64,
+    pub last_seen: i64,
+    #[sqlx(flatten)]
+    pub client: ClientInfo
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VerifiedSession {
+    pub user: User,
+    pub expires: i64,
+    pub extended: bool
+}
+
+#[derive(sqlx::FromRow, Serialize, Debug, Clone, PartialEq)]
+pub struct SessionInfo {
+    #[sqlx(rename = "public_id")]
+    pub id: i32,
+    pub created_at: i64,
+    pub last_seen: i64,
+    pub expires: i64,
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub client: ClientInfo,
+    pub current: bool
 }
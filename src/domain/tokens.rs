@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use super::users::User;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenScope {
+    Read,
+    Compile,
+    Write
+}
+
+impl TokenScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Read => "read",
+            Self::Compile => "compile",
+            Self::Write => "write"
+        }
+    }
+
+    // Every scope includes the ones below it, so a write token can also compile and read
+    pub fn includes(&self, scope: TokenScope) -> bool {
+        match self {
+            Self::Write => true,
+            Self::Compile => scope != Self::Write,
+            Self::Read => scope == Self::Read
+        }
+    }
+}
+
+impl TryFrom<String> for TokenScope {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        match name.as_str() {
+            "read" => Ok(Self::Read),
+            "compile" => Ok(Self::Compile),
+            "write" => Ok(Self::Write),
+            _ => Err(name)
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Default)]
+#[serde(transparent)]
+pub struct TokenScopes(pub Vec<TokenScope>);
+
+impl TokenScopes {
+    pub fn grants(&self, scope: TokenScope) -> bool {
+        self.0.iter().any(|granted| granted.includes(scope))
+    }
+
+    pub fn as_string(&self) -> String {
+        self.0.iter()
+            .map(TokenScope::as_str)
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+// Scopes are stored as a single comma separated column
+impl TryFrom<String> for TokenScopes {
+    type Error = String;
+
+    fn try_from(names: String) -> Result<Self, Self::Error> {
+        names.split(',')
+            .filter(|name| !name.is_empty())
+            .map(|name| TokenScope::try_from(name.to_owned()))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Self)
+    }
+}
+
+#[derive(sqlx::FromRow, Serialize, Debug, Clone, PartialEq)]
+pub struct ApiToken {
+    #[sqlx(rename = "token_id")]
+    pub id: i32,
+    pub name: String,
+    #[sqlx(try_from = "String")]
+    pub scopes: TokenScopes,
+    pub created_at: i64,
+    pub expires: Option<i64>,
+    pub last_used: Option<i64>
+}
+
+#[derive(sqlx::FromRow, Debug, Clone, PartialEq)]
+pub struct TokenOwner {
+    #[sqlx(flatten)]
+    pub user: User,
+    #[sqlx(flatten)]
+    pub token: ApiToken
+}
+
+// The secret is only ever shown once, right after creation
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CreatedApiToken {
+    #[serde(flatten)]
+    pub token: ApiToken,
+    pub secret: String
+}
+
+#[derive(Debug, Deserialize, Validate, PartialEq, Clone)]
+pub struct TokenRequest {
+    #[validate(length(min = 1, max = 64))]
+    pub name: String,
+    #[validate(length(min = 1))]
+    pub scopes: Vec<TokenScope>,
+    #[validate(range(min = 1, max = 365))]
+    pub expires_in_days: Option<u32>
+}
@@ -1,5 +1,9 @@
+use std::{env, net::SocketAddr, time::Duration};
+
 use database::create_conn_pool;
+use repository::sessions::PgSessionRepository;
 use routing::get_main_router;
+use service::sessions::purge::SessionPurger;
 use tracing::{error, info};
 
 // declare child modules
@@ -11,6 +15,7 @@ mod domain;
 mod service;
 mod repository;
 mod auth;
+mod csrf;
 mod validation;
 
 #[tracing::instrument]
@@ -32,9 +37,30 @@ pub async fn run() -> anyhow::Result<()> {
         }
     };
 
+    // A zero interval would panic inside the purge task and silently stop purging
+    let purge_interval = match env::var(constants::SESSION_PURGE_INTERVAL_ENV_VAR) {
+        Ok(seconds) => match seconds.parse() {
+            Ok(seconds) if seconds > 0 => seconds,
+            _ => {
+                error!(seconds, "{} must be a positive number of seconds, using {}", constants::SESSION_PURGE_INTERVAL_ENV_VAR, constants::SESSION_PURGE_INTERVAL_SECONDS);
+                constants::SESSION_PURGE_INTERVAL_SECONDS
+            }
+        },
+        Err(_) => constants::SESSION_PURGE_INTERVAL_SECONDS
+    };
+    SessionPurger::new(PgSessionRepository::new(&pool)).spawn(Duration::from_secs(purge_interval));
+
+    let router = match get_main_router(&pool) {
+        Ok(router) => router,
+        Err(err) => {
+            error!("Could not set up the server:\n{:?}", err);
+            return Err(err)
+        }
+    };
+
     info!("Running server!");
     match axum::Server::try_bind(&constants::SERVER_URL)?
-        .serve(get_main_router(&pool).into_make_service())
+        .serve(router.into_make_service_with_connect_info::<SocketAddr>())
         .await {
         Ok(_) => Ok(()),
         Err(err) => Err(anyhow::Error::from(err))
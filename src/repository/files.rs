@@ -0,0 +1,285 @@
+use std::{path::{PathBuf, Path}, io, fs};
+
+use axum::async_trait;
+use mockall::automock;
+use sqlx::PgPool;
+use tracing::error;
+
+use crate::domain::files::{ProjectFile, is_relative_path};
+
+pub enum FileGetError {
+    Missing,
+    Unknown
+}
+
+pub enum FileWriteError {
+    Unknown
+}
+
+pub enum FileDeleteError {
+    Missing,
+    Unknown
+}
+
+#[automock]
+#[async_trait]
+pub trait FileRepository {
+    async fn list(&self, project_id: i32) -> Result<Vec<ProjectFile>, FileGetError>;
+    async fn read(&self, project_id: i32, path: &str) -> Result<Vec<u8>, FileGetError>;
+    async fn write(&self, project_id: i32, path: &str, content: &[u8]) -> Result<(), FileWriteError>;
+    async fn delete(&self, project_id: i32, path: &str) -> Result<(), FileDeleteError>;
+    async fn delete_all(&self, project_id: i32) -> Result<(), FileDeleteError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct FsFileRepository {
+    pub root: PathBuf
+}
+
+impl FsFileRepository {
+    pub fn new(root: &Path) -> Self {
+        Self { root: root.to_path_buf() }
+    }
+
+    fn project_dir(&self, project_id: i32) -> PathBuf {
+        self.root.join(project_id.to_string())
+    }
+
+    fn resolve(&self, project_id: i32, path: &str) -> Option<PathBuf> {
+        is_relative_path(path).ok()?;
+        Some(self.project_dir(project_id).join(path))
+    }
+}
+
+pub fn list_dir(dir: &Path) -> io::Result<Vec<ProjectFile>> {
+    let mut files = Vec::new();
+    walk(dir, dir, &mut files)?;
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(files)
+}
+
+fn walk(base: &Path, dir: &Path, files: &mut Vec<ProjectFile>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+            walk(base, &entry.path(), files)?;
+        } else {
+            let path = entry.path();
+            let relative = path.strip_prefix(base).unwrap();
+            files.push(ProjectFile {
+                path: relative.components()
+                    .map(|component| component.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/"),
+                size: metadata.len() as i64
+            });
+        }
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl FileRepository for FsFileRepository {
+    #[tracing::instrument(skip(self))]
+    async fn list(&self, project_id: i32) -> Result<Vec<ProjectFile>, FileGetError> {
+        match list_dir(&self.project_dir(project_id)) {
+            Ok(files) => Ok(files),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(err) => {
+                error!(%err);
+                Err(FileGetError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn read(&self, project_id: i32, path: &str) -> Result<Vec<u8>, FileGetError> {
+        let full_path = self.resolve(project_id, path).ok_or(FileGetError::Missing)?;
+        match tokio::fs::read(full_path).await {
+            Ok(content) => Ok(content),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Err(FileGetError::Missing),
+            Err(err) => {
+                error!(%err);
+                Err(FileGetError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self, content))]
+    async fn write(&self, project_id: i32, path: &str, content: &[u8]) -> Result<(), FileWriteError> {
+        let full_path = self.resolve(project_id, path).ok_or(FileWriteError::Unknown)?;
+        if let Some(parent) = full_path.parent() {
+            if let Err(err) = tokio::fs::create_dir_all(parent).await {
+                error!(%err);
+                return Err(FileWriteError::Unknown);
+            }
+        }
+        match tokio::fs::write(full_path, content).await {
+            Ok(()) => Ok(()),
+            Err(err) => {
+                error!(%err);
+                Err(FileWriteError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn delete(&self, project_id: i32, path: &str) -> Result<(), FileDeleteError> {
+        let full_path = self.resolve(project_id, path).ok_or(FileDeleteError::Missing)?;
+        match tokio::fs::remove_file(full_path).await {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Err(FileDeleteError::Missing),
+            Err(err) => {
+                error!(%err);
+                Err(FileDeleteError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn delete_all(&self, project_id: i32) -> Result<(), FileDeleteError> {
+        match tokio::fs::remove_dir_all(self.project_dir(project_id)).await {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(err) => {
+                error!(%err);
+                Err(FileDeleteError::Unknown)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PgFileRepository {
+    pub pool: PgPool
+}
+
+impl PgFileRepository {
+    pub fn new(pool: &PgPool) -> Self {
+        Self { pool: pool.clone() }
+    }
+}
+
+#[async_trait]
+impl FileRepository for PgFileRepository {
+    #[tracing::instrument(skip(self))]
+    async fn list(&self, project_id: i32) -> Result<Vec<ProjectFile>, FileGetError> {
+        let result = sqlx::query_as::<_, ProjectFile>("SELECT path, size FROM files WHERE project_id = $1 ORDER BY path")
+            .bind(project_id)
+            .fetch_all(&self.pool)
+            .await;
+
+        match result {
+            Ok(files) => Ok(files),
+            Err(err) => {
+                error!(%err);
+                Err(FileGetError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn read(&self, project_id: i32, path: &str) -> Result<Vec<u8>, FileGetError> {
+        let result = sqlx::query_as::<_, (Vec<u8>,)>("SELECT lo_get(content_oid) FROM files WHERE project_id = $1 AND path = $2")
+            .bind(project_id)
+            .bind(path)
+            .fetch_optional(&self.pool)
+            .await;
+
+        match result {
+            Ok(Some((content,))) => Ok(content),
+            Ok(None) => Err(FileGetError::Missing),
+            Err(err) => {
+                error!(%err);
+                Err(FileGetError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self, content))]
+    async fn write(&self, project_id: i32, path: &str, content: &[u8]) -> Result<(), FileWriteError> {
+        if is_relative_path(path).is_err() {
+            error!("Refusing to store a file outside the project directory");
+            return Err(FileWriteError::Unknown);
+        }
+
+        let result: Result<(), sqlx::Error> = async {
+            let mut transaction = self.pool.begin().await?;
+
+            sqlx::query("SELECT lo_unlink(content_oid) FROM files WHERE project_id = $1 AND path = $2")
+                .bind(project_id)
+                .bind(path)
+                .execute(&mut transaction)
+                .await?;
+
+            sqlx::query("
+                INSERT INTO files (project_id, path, content_oid, size)
+                VALUES ($1, $2, lo_from_bytea(0, $3), $4)
+                ON CONFLICT (project_id, path) DO UPDATE
+                SET content_oid = EXCLUDED.content_oid, size = EXCLUDED.size, updated_at = NOW()
+            ")
+                .bind(project_id)
+                .bind(path)
+                .bind(content)
+                .bind(content.len() as i64)
+                .execute(&mut transaction)
+                .await?;
+
+            transaction.commit().await
+        }.await;
+
+        match result {
+            Ok(()) => Ok(()),
+            Err(err) => {
+                error!(%err);
+                Err(FileWriteError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn delete(&self, project_id: i32, path: &str) -> Result<(), FileDeleteError> {
+        let result = sqlx::query("
+            WITH deleted AS (
+                DELETE FROM files WHERE project_id = $1 AND path = $2 RETURNING content_oid
+            )
+            SELECT lo_unlink(content_oid) FROM deleted
+        ")
+            .bind(project_id)
+            .bind(path)
+            .fetch_all(&self.pool)
+            .await;
+
+        match result {
+            Ok(rows) => {
+                if !rows.is_empty() { Ok(()) } else { Err(FileDeleteError::Missing) }
+            },
+            Err(err) => {
+                error!(%err);
+                Err(FileDeleteError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn delete_all(&self, project_id: i32) -> Result<(), FileDeleteError> {
+        let result = sqlx::query("
+            WITH deleted AS (
+                DELETE FROM files WHERE project_id = $1 RETURNING content_oid
+            )
+            SELECT lo_unlink(content_oid) FROM deleted
+        ")
+            .bind(project_id)
+            .fetch_all(&self.pool)
+            .await;
+
+        match result {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                error!(%err);
+                Err(FileDeleteError::Unknown)
+            }
+        }
+    }
+}
@@ -1,2 +1,12 @@
+use sha2::{Digest, Sha256};
+
 pub mod users;
 pub mod sessions;
+pub mod projects;
+pub mod files;
+pub mod tokens;
+
+// Only the digest of a secret is stored, so a leaked table does not hand out live credentials
+pub fn digest(secret: &str) -> String {
+    format!("{:x}", Sha256::digest(secret.as_bytes()))
+}
@@ -0,0 +1,146 @@
+use axum::async_trait;
+use mockall::automock;
+use sqlx::PgPool;
+use tracing::error;
+
+use crate::domain::projects::{Project, ProjectMetadata};
+
+pub enum ProjectGetError {
+    Missing,
+    Unknown
+}
+
+pub enum ProjectInsertError {
+    Unknown
+}
+
+pub enum ProjectUpdateError {
+    Missing,
+    Unknown
+}
+
+pub enum ProjectDeleteError {
+    Missing,
+    Unknown
+}
+
+#[automock]
+#[async_trait]
+pub trait ProjectRepository {
+    async fn get(&self, id: i32) -> Result<Project, ProjectGetError>;
+    async fn get_by_owner(&self, owner_id: i32) -> Result<Vec<Project>, ProjectGetError>;
+    async fn insert(&self, owner_id: i32, metadata: &ProjectMetadata) -> Result<Project, ProjectInsertError>;
+    async fn update(&self, id: i32, metadata: &ProjectMetadata) -> Result<Project, ProjectUpdateError>;
+    async fn delete(&self, id: i32) -> Result<(), ProjectDeleteError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct PgProjectRepository {
+    pub pool: PgPool
+}
+
+impl PgProjectRepository {
+    pub fn new(pool: &PgPool) -> Self {
+        Self { pool: pool.clone() }
+    }
+}
+
+#[async_trait]
+impl ProjectRepository for PgProjectRepository {
+    #[tracing::instrument(skip(self))]
+    async fn get(&self, id: i32) -> Result<Project, ProjectGetError> {
+        let result = sqlx::query_as::<_, Project>("SELECT * FROM projects WHERE project_id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await;
+
+        match result {
+            Ok(Some(project)) => Ok(project),
+            Ok(None) => Err(ProjectGetError::Missing),
+            Err(err) => {
+                error!(%err);
+                Err(ProjectGetError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn get_by_owner(&self, owner_id: i32) -> Result<Vec<Project>, ProjectGetError> {
+        let result = sqlx::query_as::<_, Project>("SELECT * FROM projects WHERE owner_id = $1 ORDER BY project_id")
+            .bind(owner_id)
+            .fetch_all(&self.pool)
+            .await;
+
+        match result {
+            Ok(projects) => Ok(projects),
+            Err(err) => {
+                error!(%err);
+                Err(ProjectGetError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self, metadata), fields(name = metadata.name))]
+    async fn insert(&self, owner_id: i32, metadata: &ProjectMetadata) -> Result<Project, ProjectInsertError> {
+        let result = sqlx::query_as::<_, Project>("
+            INSERT INTO projects (owner_id, name, engine)
+            VALUES ($1, $2, COALESCE($3, 'pdflatex'))
+            RETURNING *
+        ")
+            .bind(owner_id)
+            .bind(&metadata.name)
+            .bind(metadata.engine.map(|engine| engine.as_str()))
+            .fetch_one(&self.pool)
+            .await;
+
+        match result {
+            Ok(project) => Ok(project),
+            Err(err) => {
+                error!(%err);
+                Err(ProjectInsertError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self, metadata), fields(name = metadata.name))]
+    async fn update(&self, id: i32, metadata: &ProjectMetadata) -> Result<Project, ProjectUpdateError> {
+        let result = sqlx::query_as::<_, Project>("
+            UPDATE projects
+            SET name = $2, engine = COALESCE($3, engine), updated_at = NOW()
+            WHERE project_id = $1
+            RETURNING *
+        ")
+            .bind(id)
+            .bind(&metadata.name)
+            .bind(metadata.engine.map(|engine| engine.as_str()))
+            .fetch_optional(&self.pool)
+            .await;
+
+        match result {
+            Ok(Some(project)) => Ok(project),
+            Ok(None) => Err(ProjectUpdateError::Missing),
+            Err(err) => {
+                error!(%err);
+                Err(ProjectUpdateError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn delete(&self, id: i32) -> Result<(), ProjectDeleteError> {
+        let result = sqlx::query("DELETE FROM projects WHERE project_id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await;
+
+        match result {
+            Ok(result) => {
+                if result.rows_affected() > 0 { Ok(()) } else { Err(ProjectDeleteError::Missing) }
+            },
+            Err(err) => {
+                error!(%err);
+                Err(ProjectDeleteError::Unknown)
+            }
+        }
+    }
+}
@@ -3,7 +3,9 @@ use mockall::automock;
 use sqlx::PgPool;
 use tracing::error;
 
-use crate::domain::sessions::Session;
+use crate::domain::sessions::{Session, SessionInfo};
+
+use super::digest;
 
 pub enum SessionGetError {
     Missing,
@@ -11,6 +13,7 @@ pub enum SessionGetError {
 }
 
 pub enum SessionDeleteError {
+    Missing,
     Unknown
 }
 
@@ -18,12 +21,21 @@ pub enum SessionInsertError {
     Unknown
 }
 
+pub enum SessionUpdateError {
+    Unknown
+}
+
 #[automock]
 #[async_trait]
 pub trait SessionRepository {
     async fn insert(&self, session: &Session) -> Result<(), SessionInsertError>;
     async fn get(&self, id: &str) -> Result<Session, SessionGetError>;
+    async fn touch(&self, id: &str, last_seen: i64, expires: i64) -> Result<(), SessionUpdateError>;
+    async fn list(&self, user_id: i32, current_id: &str) -> Result<Vec<SessionInfo>, SessionGetError>;
     async fn delete(&self, id: &str) -> Result<(), SessionDeleteError>;
+    async fn delete_by_public_id(&self, user_id: i32, public_id: i32) -> Result<(), SessionDeleteError>;
+    async fn delete_all(&self, user_id: i32) -> Result<u64, SessionDeleteError>;
+    async fn delete_expired(&self, now: i64) -> Result<u64, SessionDeleteError>;
 }
 
 #[derive(Debug, Clone)]
@@ -39,12 +51,19 @@ impl PgSessionRepository {
 
 #[async_trait]
 impl SessionRepository for PgSessionRepository {
-    #[tracing::instrument(skip_all, fields(id = session.id))]
+    #[tracing::instrument(skip_all, fields(user_id = session.user.id))]
     async fn insert(&self, session: &Session) -> Result<(), SessionInsertError> {
-        match sqlx::query("INSERT INTO sessions (session_id, user_id, expires) VALUES ($1, $2, $3)")
-            .bind(&session.id)
+        match sqlx::query("
+            INSERT INTO sessions (session_id, user_id, expires, created_at, last_seen, ip_address, user_agent)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+        ")
+            .bind(digest(&session.id))
             .bind(session.user.id)
             .bind(session.expires)
+            .bind(session.created_at)
+            .bind(session.last_seen)
+            .bind(&session.client.ip_address)
+            .bind(&session.client.user_agent)
             .execute(&self.pool)
             .await {
                 Ok(_) => Ok(()),
@@ -55,20 +74,20 @@ impl SessionRepository for PgSessionRepository {
             }
     }
 
-    #[tracing::instrument(skip(self))]
+    #[tracing::instrument(skip_all)]
     async fn get(&self, id: &str) -> Result<Session, SessionGetError> {
         let session = sqlx::query_as::<_, Session>("
-            SELECT session_id, users.user_id, expires, email, password_hash
+            SELECT session_id, users.user_id, expires, created_at, last_seen, ip_address, user_agent, email, password_hash
             FROM sessions JOIN users
             ON sessions.user_id = users.user_id
             WHERE sessions.session_id = $1
         ")
-            .bind(id)
+            .bind(digest(id))
             .fetch_optional(&self.pool)
             .await;
     
         match session {
-            Ok(Some(session)) => Ok(session),
+            Ok(Some(session)) => Ok(Session { id: id.to_owned(), ..session }),
             Ok(None) => return Err(SessionGetError::Missing),
             Err(err) => {
                 error!(%err);
@@ -77,10 +96,49 @@ impl SessionRepository for PgSessionRepository {
         }
     }
 
-    #[tracing::instrument(skip(self))]
+    #[tracing::instrument(skip(self, id))]
+    async fn touch(&self, id: &str, last_seen: i64, expires: i64) -> Result<(), SessionUpdateError> {
+        match sqlx::query("UPDATE sessions SET last_seen = $2, expires = $3 WHERE session_id = $1")
+            .bind(digest(id))
+            .bind(last_seen)
+            .bind(expires)
+            .execute(&self.pool)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                error!(%err);
+                Err(SessionUpdateError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self, current_id))]
+    async fn list(&self, user_id: i32, current_id: &str) -> Result<Vec<SessionInfo>, SessionGetError> {
+        let sessions = sqlx::query_as::<_, SessionInfo>("
+            SELECT public_id, created_at, last_seen, expires, ip_address, user_agent, session_id = $2 AS current
+            FROM sessions
+            WHERE user_id = $1 AND expires > EXTRACT(EPOCH FROM NOW())
+            ORDER BY last_seen DESC
+        ")
+            .bind(user_id)
+            .bind(digest(current_id))
+            .fetch_all(&self.pool)
+            .await;
+
+        match sessions {
+            Ok(sessions) => Ok(sessions),
+            Err(err) => {
+                error!(%err);
+                Err(SessionGetError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip_all)]
     async fn delete(&self, id: &str) -> Result<(), SessionDeleteError> {
-        match sqlx::query("DELETE FROM sessions WHERE id = $1")
-            .bind(id)
+        match sqlx::query("DELETE FROM sessions WHERE session_id = $1")
+            .bind(digest(id))
             .execute(&self.pool)
             .await
         {
@@ -91,4 +149,51 @@ impl SessionRepository for PgSessionRepository {
             }
         }
     }
+
+    #[tracing::instrument(skip(self))]
+    async fn delete_by_public_id(&self, user_id: i32, public_id: i32) -> Result<(), SessionDeleteError> {
+        match sqlx::query("DELETE FROM sessions WHERE user_id = $1 AND public_id = $2")
+            .bind(user_id)
+            .bind(public_id)
+            .execute(&self.pool)
+            .await
+        {
+            Ok(result) if result.rows_affected() == 0 => Err(SessionDeleteError::Missing),
+            Ok(_) => Ok(()),
+            Err(err) => {
+                error!(%err);
+                Err(SessionDeleteError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn delete_all(&self, user_id: i32) -> Result<u64, SessionDeleteError> {
+        match sqlx::query("DELETE FROM sessions WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&self.pool)
+            .await
+        {
+            Ok(result) => Ok(result.rows_affected()),
+            Err(err) => {
+                error!(%err);
+                Err(SessionDeleteError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn delete_expired(&self, now: i64) -> Result<u64, SessionDeleteError> {
+        match sqlx::query("DELETE FROM sessions WHERE expires < $1")
+            .bind(now)
+            .execute(&self.pool)
+            .await
+        {
+            Ok(result) => Ok(result.rows_affected()),
+            Err(err) => {
+                error!(%err);
+                Err(SessionDeleteError::Unknown)
+            }
+        }
+    }
 }
@@ -0,0 +1,150 @@
+use axum::async_trait;
+use mockall::automock;
+use sqlx::PgPool;
+use tracing::error;
+
+use crate::domain::tokens::{ApiToken, TokenOwner, TokenScopes};
+
+use super::digest;
+
+pub enum ApiTokenGetError {
+    Missing,
+    Unknown
+}
+
+pub enum ApiTokenInsertError {
+    Unknown
+}
+
+pub enum ApiTokenUpdateError {
+    Unknown
+}
+
+pub enum ApiTokenDeleteError {
+    Missing,
+    Unknown
+}
+
+#[automock]
+#[async_trait]
+pub trait ApiTokenRepository {
+    async fn insert(&self, user_id: i32, secret: &str, name: &str, scopes: &TokenScopes, expires: Option<i64>) -> Result<ApiToken, ApiTokenInsertError>;
+    async fn get(&self, secret: &str) -> Result<TokenOwner, ApiTokenGetError>;
+    async fn list(&self, user_id: i32) -> Result<Vec<ApiToken>, ApiTokenGetError>;
+    async fn touch(&self, id: i32, last_used: i64) -> Result<(), ApiTokenUpdateError>;
+    async fn delete(&self, user_id: i32, id: i32) -> Result<(), ApiTokenDeleteError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct PgApiTokenRepository {
+    pub pool: PgPool
+}
+
+impl PgApiTokenRepository {
+    pub fn new(pool: &PgPool) -> Self {
+        Self { pool: pool.clone() }
+    }
+}
+
+#[async_trait]
+impl ApiTokenRepository for PgApiTokenRepository {
+    #[tracing::instrument(skip(self, secret, scopes))]
+    async fn insert(&self, user_id: i32, secret: &str, name: &str, scopes: &TokenScopes, expires: Option<i64>) -> Result<ApiToken, ApiTokenInsertError> {
+        let result = sqlx::query_as::<_, ApiToken>("
+            INSERT INTO api_tokens (token_hash, user_id, name, scopes, expires)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING token_id, name, scopes, created_at, expires, last_used
+        ")
+            .bind(digest(secret))
+            .bind(user_id)
+            .bind(name)
+            .bind(scopes.as_string())
+            .bind(expires)
+            .fetch_one(&self.pool)
+            .await;
+
+        match result {
+            Ok(token) => Ok(token),
+            Err(err) => {
+                error!(%err);
+                Err(ApiTokenInsertError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn get(&self, secret: &str) -> Result<TokenOwner, ApiTokenGetError> {
+        let result = sqlx::query_as::<_, TokenOwner>("
+            SELECT token_id, name, scopes, api_tokens.created_at, expires, last_used, users.user_id, email, password_hash
+            FROM api_tokens JOIN users
+            ON api_tokens.user_id = users.user_id
+            WHERE api_tokens.token_hash = $1
+        ")
+            .bind(digest(secret))
+            .fetch_optional(&self.pool)
+            .await;
+
+        match result {
+            Ok(Some(owner)) => Ok(owner),
+            Ok(None) => Err(ApiTokenGetError::Missing),
+            Err(err) => {
+                error!(%err);
+                Err(ApiTokenGetError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn list(&self, user_id: i32) -> Result<Vec<ApiToken>, ApiTokenGetError> {
+        let result = sqlx::query_as::<_, ApiToken>("
+            SELECT token_id, name, scopes, created_at, expires, last_used
+            FROM api_tokens
+            WHERE user_id = $1
+            ORDER BY token_id
+        ")
+            .bind(user_id)
+            .fetch_all(&self.pool)
+            .await;
+
+        match result {
+            Ok(tokens) => Ok(tokens),
+            Err(err) => {
+                error!(%err);
+                Err(ApiTokenGetError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn touch(&self, id: i32, last_used: i64) -> Result<(), ApiTokenUpdateError> {
+        match sqlx::query("UPDATE api_tokens SET last_used = $2 WHERE token_id = $1")
+            .bind(id)
+            .bind(last_used)
+            .execute(&self.pool)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                error!(%err);
+                Err(ApiTokenUpdateError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn delete(&self, user_id: i32, id: i32) -> Result<(), ApiTokenDeleteError> {
+        match sqlx::query("DELETE FROM api_tokens WHERE user_id = $1 AND token_id = $2")
+            .bind(user_id)
+            .bind(id)
+            .execute(&self.pool)
+            .await
+        {
+            Ok(result) if result.rows_affected() == 0 => Err(ApiTokenDeleteError::Missing),
+            Ok(_) => Ok(()),
+            Err(err) => {
+                error!(%err);
+                Err(ApiTokenDeleteError::Unknown)
+            }
+        }
+    }
+}
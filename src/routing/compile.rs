@@ -1,13 +1,25 @@
-use axum::{Router, routing, Extension};
+use axum::{Router, routing, Extension, extract::DefaultBodyLimit};
+use sqlx::PgPool;
 
-use crate::{service::{compilation::SimpleCompilationService, execution::ProcessExecutionService}, control::compile};
+use crate::{service::compilation::queue::CompileQueue, control::compile, constants, csrf::CsrfLayer, auth::CookieConfig, domain::tokens::TokenScope};
 
-pub fn compile_router() -> Router {
-    let simple_compile_service = SimpleCompilationService::new(ProcessExecutionService {});
-    
-    let handler = routing::post(compile::post_compile::<SimpleCompilationService<ProcessExecutionService>>)
-        .layer(Extension(simple_compile_service));
+use super::auth_layer;
+
+pub fn compile_router(pool: &PgPool, cookies: CookieConfig, queue: CompileQueue) -> Router {
+    let auth = auth_layer(pool, cookies.clone()).with_write_scope(TokenScope::Compile);
+
+    let jobs = Router::new()
+        .route("/", routing::post(compile::post_job::<CompileQueue>))
+        .route("/:job_id", routing::get(compile::get_job::<CompileQueue>))
+        .route("/:job_id/pdf", routing::get(compile::get_job_pdf::<CompileQueue>))
+        .route("/:job_id/events", routing::get(compile::get_job_events::<CompileQueue>))
+        .route("/:job_id/artifacts/*name", routing::get(compile::get_job_artifact::<CompileQueue>))
+        .route_layer(auth)
+        .route_layer(CsrfLayer::new(cookies));
 
     Router::new()
-        .route("/", handler)
+        .route("/", routing::post(compile::post_compile::<CompileQueue>))
+        .nest("/jobs", jobs)
+        .layer(Extension(queue))
+        .layer(DefaultBodyLimit::max(constants::MAX_UPLOAD_BYTES))
 }
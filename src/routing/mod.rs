@@ -1,26 +1,99 @@
-use std::env;
+use std::{env, path::{Path, PathBuf}, time::Duration};
 
-use axum::{routing::get, Router};
+use axum::{routing::get, Extension, Router};
+use axum_extra::extract::cookie::SameSite;
 use http::HeaderValue;
 use sqlx::PgPool;
 use tower_http::cors::{CorsLayer, Any};
+use tracing::{info, warn};
 
-use crate::{constants, domain::users::User, auth::AuthLayer, service::{sessions::HashSessionService, hash::BcryptHashService}, repository::{sessions::PgSessionRepository, users::PgUserRepository}};
+use crate::{constants, domain::users::User, auth::{AuthLayer, CookieConfig, TrustedProxies}, service::{sessions::{HashSessionService, SessionConfig}, tokens::SimpleTokenService, hash::BcryptHashService, compilation::{SimpleCompilationService, queue::{CompileQueue, QueueConfig}, cache::OutputCache, janitor::Janitor}, execution::{ProcessExecutionService, ExecutionLimits, sandbox::{SandboxExecutionService, SandboxConfig}}}, repository::{sessions::PgSessionRepository, tokens::PgApiTokenRepository, users::PgUserRepository, files::{FsFileRepository, PgFileRepository}}};
 
-use self::{users::users_router, sessions::sessions_router, compile::compile_router};
+use self::{users::users_router, sessions::sessions_router, compile::compile_router, projects::projects_router};
 
 mod users;
 mod sessions;
 mod compile;
+mod projects;
 
-pub fn get_main_router(pool: &PgPool) -> Router {
-    let auth = AuthLayer::new(
-        HashSessionService::new(
-            PgSessionRepository::new(pool), 
-            PgUserRepository::new(pool), 
-            BcryptHashService::new()
-        )
-    );
+pub type SessionServiceImpl = HashSessionService<PgSessionRepository, PgUserRepository, BcryptHashService>;
+
+pub fn session_service(pool: &PgPool) -> SessionServiceImpl {
+    let defaults = SessionConfig::default();
+    let config = SessionConfig {
+        idle_seconds: env::var(constants::SESSION_IDLE_ENV_VAR)
+            .ok()
+            .and_then(|seconds| seconds.parse().ok())
+            .unwrap_or(defaults.idle_seconds),
+        lifetime_seconds: env::var(constants::SESSION_LENGTH_ENV_VAR)
+            .ok()
+            .and_then(|seconds| seconds.parse().ok())
+            .unwrap_or(defaults.lifetime_seconds),
+        ..defaults
+    };
+
+    HashSessionService::new(
+        PgSessionRepository::new(pool),
+        PgUserRepository::new(pool),
+        BcryptHashService::new(),
+        config
+    )
+}
+
+pub type TokenServiceImpl = SimpleTokenService<PgApiTokenRepository>;
+
+pub fn token_service(pool: &PgPool) -> TokenServiceImpl {
+    SimpleTokenService::new(PgApiTokenRepository::new(pool))
+}
+
+pub fn auth_layer(pool: &PgPool, cookies: CookieConfig) -> AuthLayer<SessionServiceImpl, TokenServiceImpl> {
+    AuthLayer::new(session_service(pool), token_service(pool), cookies)
+}
+
+// A typo must not quietly leave cookies insecure, so unknown values fail startup
+pub fn cookie_config() -> anyhow::Result<CookieConfig> {
+    let defaults = CookieConfig::default();
+    Ok(CookieConfig {
+        secure: env_flag(constants::COOKIE_SECURE_ENV_VAR, defaults.secure)?,
+        same_site: match env::var(constants::COOKIE_SAME_SITE_ENV_VAR).as_deref() {
+            Ok("strict") => SameSite::Strict,
+            Ok("lax") => SameSite::Lax,
+            Ok(same_site) => anyhow::bail!("Unknown {} {:?}, expected lax or strict", constants::COOKIE_SAME_SITE_ENV_VAR, same_site),
+            Err(env::VarError::NotPresent) => defaults.same_site,
+            Err(err) => anyhow::bail!("Invalid {}: {}", constants::COOKIE_SAME_SITE_ENV_VAR, err)
+        },
+        domain: env::var(constants::COOKIE_DOMAIN_ENV_VAR).ok(),
+        path: env::var(constants::COOKIE_PATH_ENV_VAR).unwrap_or(defaults.path),
+        host_prefix: env_flag(constants::COOKIE_HOST_PREFIX_ENV_VAR, defaults.host_prefix)?
+    })
+}
+
+fn env_flag(name: &str, default: bool) -> anyhow::Result<bool> {
+    match env::var(name).as_deref() {
+        Ok("true") => Ok(true),
+        Ok("false") => Ok(false),
+        Ok(value) => anyhow::bail!("Unknown {} {:?}, expected true or false", name, value),
+        Err(env::VarError::NotPresent) => Ok(default),
+        Err(err) => anyhow::bail!("Invalid {}: {}", name, err)
+    }
+}
+
+pub fn trusted_proxies() -> anyhow::Result<TrustedProxies> {
+    let proxies = match env::var(constants::TRUSTED_PROXIES_ENV_VAR) {
+        Ok(proxies) => proxies.split(',')
+            .map(|proxy| proxy.trim().parse().map_err(|_| anyhow::anyhow!("Invalid proxy address {:?} in {}", proxy, constants::TRUSTED_PROXIES_ENV_VAR)))
+            .collect::<anyhow::Result<Vec<_>>>()?,
+        Err(_) => Vec::new()
+    };
+    Ok(TrustedProxies::new(proxies))
+}
+
+pub fn get_main_router(pool: &PgPool) -> anyhow::Result<Router> {
+    let cookies = cookie_config()?;
+    if !cookies.secure && !cookies.host_prefix {
+        warn!("{} is false, so session cookies are also sent over plain HTTP", constants::COOKIE_SECURE_ENV_VAR);
+    }
+    let auth = auth_layer(pool, cookies.clone());
 
     let authorized_handler = get(|user: User| async move { format!("Hello, {}", user.email) })
         .layer(auth);
@@ -28,17 +101,78 @@ pub fn get_main_router(pool: &PgPool) -> Router {
     let cors = match env::var(constants::CLIENT_URL_ENV_VAR) {
         Ok(client_url) => CorsLayer::new()
             .allow_origin(client_url.parse::<HeaderValue>().unwrap())
-            .allow_headers(Any),
+            .allow_headers(Any)
+            .expose_headers([constants::DIAGNOSTICS_COUNT_HEADER.clone()]),
         Err(_) => CorsLayer::new()
             .allow_origin(Any)
             .allow_headers(Any)
+            .expose_headers([constants::DIAGNOSTICS_COUNT_HEADER.clone()])
+    };
+
+    let proxies = trusted_proxies()?;
+    match proxies.is_empty() {
+        true => info!("No trusted proxies, client addresses are taken from the connection"),
+        false => info!(?proxies, "Client addresses are taken from X-Forwarded-For behind trusted proxies")
+    };
+
+    let queue_config = QueueConfig {
+        workers: env::var(constants::COMPILE_WORKERS_ENV_VAR)
+            .ok()
+            .and_then(|workers| workers.parse().ok())
+            .unwrap_or(constants::DEFAULT_COMPILE_WORKERS),
+        ..QueueConfig::default()
+    };
+    let cache = OutputCache::new(&constants::CACHE_DIR, constants::MAX_CACHE_BYTES)?;
+    let cgroup = env::var(constants::EXECUTION_CGROUP_ENV_VAR).ok().map(PathBuf::from);
+    match &cgroup {
+        Some(cgroup) if !cgroup.join("cgroup.procs").exists() => anyhow::bail!("{} is not a cgroup", cgroup.display()),
+        Some(cgroup) => info!(?cgroup, "Compilations are limited by a cgroup"),
+        None => warn!("{} is not set, so processes are only limited per user and the memory of compilations is not limited", constants::EXECUTION_CGROUP_ENV_VAR)
+    };
+    // Falling back to plain processes on a typo would silently run compilations unsandboxed
+    let queue = match env::var(constants::EXECUTOR_ENV_VAR).as_deref() {
+        Ok("sandbox") => {
+            info!("Compilations run in the bubblewrap sandbox");
+            let mut executor = SandboxExecutionService::new(ExecutionLimits::default(), SandboxConfig::default());
+            if let Some(cgroup) = &cgroup {
+                executor = executor.with_cgroup(cgroup);
+            }
+            CompileQueue::new(SimpleCompilationService::new(executor, cache, *constants::COMPILE_DIR)?, queue_config)
+        },
+        Ok("process") | Err(env::VarError::NotPresent) => {
+            info!("Compilations run as plain processes");
+            let mut executor = ProcessExecutionService::new(ExecutionLimits::default());
+            if let Some(cgroup) = &cgroup {
+                executor = executor.with_cgroup(cgroup);
+            }
+            CompileQueue::new(SimpleCompilationService::new(executor, cache, *constants::COMPILE_DIR)?, queue_config)
+        },
+        Ok(executor) => anyhow::bail!("Unknown {} {:?}, expected sandbox or process", constants::EXECUTOR_ENV_VAR, executor),
+        Err(err) => anyhow::bail!("Invalid {}: {}", constants::EXECUTOR_ENV_VAR, err)
+    };
+
+    Janitor::new(&constants::JOBS_DIR, Duration::from_secs(constants::JOB_RETENTION_SECONDS)).with_queue(queue.clone()).spawn(
+        vec![constants::JOBS_DIR.clone(), constants::RUN_DIR.clone()],
+        Duration::from_secs(constants::JANITOR_INTERVAL_SECONDS)
+    );
+
+    let projects = match env::var(constants::FILE_STORAGE_ENV_VAR).as_deref() {
+        Ok("fs") => {
+            let files_dir = env::var(constants::FILES_DIR_ENV_VAR)
+                .unwrap_or_else(|_| constants::DEFAULT_FILES_DIR.to_owned());
+            projects_router(pool, cookies.clone(), FsFileRepository::new(Path::new(&files_dir)), queue.clone())
+        },
+        _ => projects_router(pool, cookies.clone(), PgFileRepository::new(pool), queue.clone())
     };
 
-    Router::new()
-        .nest("/users", users_router(pool))
-        .nest("/sessions", sessions_router(pool))
-        .nest("/compile", compile_router())
+    let router = Router::new()
+        .nest("/users", users_router(pool, cookies.clone()))
+        .nest("/sessions", sessions_router(pool, cookies.clone()))
+        .nest("/compile", compile_router(pool, cookies, queue))
+        .nest("/projects", projects)
         .route("/", get(|| async { "Hello, World!" }))
         .route("/authorized", authorized_handler)
-        .layer(cors)
+        .layer(Extension(proxies))
+        .layer(cors);
+    Ok(router)
 }
@@ -0,0 +1,56 @@
+use std::fmt::Debug;
+
+use axum::{Router, routing, Extension};
+use sqlx::PgPool;
+
+use crate::{service::{compilation::queue::CompileQueue, projects::SimpleProjectService, files::SimpleFileService}, control::{compile, projects, files}, csrf::CsrfLayer, auth::CookieConfig, domain::tokens::TokenScope, repository::{projects::PgProjectRepository, files::FileRepository}};
+
+use super::auth_layer;
+
+pub fn projects_router<F>(pool: &PgPool, cookies: CookieConfig, file_repository: F, queue: CompileQueue) -> Router
+where
+    F: FileRepository + Debug + Clone + Send + Sync + 'static
+{
+    let auth = auth_layer(pool, cookies.clone());
+
+    let project_service = SimpleProjectService::new(PgProjectRepository::new(pool), file_repository.clone());
+    let file_service = SimpleFileService::new(project_service.clone(), file_repository);
+
+    let projects_handler = routing::get(projects::get_projects::<SimpleProjectService<PgProjectRepository, F>>)
+        .post(projects::post_projects::<SimpleProjectService<PgProjectRepository, F>>);
+
+    let project_handler = routing::get(projects::get_project::<SimpleProjectService<PgProjectRepository, F>>)
+        .patch(projects::patch_project::<SimpleProjectService<PgProjectRepository, F>>)
+        .delete(projects::delete_project::<SimpleProjectService<PgProjectRepository, F>>);
+
+    let files_handler = routing::get(files::get_files::<SimpleFileService<SimpleProjectService<PgProjectRepository, F>, F>>);
+
+    let file_handler = routing::get(files::get_file::<SimpleFileService<SimpleProjectService<PgProjectRepository, F>, F>>)
+        .put(files::put_file::<SimpleFileService<SimpleProjectService<PgProjectRepository, F>, F>>)
+        .delete(files::delete_file::<SimpleFileService<SimpleProjectService<PgProjectRepository, F>, F>>);
+
+    let pdf_handler = routing::post(compile::post_project_pdf::<CompileQueue, SimpleProjectService<PgProjectRepository, F>, SimpleFileService<SimpleProjectService<PgProjectRepository, F>, F>>)
+        .layer(Extension(queue))
+        .route_layer(auth.clone().with_write_scope(TokenScope::Compile));
+
+    let diagnostics_handler = routing::get(compile::get_project_diagnostics::<SimpleProjectService<PgProjectRepository, F>>);
+
+    let forward_search_handler = routing::get(compile::get_project_synctex_forward::<SimpleProjectService<PgProjectRepository, F>>);
+
+    let inverse_search_handler = routing::get(compile::get_project_synctex_inverse::<SimpleProjectService<PgProjectRepository, F>>);
+
+    // Compiling needs its own token scope, so the PDF route is authenticated separately
+    Router::new()
+        .route("/", projects_handler)
+        .route("/:project_id", project_handler)
+        .route("/:project_id/diagnostics", diagnostics_handler)
+        .route("/:project_id/synctex/forward", forward_search_handler)
+        .route("/:project_id/synctex/inverse", inverse_search_handler)
+        .route("/:project_id/files", files_handler)
+        .route("/:project_id/files/*path", file_handler)
+        .route_layer(auth)
+        .route("/:project_id/pdf", pdf_handler)
+        .route_layer(CsrfLayer::new(cookies))
+        .layer(Extension(project_service))
+        .layer(Extension(file_service))
+}
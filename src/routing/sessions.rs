@@ -1,18 +1,24 @@
 use axum::{routing, Router, Extension};
 use sqlx::PgPool;
 
-use crate::{service::{sessions::HashSessionService, hash::BcryptHashService}, control::sessions, repository::{sessions::PgSessionRepository, users::PgUserRepository}};
+use crate::{control::sessions, csrf::CsrfLayer, auth::CookieConfig};
 
-pub fn sessions_router(pool: &PgPool) -> Router {
-    let session_service = HashSessionService::new(
-        PgSessionRepository::new(pool), 
-        PgUserRepository::new(pool), 
-        BcryptHashService::new()
-    );
+use super::{session_service, auth_layer, SessionServiceImpl};
 
-    let handler = routing::post(sessions::post_sessions::<HashSessionService<PgSessionRepository, PgUserRepository, BcryptHashService>>)
-        .layer(Extension(session_service));
+pub fn sessions_router(pool: &PgPool, cookies: CookieConfig) -> Router {
+    let session_service = session_service(pool);
+    let auth = auth_layer(pool, cookies.clone()).without_tokens();
+    let csrf = CsrfLayer::new(cookies.clone());
+
+    let authorized = routing::get(sessions::get_sessions::<SessionServiceImpl>)
+        .delete(sessions::delete_sessions::<SessionServiceImpl>)
+        .route_layer(auth.clone())
+        .route_layer(csrf.clone());
 
     Router::new()
-        .route("/", handler)
+        .route("/", routing::post(sessions::post_sessions::<SessionServiceImpl>).merge(authorized))
+        .route("/all", routing::delete(sessions::delete_all_sessions::<SessionServiceImpl>).route_layer(auth.clone()).route_layer(csrf.clone()))
+        .route("/:public_id", routing::delete(sessions::delete_session::<SessionServiceImpl>).route_layer(auth).route_layer(csrf))
+        .layer(Extension(session_service))
+        .layer(Extension(cookies))
 }
@@ -1,14 +1,25 @@
 use axum::{Router, routing, Extension};
 use sqlx::PgPool;
 
-use crate::{control, service::{users::HashUserService, hash::BcryptHashService}, repository::users::PgUserRepository};
+use crate::{control, service::{users::HashUserService, hash::BcryptHashService}, repository::users::PgUserRepository, csrf::CsrfLayer, auth::CookieConfig};
 
-pub fn users_router(pool: &PgPool) -> Router {
+use super::{auth_layer, token_service, TokenServiceImpl};
+
+pub fn users_router(pool: &PgPool, cookies: CookieConfig) -> Router {
     let user_service = HashUserService::new(PgUserRepository::new(pool), BcryptHashService::new());
 
     let handler = routing::post(control::users::post_users::<HashUserService<PgUserRepository, BcryptHashService>>)
         .layer(Extension(user_service));
+
+    // A token must not be able to mint more tokens, so these only accept the session cookie
+    let tokens = Router::new()
+        .route("/", routing::get(control::tokens::get_tokens::<TokenServiceImpl>).post(control::tokens::post_tokens::<TokenServiceImpl>))
+        .route("/:token_id", routing::delete(control::tokens::delete_token::<TokenServiceImpl>))
+        .route_layer(auth_layer(pool, cookies.clone()).without_tokens())
+        .route_layer(CsrfLayer::new(cookies))
+        .layer(Extension(token_service(pool)));
     
     Router::new()
         .route("/", handler)
+        .nest("/me/tokens", tokens)
 }
@@ -0,0 +1,155 @@
+use std::{fs, path::Path};
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::domain::compile::{Diagnostic, Severity, SourceFile};
+
+lazy_static! {
+    static ref BIBLATEX_REGEX: Regex = Regex::new(r"\\usepackage\s*(?:\[([^\]]*)\])?\s*\{[^}]*\bbiblatex\b[^}]*\}").unwrap();
+    static ref BACKEND_REGEX: Regex = Regex::new(r"backend\s*=\s*bibtex").unwrap();
+    static ref BIBTEX_REGEX: Regex = Regex::new(r"\\usepackage\s*(?:\[[^\]]*\])?\s*\{[^}]*\bnatbib\b[^}]*\}|\\bibliography\s*\{|\\bibliographystyle\s*\{").unwrap();
+    static ref BIBTEX_LOCATION_REGEX: Regex = Regex::new(r"-+line (\d+) of file (\S+)$").unwrap();
+    static ref BIBER_MESSAGE_REGEX: Regex = Regex::new(r"^\[\d+\] [^>]*> (WARN|ERROR) - (.*)$").unwrap();
+    static ref BIBER_FILE_REGEX: Regex = Regex::new(r"([^/\s,'()]+\.bib)(?:_\d+\.utf8)?").unwrap();
+    static ref BIBER_LINE_REGEX: Regex = Regex::new(r"\bline (\d+)").unwrap();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BibliographyTool {
+    Bibtex,
+    Biber
+}
+
+impl BibliographyTool {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Bibtex => "bibtex",
+            Self::Biber => "biber"
+        }
+    }
+}
+
+pub fn detect(files: &[SourceFile]) -> Option<BibliographyTool> {
+    let sources: Vec<String> = files.iter()
+        .filter(|file| file.path.ends_with(".tex"))
+        .map(|file| strip_comments(&String::from_utf8_lossy(&file.content)))
+        .collect();
+
+    // biblatex defaults to biber, but can be pointed at bibtex
+    if let Some(captures) = sources.iter().find_map(|source| BIBLATEX_REGEX.captures(source)) {
+        let uses_bibtex = matches!(captures.get(1), Some(options) if BACKEND_REGEX.is_match(options.as_str()));
+        return Some(if uses_bibtex { BibliographyTool::Bibtex } else { BibliographyTool::Biber });
+    }
+
+    if sources.iter().any(|source| BIBTEX_REGEX.is_match(source)) {
+        return Some(BibliographyTool::Bibtex);
+    }
+    None
+}
+
+// Without a bibliography, latexmk must not pick up stale .bbl files or try to run bibtex at all
+pub fn latexmk_flags(tool: Option<BibliographyTool>) -> Vec<String> {
+    match tool {
+        Some(_) => vec!["-bibtex".to_string()],
+        None => vec!["-bibtex-".to_string()]
+    }
+}
+
+pub fn read_diagnostics(blg_path: &Path) -> Option<Vec<Diagnostic>> {
+    let blg = fs::read(blg_path).ok()?;
+    Some(parse_blg(&String::from_utf8_lossy(&blg)))
+}
+
+pub fn parse_blg(blg: &str) -> Vec<Diagnostic> {
+    let lines: Vec<&str> = blg.lines().collect();
+    if lines.iter().any(|line| BIBER_MESSAGE_REGEX.is_match(line)) {
+        parse_biber(&lines)
+    } else {
+        parse_bibtex(&lines)
+    }
+}
+
+fn parse_bibtex(lines: &[&str]) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    let mut index = 0;
+    while index < lines.len() {
+        let line = lines[index];
+        index += 1;
+
+        if let Some(message) = line.strip_prefix("Warning--") {
+            let mut diagnostic = bibtex_diagnostic(Severity::Warning, message.trim());
+            // Some warnings are followed by the location of the offending entry
+            if let Some(captures) = lines.get(index).and_then(|next| BIBTEX_LOCATION_REGEX.captures(next)) {
+                diagnostic.line = captures[1].parse().ok();
+                diagnostic.file = Some(captures[2].to_owned());
+                index += 1;
+            }
+            diagnostics.push(diagnostic);
+        } else if let Some(captures) = BIBTEX_LOCATION_REGEX.captures(line) {
+            let message = line[..captures.get(0).unwrap().start()].trim();
+            let mut diagnostic = bibtex_diagnostic(Severity::Error, message);
+            diagnostic.line = captures[1].parse().ok();
+            diagnostic.file = Some(captures[2].to_owned());
+
+            while let Some(context) = lines.get(index).and_then(|next| next.strip_prefix(" : ")) {
+                diagnostic.context.push(context.to_owned());
+                index += 1;
+            }
+            diagnostics.push(diagnostic);
+        } else if line.starts_with("I couldn't open") || line.starts_with("I found no") {
+            diagnostics.push(bibtex_diagnostic(Severity::Error, line.trim()));
+        }
+    }
+
+    diagnostics
+}
+
+fn bibtex_diagnostic(severity: Severity, message: &str) -> Diagnostic {
+    Diagnostic {
+        severity,
+        file: None,
+        line: None,
+        message: message.to_owned(),
+        package: Some(BibliographyTool::Bibtex.as_str().to_owned()),
+        context: Vec::new()
+    }
+}
+
+fn parse_biber(lines: &[&str]) -> Vec<Diagnostic> {
+    lines.iter()
+        .filter_map(|line| BIBER_MESSAGE_REGEX.captures(line))
+        .map(|captures| {
+            let message = captures[2].trim().to_owned();
+            Diagnostic {
+                severity: if &captures[1] == "ERROR" { Severity::Error } else { Severity::Warning },
+                file: BIBER_FILE_REGEX.captures(&message).map(|file| file[1].to_owned()),
+                line: BIBER_LINE_REGEX.captures(&message).and_then(|line| line[1].parse().ok()),
+                package: Some(BibliographyTool::Biber.as_str().to_owned()),
+                message,
+                context: Vec::new()
+            }
+        })
+        .collect()
+}
+
+fn strip_comments(source: &str) -> String {
+    source.lines()
+        .map(|line| {
+            let mut escaped = false;
+            let end = line.char_indices()
+                .find(|&(_, c)| {
+                    let comment = c == '%' && !escaped;
+                    escaped = c == '\\' && !escaped;
+                    comment
+                })
+                .map_or(line.len(), |(position, _)| position);
+            &line[..end]
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests;
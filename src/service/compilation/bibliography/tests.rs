@@ -0,0 +1,123 @@
+use super::*;
+
+fn mock_source(content: &str) -> SourceFile {
+    SourceFile {
+        path: String::from("main.tex"),
+        content: content.as_bytes().to_vec()
+    }
+}
+
+fn mock_bibtex_blg() -> String {
+    String::from("\
+This is BibTeX, Version 0.99d (TeX Live 2019/Debian)
+The top-level auxiliary file: main.aux
+The style file: plain.bst
+Database file #1: refs.bib
+I was expecting a `,' or a `}'---line 7 of file refs.bib
+ :   title = \"Missing comma\"
+ :   author
+I'm skipping whatever remains of this entry
+Warning--I didn't find a database entry for \"missing\"
+Warning--empty journal in knuth84
+Warning--entry type for \"odd\" isn't style-file defined
+--line 12 of file refs.bib
+(There was 1 error message)
+")
+}
+
+fn mock_biber_blg() -> String {
+    String::from("\
+[0] Config.pm:311> INFO - This is Biber 2.14
+[45] Biber.pm:3932> INFO - Reading 'refs.bib'
+[60] Utils.pm:209> ERROR - BibTeX subsystem: /tmp/biber_tmp_abc/refs.bib_123.utf8, line 5, syntax error: found \"}\", expected \",\"
+[61] Biber.pm:1338> WARN - I didn't find a database entry for 'missing' (section 0)
+[62] Utils.pm:193> WARN - Datamodel: Entry 'knuth84' (refs.bib): Missing mandatory field 'author'
+[70] Biber.pm:128> INFO - ERRORS: 1
+")
+}
+
+#[test]
+fn detect_biblatex() {
+    let files = vec![mock_source("\\usepackage[style=authoryear]{biblatex}\n\\addbibresource{refs.bib}")];
+
+    assert_eq!(Some(BibliographyTool::Biber), detect(&files));
+}
+
+#[test]
+fn detect_biblatex_bibtex_backend() {
+    let files = vec![mock_source("\\usepackage[backend=bibtex, style=numeric]{biblatex}")];
+
+    assert_eq!(Some(BibliographyTool::Bibtex), detect(&files));
+}
+
+#[test]
+fn detect_natbib() {
+    let files = vec![
+        mock_source("\\input{chapters/intro}"),
+        SourceFile {
+            path: String::from("preamble.tex"),
+            content: b"\\usepackage[numbers]{natbib}".to_vec()
+        }
+    ];
+
+    assert_eq!(Some(BibliographyTool::Bibtex), detect(&files));
+}
+
+#[test]
+fn detect_plain_bibliography() {
+    let files = vec![mock_source("\\bibliographystyle{plain}\n\\bibliography{refs}")];
+
+    assert_eq!(Some(BibliographyTool::Bibtex), detect(&files));
+}
+
+#[test]
+fn detect_ignores_comments_and_other_files() {
+    let files = vec![
+        mock_source("% \\usepackage{biblatex}\n100\\% done"),
+        SourceFile {
+            path: String::from("notes.txt"),
+            content: b"\\bibliography{refs}".to_vec()
+        }
+    ];
+
+    assert_eq!(None, detect(&files));
+}
+
+#[test]
+fn parse_bibtex_blg() {
+    let diagnostics = parse_blg(&mock_bibtex_blg());
+
+    assert_eq!(4, diagnostics.len());
+
+    assert_eq!(Severity::Error, diagnostics[0].severity);
+    assert_eq!("I was expecting a `,' or a `}'", diagnostics[0].message);
+    assert_eq!(Some(String::from("refs.bib")), diagnostics[0].file);
+    assert_eq!(Some(7), diagnostics[0].line);
+    assert_eq!(2, diagnostics[0].context.len());
+    assert_eq!(Some(String::from("bibtex")), diagnostics[0].package);
+
+    assert_eq!(Severity::Warning, diagnostics[1].severity);
+    assert_eq!("I didn't find a database entry for \"missing\"", diagnostics[1].message);
+    assert_eq!(None, diagnostics[1].line);
+
+    assert_eq!(Some(String::from("refs.bib")), diagnostics[3].file);
+    assert_eq!(Some(12), diagnostics[3].line);
+}
+
+#[test]
+fn parse_biber_blg() {
+    let diagnostics = parse_blg(&mock_biber_blg());
+
+    assert_eq!(3, diagnostics.len());
+
+    assert_eq!(Severity::Error, diagnostics[0].severity);
+    assert_eq!(Some(String::from("refs.bib")), diagnostics[0].file);
+    assert_eq!(Some(5), diagnostics[0].line);
+    assert_eq!(Some(String::from("biber")), diagnostics[0].package);
+
+    assert_eq!(Severity::Warning, diagnostics[1].severity);
+    assert_eq!("I didn't find a database entry for 'missing' (section 0)", diagnostics[1].message);
+    assert_eq!(None, diagnostics[1].file);
+
+    assert_eq!(Some(String::from("refs.bib")), diagnostics[2].file);
+}
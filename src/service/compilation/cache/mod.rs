@@ -0,0 +1,280 @@
+use std::{collections::{BTreeMap, HashMap, HashSet}, fmt::{self, Debug, Formatter}, fs, io, iter, path::{Path, PathBuf}, sync::{Arc, Mutex}};
+
+use sha2::{Digest, Sha256};
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::{domain::compile::{OutputFormat, SourceFile}, repository::files::list_dir};
+
+use super::{CompileOutput, diagnostics, synctex};
+
+pub fn cache_key(files: &[SourceFile], entrypoint: &str, flags: &[String]) -> String {
+    let mut files: Vec<&SourceFile> = files.iter().collect();
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let mut hasher = Sha256::new();
+    // Every field is length-prefixed, so that no two different inputs share a byte stream
+    for flag in flags {
+        update_field(&mut hasher, flag.as_bytes());
+    }
+    update_field(&mut hasher, entrypoint.as_bytes());
+    for file in files {
+        update_field(&mut hasher, file.path.as_bytes());
+        update_field(&mut hasher, &file.content);
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+fn update_field(hasher: &mut Sha256, field: &[u8]) {
+    hasher.update((field.len() as u64).to_le_bytes());
+    hasher.update(field);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct CacheEntry {
+    size: u64,
+    last_used: u64,
+    pins: usize
+}
+
+// Entries are ordered by their last use, so the least recent one is found without scanning every entry
+#[derive(Default)]
+struct CacheIndex {
+    entries: HashMap<String, CacheEntry>,
+    recency: BTreeMap<u64, String>,
+    total_bytes: u64,
+    clock: u64
+}
+
+impl CacheIndex {
+    fn touch(&mut self, key: &str) {
+        self.clock += 1;
+        if let Some(entry) = self.entries.get_mut(key) {
+            self.recency.remove(&entry.last_used);
+            entry.last_used = self.clock;
+            self.recency.insert(self.clock, key.to_owned());
+        }
+    }
+
+    fn insert(&mut self, key: String, size: u64) {
+        self.clock += 1;
+        let mut entry = CacheEntry { size, last_used: self.clock, pins: 0 };
+        if let Some(previous) = self.entries.get(&key) {
+            self.total_bytes -= previous.size;
+            self.recency.remove(&previous.last_used);
+            entry.pins = previous.pins;
+        }
+        self.recency.insert(self.clock, key.clone());
+        self.entries.insert(key, entry);
+        self.total_bytes += size;
+    }
+
+    // A pinned entry is not evicted, so it can be read without the index being locked
+    fn pin(&mut self, key: &str) -> bool {
+        self.touch(key);
+        match self.entries.get_mut(key) {
+            Some(entry) => {
+                entry.pins += 1;
+                true
+            },
+            None => false
+        }
+    }
+
+    fn unpin(&mut self, key: &str) {
+        if let Some(entry) = self.entries.get_mut(key) {
+            entry.pins = entry.pins.saturating_sub(1);
+        }
+    }
+
+    fn pop_least_recent(&mut self) -> Option<String> {
+        let (&last_used, key) = self.recency.iter()
+            .find(|(_, key)| matches!(self.entries.get(*key), Some(entry) if entry.pins == 0))?;
+        let key = key.clone();
+        self.recency.remove(&last_used);
+        if let Some(entry) = self.entries.remove(&key) {
+            self.total_bytes -= entry.size;
+        }
+        Some(key)
+    }
+}
+
+#[derive(Clone)]
+pub struct OutputCache {
+    dir: PathBuf,
+    max_bytes: u64,
+    index: Arc<Mutex<CacheIndex>>
+}
+
+impl Debug for OutputCache {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OutputCache")
+            .field("dir", &self.dir)
+            .field("max_bytes", &self.max_bytes)
+            .finish()
+    }
+}
+
+impl OutputCache {
+    pub fn new(dir: &Path, max_bytes: u64) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let cache = Self {
+            dir: dir.to_path_buf(),
+            max_bytes,
+            index: Arc::new(Mutex::new(CacheIndex::default()))
+        };
+        if let Err(err) = cache.load() {
+            error!(%err, "Failed to load compile cache");
+        }
+        Ok(cache)
+    }
+
+    // Copies a cached build next to pdf_path, so that serving it does not depend on the entry staying cached
+    pub fn restore(&self, key: &str, pdf_path: &Path, formats: &[OutputFormat]) -> io::Result<Option<CompileOutput>> {
+        if !self.index.lock().unwrap().pin(key) {
+            return Ok(None);
+        }
+        let restored = copy_entry(&self.dir.join(key), pdf_path);
+        self.index.lock().unwrap().unpin(key);
+
+        if !restored? {
+            return Ok(None);
+        }
+        info!(key, "Compile cache hit");
+        Ok(Some(CompileOutput {
+            pdf: pdf_path.to_path_buf(),
+            diagnostics: diagnostics::read_diagnostics(&pdf_path.with_extension("log")).unwrap_or_default(),
+            artifacts: super::collect_artifacts(pdf_path, formats),
+            job_dir: None
+        }))
+    }
+
+    // The key does not depend on where the sources were compiled, so paths to them are made relative to source_path
+    pub fn insert(&self, key: &str, output: &CompileOutput, source_path: &Path) -> io::Result<()> {
+        let entry = self.dir.join(key);
+        if entry.is_dir() {
+            self.index.lock().unwrap().touch(key);
+            return Ok(());
+        }
+
+        // Every file keeps its path relative to the pdf, so the entry can be restored under the same names
+        let base = output.pdf.parent().ok_or(io::ErrorKind::InvalidInput)?;
+        let files = iter::once(output.pdf.clone())
+            .chain([output.pdf.with_extension("log"), output.pdf.with_extension("blg")].into_iter().filter(|path| path.is_file()))
+            .chain(output.artifacts.iter().cloned());
+        let mut copied = HashSet::new();
+
+        // Entries are assembled under a temporary name and renamed, so readers never see a partial entry
+        let staging = self.dir.join(format!("{}.{}.tmp", key, Uuid::new_v4()));
+        fs::create_dir_all(&staging)?;
+        let mut size = 0;
+        for file in files {
+            let relative = file.strip_prefix(base).map_err(|_| io::Error::from(io::ErrorKind::InvalidInput))?;
+            if !copied.insert(relative.to_path_buf()) {
+                continue;
+            }
+            let target = staging.join(relative);
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            size += if relative.to_string_lossy().ends_with(".synctex.gz") {
+                synctex::relativize(&file, &target, source_path)?
+            } else {
+                fs::copy(&file, target)?
+            };
+        }
+
+        if let Err(err) = fs::rename(&staging, &entry) {
+            fs::remove_dir_all(&staging)?;
+            if !entry.is_dir() {
+                return Err(err);
+            }
+        }
+
+        self.index.lock().unwrap().insert(key.to_owned(), size);
+        self.evict();
+        Ok(())
+    }
+
+    fn evict(&self) {
+        let mut index = self.index.lock().unwrap();
+        while index.total_bytes > self.max_bytes {
+            let key = match index.pop_least_recent() {
+                Some(key) => key,
+                None => break
+            };
+            info!(key, "Evicting compile cache entry");
+            if let Err(err) = fs::remove_dir_all(self.dir.join(&key)) {
+                error!(%err);
+            }
+        }
+    }
+
+    fn load(&self) -> io::Result<()> {
+        let mut entries = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            let key = entry.file_name().to_string_lossy().into_owned();
+
+            if key.ends_with(".tmp") {
+                fs::remove_dir_all(&path)?;
+                continue;
+            }
+
+            let size = list_dir(&path)?.iter().map(|file| file.size as u64).sum();
+            entries.push((entry.metadata()?.modified()?, key, size));
+        }
+
+        entries.sort();
+        let mut index = self.index.lock().unwrap();
+        for (_, key, size) in entries {
+            index.insert(key, size);
+        }
+        drop(index);
+
+        self.evict();
+        Ok(())
+    }
+}
+
+// Returns false if the entry holds no pdf of that name
+fn copy_entry(entry: &Path, pdf_path: &Path) -> io::Result<bool> {
+    let output_path = pdf_path.parent().ok_or(io::ErrorKind::InvalidInput)?;
+    let pdf_name = pdf_path.file_name().ok_or(io::ErrorKind::InvalidInput)?;
+    if !entry.join(pdf_name).is_file() {
+        return Ok(false);
+    }
+
+    fs::create_dir_all(output_path)?;
+    let optional = [Path::new(pdf_name).with_extension("log"), Path::new(pdf_name).with_extension("blg")];
+    for name in &optional {
+        restore_optional(&entry.join(name), &output_path.join(name))?;
+    }
+    for file in list_dir(entry)? {
+        if optional.iter().any(|name| name == Path::new(&file.path)) {
+            continue;
+        }
+        let target = output_path.join(&file.path);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(entry.join(&file.path), &target)?;
+    }
+    Ok(true)
+}
+
+// A file missing from the entry must not be left over from an earlier build either
+fn restore_optional(from: &Path, to: &Path) -> io::Result<()> {
+    match fs::copy(from, to) {
+        Ok(_) => Ok(()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => match fs::remove_file(to) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+            _ => Ok(())
+        },
+        Err(err) => Err(err)
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,148 @@
+use super::*;
+
+fn mock_dir(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("agartex_cache_{}_{}", name, rand::random::<u32>()))
+}
+
+fn mock_files() -> Vec<SourceFile> {
+    vec![
+        SourceFile { path: String::from("main.tex"), content: b"\\input{intro}".to_vec() },
+        SourceFile { path: String::from("intro.tex"), content: b"Intro".to_vec() }
+    ]
+}
+
+fn mock_flags() -> Vec<String> {
+    vec![String::from("-pdf")]
+}
+
+fn mock_output(dir: &Path, size: usize) -> CompileOutput {
+    fs::create_dir_all(dir).unwrap();
+    let pdf = dir.join("main.pdf");
+    fs::write(&pdf, vec![0; size]).unwrap();
+    fs::write(dir.join("main.log"), "LaTeX Warning: There were undefined references.\n").unwrap();
+    CompileOutput { pdf, diagnostics: Vec::new(), artifacts: Vec::new(), job_dir: None }
+}
+
+#[test]
+fn cache_key_ignores_file_order() {
+    let mut reversed = mock_files();
+    reversed.reverse();
+
+    assert_eq!(cache_key(&mock_files(), "main.tex", &mock_flags()), cache_key(&reversed, "main.tex", &mock_flags()));
+}
+
+#[test]
+fn cache_key_depends_on_inputs() {
+    let key = cache_key(&mock_files(), "main.tex", &mock_flags());
+
+    let mut changed = mock_files();
+    changed[1].content = b"Changed".to_vec();
+
+    assert_ne!(key, cache_key(&changed, "main.tex", &mock_flags()));
+    assert_ne!(key, cache_key(&mock_files(), "intro.tex", &mock_flags()));
+    assert_ne!(key, cache_key(&mock_files(), "main.tex", &[String::from("-pdfxe")]));
+}
+
+#[test]
+fn insert_then_restore() {
+    let dir = mock_dir("restore");
+    let cache = OutputCache::new(&dir.join("cache"), 1024).unwrap();
+    let output = mock_output(&dir.join("out"), 16);
+
+    assert_eq!(None, cache.restore("key", &dir.join("job/main.pdf"), &[]).unwrap());
+    cache.insert("key", &output, &dir.join("src")).unwrap();
+
+    let cached = cache.restore("key", &dir.join("job/main.pdf"), &[]).unwrap().unwrap();
+    assert_eq!(dir.join("job/main.pdf"), cached.pdf);
+    assert!(cached.pdf.is_file());
+    assert_eq!(1, cached.diagnostics.len());
+    assert_eq!(0, cache.index.lock().unwrap().entries["key"].pins);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn insert_evicts_least_recently_used() {
+    let dir = mock_dir("evict");
+    let cache = OutputCache::new(&dir.join("cache"), 400).unwrap();
+    let output = mock_output(&dir.join("out"), 100);
+
+    cache.insert("first", &output, &dir.join("src")).unwrap();
+    cache.insert("second", &output, &dir.join("src")).unwrap();
+    cache.restore("first", &dir.join("job/main.pdf"), &[]).unwrap().unwrap();
+    cache.insert("third", &output, &dir.join("src")).unwrap();
+
+    assert!(dir.join("cache/first").is_dir());
+    assert!(!dir.join("cache/second").exists());
+    assert!(dir.join("cache/third").is_dir());
+    assert_eq!(vec!["first", "third"], cache.index.lock().unwrap().recency.values().collect::<Vec<_>>());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn insert_keeps_pinned_entries() {
+    let dir = mock_dir("pinned");
+    let cache = OutputCache::new(&dir.join("cache"), 400).unwrap();
+    let output = mock_output(&dir.join("out"), 100);
+
+    cache.insert("first", &output, &dir.join("src")).unwrap();
+    assert!(cache.index.lock().unwrap().pin("first"));
+    cache.insert("second", &output, &dir.join("src")).unwrap();
+    cache.insert("third", &output, &dir.join("src")).unwrap();
+
+    assert!(dir.join("cache/first").is_dir());
+    assert!(!dir.join("cache/second").exists());
+    assert!(dir.join("cache/third").is_dir());
+
+    cache.index.lock().unwrap().unpin("first");
+    cache.insert("fourth", &output, &dir.join("src")).unwrap();
+    assert!(!dir.join("cache/first").exists());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn new_loads_existing_entries() {
+    let dir = mock_dir("load");
+    let output = mock_output(&dir.join("out"), 100);
+    OutputCache::new(&dir.join("cache"), 1024).unwrap().insert("key", &output, &dir.join("src")).unwrap();
+
+    let cache = OutputCache::new(&dir.join("cache"), 1024).unwrap();
+
+    assert!(cache.restore("key", &dir.join("job/main.pdf"), &[]).unwrap().is_some());
+    assert_eq!(1, cache.index.lock().unwrap().entries.len());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn insert_keeps_artifacts() {
+    let dir = mock_dir("artifacts");
+    let cache = OutputCache::new(&dir.join("cache"), 1024).unwrap();
+    let mut output = mock_output(&dir.join("out"), 16);
+    fs::create_dir_all(dir.join("out/html")).unwrap();
+    fs::write(dir.join("out/main.dvi"), "dvi").unwrap();
+    fs::write(dir.join("out/html/main.html"), "html").unwrap();
+    output.artifacts = vec![dir.join("out/html/main.html"), dir.join("out/main.dvi")];
+
+    cache.insert("key", &output, &dir.join("src")).unwrap();
+    let cached = cache.restore("key", &dir.join("job/main.pdf"), &[OutputFormat::Dvi, OutputFormat::Html]).unwrap().unwrap();
+
+    assert!(dir.join("cache/key/main.pdf").is_file());
+    assert!(dir.join("cache/key/html/main.html").is_file());
+    assert_eq!(vec![dir.join("job/main.dvi"), dir.join("job/html/main.html")], cached.artifacts);
+    assert_eq!(vec!["main.pdf", "main.dvi", "html/main.html"], cached.artifact_names());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn new_fails_on_unusable_dir() {
+    let dir = mock_dir("unusable");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("cache"), "not a directory").unwrap();
+
+    assert!(OutputCache::new(&dir.join("cache"), 1024).is_err());
+    fs::remove_dir_all(dir).unwrap();
+}
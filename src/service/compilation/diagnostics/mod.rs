@@ -0,0 +1,205 @@
+use std::{fs, path::Path};
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::domain::compile::{Diagnostic, Severity};
+
+use super::bibliography;
+
+// TeX hard-wraps the log at max_print_line characters
+const MAX_LINE_LENGTH: usize = 79;
+const MAX_CONTEXT_LINES: usize = 6;
+const MAX_ERROR_LOOKAHEAD: usize = 20;
+
+lazy_static! {
+    static ref ERROR_LINE_REGEX: Regex = Regex::new(r"^l\.(\d+)").unwrap();
+    static ref PACKAGE_ERROR_REGEX: Regex = Regex::new(r"^(?:Package|Class) (\S+) Error: ").unwrap();
+    static ref WARNING_REGEX: Regex = Regex::new(r"^(?:(?:Package|Class) (\S+)|LaTeX(?: \w+)?|pdfTeX) [Ww]arning(?: \([^)]*\))?: (.*)$").unwrap();
+    static ref CONTINUATION_REGEX: Regex = Regex::new(r"^\((\S+)\)\s+(.*)$").unwrap();
+    static ref BAD_BOX_REGEX: Regex = Regex::new(r"^(?:Over|Under)full \\[hv]box").unwrap();
+    static ref INPUT_LINE_REGEX: Regex = Regex::new(r"on input line (\d+)").unwrap();
+    static ref BOX_LINE_REGEX: Regex = Regex::new(r"(?:at lines? |detected at line )(\d+)").unwrap();
+    static ref FILE_REGEX: Regex = Regex::new(r"^(?:\.{0,2}/)?[^\s()\[\]{}]*\.[A-Za-z]{1,8}").unwrap();
+}
+
+pub fn read_diagnostics(log_path: &Path) -> Option<Vec<Diagnostic>> {
+    let log = fs::read(log_path).ok()?;
+    let mut diagnostics = parse_log(&String::from_utf8_lossy(&log));
+
+    // BibTeX and biber write their own log next to the LaTeX one
+    if let Some(bibliography) = bibliography::read_diagnostics(&log_path.with_extension("blg")) {
+        diagnostics.extend(bibliography);
+    }
+    Some(diagnostics)
+}
+
+pub fn parse_log(log: &str) -> Vec<Diagnostic> {
+    let lines = unwrap_lines(log);
+    let mut files = FileStack::default();
+    let mut diagnostics = Vec::new();
+
+    let mut index = 0;
+    while index < lines.len() {
+        let line = &lines[index];
+        index += 1;
+
+        if let Some(message) = line.strip_prefix("! ") {
+            if is_fatal_summary(message) {
+                continue;
+            }
+            let (diagnostic, consumed) = parse_error(message, &lines[index..], files.current());
+            diagnostics.push(diagnostic);
+            index += consumed;
+        } else if let Some(captures) = WARNING_REGEX.captures(line) {
+            let mut message = captures[2].trim().to_owned();
+            while let Some(continuation) = lines.get(index).and_then(|line| CONTINUATION_REGEX.captures(line)) {
+                message.push(' ');
+                message.push_str(continuation[2].trim());
+                index += 1;
+            }
+
+            diagnostics.push(Diagnostic {
+                severity: Severity::Warning,
+                file: files.current(),
+                line: capture_number(&INPUT_LINE_REGEX, &message),
+                package: captures.get(1).map(|package| package.as_str().to_owned()),
+                message,
+                context: Vec::new()
+            });
+        } else if BAD_BOX_REGEX.is_match(line) {
+            // The typeset material following a bad box can contain unbalanced parentheses
+            let mut context = Vec::new();
+            while let Some(line) = lines.get(index).filter(|line| !line.trim().is_empty()) {
+                if context.len() < MAX_CONTEXT_LINES {
+                    context.push(line.clone());
+                }
+                index += 1;
+            }
+
+            diagnostics.push(Diagnostic {
+                severity: Severity::Info,
+                file: files.current(),
+                line: capture_number(&BOX_LINE_REGEX, line),
+                message: line.trim().to_owned(),
+                package: None,
+                context
+            });
+        } else {
+            files.scan(line);
+        }
+    }
+
+    diagnostics
+}
+
+pub fn is_warning(line: &str) -> bool {
+    WARNING_REGEX.is_match(line)
+}
+
+fn parse_error(message: &str, rest: &[String], file: Option<String>) -> (Diagnostic, usize) {
+    let package = PACKAGE_ERROR_REGEX.captures(message)
+        .map(|captures| captures[1].to_owned());
+
+    let mut context = Vec::new();
+    let mut line = None;
+    let mut consumed = 0;
+
+    for (offset, text) in rest.iter().take(MAX_ERROR_LOOKAHEAD).enumerate() {
+        if let Some(captures) = ERROR_LINE_REGEX.captures(text) {
+            line = captures[1].parse().ok();
+            context.push(text.clone());
+            if let Some(next) = rest.get(offset + 1) {
+                context.push(next.clone());
+            }
+            consumed = (offset + 2).min(rest.len());
+            break;
+        }
+
+        if is_error_context(text) && context.len() < MAX_CONTEXT_LINES {
+            context.push(text.clone());
+        }
+    }
+
+    if line.is_none() {
+        context.clear();
+    }
+
+    let diagnostic = Diagnostic {
+        severity: Severity::Error,
+        file,
+        line,
+        message: message.trim().to_owned(),
+        package,
+        context
+    };
+    (diagnostic, consumed)
+}
+
+fn is_fatal_summary(message: &str) -> bool {
+    message.trim_start().starts_with("==>") || message.trim() == "Emergency stop."
+}
+
+fn is_error_context(line: &str) -> bool {
+    !line.trim().is_empty()
+        && !line.starts_with("Type  H <return>")
+        && !line.starts_with("See the ")
+        && line.trim() != "..."
+}
+
+fn capture_number(regex: &Regex, text: &str) -> Option<u32> {
+    regex.captures(text)
+        .and_then(|captures| captures[1].parse().ok())
+}
+
+fn unwrap_lines(log: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    for line in log.lines() {
+        current.push_str(line);
+        if line.chars().count() != MAX_LINE_LENGTH {
+            lines.push(std::mem::take(&mut current));
+        }
+    }
+    if !current.is_empty() {
+        lines.push(current);
+    }
+    lines
+}
+
+#[derive(Default)]
+struct FileStack {
+    stack: Vec<Option<String>>
+}
+
+impl FileStack {
+    fn current(&self) -> Option<String> {
+        self.stack.iter()
+            .rev()
+            .find_map(|file| file.clone())
+    }
+
+    fn scan(&mut self, line: &str) {
+        let mut rest = line;
+        while let Some(position) = rest.find(['(', ')']) {
+            if rest[position..].starts_with(')') {
+                self.stack.pop();
+                rest = &rest[position + 1..];
+                continue;
+            }
+
+            rest = &rest[position + 1..];
+            match FILE_REGEX.find(rest) {
+                Some(file) => {
+                    let path = file.as_str();
+                    self.stack.push(Some(path.strip_prefix("./").unwrap_or(path).to_owned()));
+                    rest = &rest[file.end()..];
+                },
+                None => self.stack.push(None)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,121 @@
+use super::*;
+
+fn mock_error_log() -> String {
+    String::from("\
+This is pdfTeX, Version 3.14159265-2.6-1.40.20 (TeX Live 2019/Debian) (preloaded format=pdflatex 2023.3.1)
+entering extended mode
+(./main.tex
+LaTeX2e <2020-02-02> patch level 2
+(/usr/share/texlive/texmf-dist/tex/latex/base/article.cls
+Document Class: article 2019/12/20 v1.4l Standard LaTeX document class
+(/usr/share/texlive/texmf-dist/tex/latex/base/size10.clo)) (./chapters/intro.tex
+! Undefined control sequence.
+l.3 \\foo
+         {bar}
+The control sequence at the end of the top line
+of your error message was never \\def'ed.
+
+) [1] (./main.aux) )
+")
+}
+
+fn mock_warning_log() -> String {
+    String::from("\
+(./main.tex
+Package hyperref Warning: Token not allowed in a PDF string (Unicode):
+(hyperref)                removing `math shift' on input line 12.
+
+LaTeX Warning: Reference `fig:missing' on page 1 undefined on input line 17.
+
+Overfull \\hbox (12.0pt too wide) in paragraph at lines 20--22
+[]\\OT1/cmr/m/n/10 Some (unbalanced text
+
+
+LaTeX Warning: There were undefined references.
+
+ )
+")
+}
+
+#[test]
+fn parse_log_error_with_line() {
+    let diagnostics = parse_log(&mock_error_log());
+
+    assert_eq!(vec![Diagnostic {
+        severity: Severity::Error,
+        file: Some(String::from("chapters/intro.tex")),
+        line: Some(3),
+        message: String::from("Undefined control sequence."),
+        package: None,
+        context: vec![String::from("l.3 \\foo"), String::from("         {bar}")]
+    }], diagnostics);
+}
+
+#[test]
+fn parse_log_package_error() {
+    let log = "\
+(./main.tex
+! Package inputenc Error: Unicode character ą (U+0105)
+(inputenc)                not set up for use with LaTeX.
+
+See the inputenc package documentation for explanation.
+Type  H <return>  for immediate help.
+ ...
+
+l.7 Zażółć
+          gęślą jaźń
+";
+    let diagnostics = parse_log(log);
+
+    assert_eq!(1, diagnostics.len());
+    assert_eq!(Severity::Error, diagnostics[0].severity);
+    assert_eq!(Some(String::from("inputenc")), diagnostics[0].package);
+    assert_eq!(Some(7), diagnostics[0].line);
+    assert_eq!(Some(String::from("main.tex")), diagnostics[0].file);
+}
+
+#[test]
+fn parse_log_warnings_and_bad_boxes() {
+    let diagnostics = parse_log(&mock_warning_log());
+
+    assert_eq!(4, diagnostics.len());
+
+    assert_eq!(Severity::Warning, diagnostics[0].severity);
+    assert_eq!(Some(String::from("hyperref")), diagnostics[0].package);
+    assert_eq!(Some(12), diagnostics[0].line);
+    assert_eq!("Token not allowed in a PDF string (Unicode): removing `math shift' on input line 12.", diagnostics[0].message);
+
+    assert_eq!(Severity::Warning, diagnostics[1].severity);
+    assert_eq!(Some(17), diagnostics[1].line);
+    assert_eq!(None, diagnostics[1].package);
+
+    assert_eq!(Severity::Info, diagnostics[2].severity);
+    assert_eq!(Some(20), diagnostics[2].line);
+    assert_eq!(Some(String::from("main.tex")), diagnostics[2].file);
+
+    assert_eq!(Severity::Warning, diagnostics[3].severity);
+    assert_eq!(Some(String::from("main.tex")), diagnostics[3].file);
+}
+
+#[test]
+fn parse_log_skips_fatal_summary() {
+    let log = "\
+(./main.tex
+! Emergency stop.
+<*> main.tex
+
+!  ==> Fatal error occurred, no output PDF file produced!
+";
+    assert!(parse_log(log).is_empty());
+}
+
+#[test]
+fn parse_log_unwraps_long_lines() {
+    let path = format!("./{}.tex", "a".repeat(MAX_LINE_LENGTH - 3));
+    let log = format!("({}\n{}\n! Missing $ inserted.\nl.1 x\n\n)", &path[..MAX_LINE_LENGTH - 1], &path[MAX_LINE_LENGTH - 1..]);
+
+    let diagnostics = parse_log(&log);
+
+    assert_eq!(1, diagnostics.len());
+    assert_eq!(Some(path[2..].to_owned()), diagnostics[0].file);
+}
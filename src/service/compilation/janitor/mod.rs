@@ -0,0 +1,174 @@
+use std::{collections::HashSet, fs, io, path::{Path, PathBuf}, sync::{Arc, atomic::{AtomicU64, Ordering}}, time::{Duration, SystemTime}};
+
+use tracing::{error, info, warn};
+
+use super::queue::CompileQueue;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct SweepStats {
+    pub dirs_removed: u64,
+    pub bytes_reclaimed: u64,
+    pub failures: u64
+}
+
+#[derive(Debug, Default)]
+pub struct JanitorMetrics {
+    pub dirs_removed: AtomicU64,
+    pub bytes_reclaimed: AtomicU64
+}
+
+impl JanitorMetrics {
+    fn record(&self, stats: SweepStats) {
+        self.dirs_removed.fetch_add(stats.dirs_removed, Ordering::Relaxed);
+        self.bytes_reclaimed.fetch_add(stats.bytes_reclaimed, Ordering::Relaxed);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Janitor {
+    dir: PathBuf,
+    retention: Duration,
+    metrics: Arc<JanitorMetrics>,
+    queue: Option<CompileQueue>
+}
+
+impl Janitor {
+    pub fn new(dir: &Path, retention: Duration) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            retention,
+            metrics: Arc::new(JanitorMetrics::default()),
+            queue: None
+        }
+    }
+
+    // The directories of the queue's jobs are only removed when their jobs expire
+    pub fn with_queue(mut self, queue: CompileQueue) -> Self {
+        self.queue = Some(queue);
+        self
+    }
+
+    // No job survives a restart, so everything left over from a previous run is swept right away,
+    // before any new job can create its directories
+    pub fn spawn(self, startup_dirs: Vec<PathBuf>, interval: Duration) {
+        for dir in startup_dirs {
+            self.sweep_dir(&dir, Duration::ZERO, &HashSet::new());
+        }
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            loop {
+                interval.tick().await;
+                let janitor = self.clone();
+                if let Err(err) = tokio::task::spawn_blocking(move || janitor.sweep_jobs()).await {
+                    error!(%err);
+                }
+            }
+        });
+    }
+
+    fn sweep_jobs(&self) {
+        let keep = match &self.queue {
+            Some(queue) => {
+                let (expired, keep) = queue.expire(self.retention);
+                info!(expired, "Expired compile jobs");
+                keep
+            },
+            None => HashSet::new()
+        };
+        self.sweep_dir(&self.dir, self.retention, &keep);
+    }
+
+    fn sweep_dir(&self, dir: &Path, retention: Duration, keep: &HashSet<PathBuf>) {
+        match sweep(dir, retention, keep) {
+            Ok(stats) => {
+                self.metrics.record(stats);
+                info!(
+                    ?dir,
+                    stats.dirs_removed,
+                    stats.bytes_reclaimed,
+                    stats.failures,
+                    total_dirs_removed = self.metrics.dirs_removed.load(Ordering::Relaxed),
+                    total_bytes_reclaimed = self.metrics.bytes_reclaimed.load(Ordering::Relaxed),
+                    "Swept compile directories"
+                );
+            },
+            Err(err) if err.kind() == io::ErrorKind::NotFound => (),
+            Err(err) => error!(%err, ?dir, "Failed to sweep compile directories")
+        }
+    }
+}
+
+// An entry that cannot be removed is skipped, so that it does not keep the others around
+pub fn sweep(dir: &Path, retention: Duration, keep: &HashSet<PathBuf>) -> io::Result<SweepStats> {
+    let now = SystemTime::now();
+    let mut stats = SweepStats::default();
+
+    for entry in fs::read_dir(dir)? {
+        let path = match entry {
+            Ok(entry) => entry.path(),
+            Err(err) => {
+                warn!(%err, ?dir, "Failed to read compile directory entry");
+                stats.failures += 1;
+                continue;
+            }
+        };
+        if keep.contains(&path) {
+            continue;
+        }
+
+        match sweep_entry(&path, now, retention) {
+            Ok(Some(size)) => {
+                stats.dirs_removed += 1;
+                stats.bytes_reclaimed += size;
+            },
+            Ok(None) => (),
+            Err(err) => {
+                warn!(%err, ?path, "Failed to sweep compile directory entry");
+                stats.failures += 1;
+            }
+        }
+    }
+
+    Ok(stats)
+}
+
+fn sweep_entry(path: &Path, now: SystemTime, retention: Duration) -> io::Result<Option<u64>> {
+    let metadata = fs::symlink_metadata(path)?;
+    let age = now.duration_since(metadata.modified()?).unwrap_or_default();
+    if age < retention {
+        return Ok(None);
+    }
+
+    let size = disk_usage(path)?;
+    if metadata.is_dir() {
+        fs::remove_dir_all(path)?;
+    } else {
+        fs::remove_file(path)?;
+    }
+    Ok(Some(size))
+}
+
+pub fn remove_job_dir(path: &Path) {
+    if let Err(err) = fs::remove_dir_all(path) {
+        if err.kind() != io::ErrorKind::NotFound {
+            warn!(%err, ?path, "Failed to remove job directory");
+        }
+    }
+}
+
+fn disk_usage(path: &Path) -> io::Result<u64> {
+    let metadata = fs::symlink_metadata(path)?;
+    if !metadata.is_dir() {
+        return Ok(metadata.len());
+    }
+
+    let mut size = 0;
+    for entry in fs::read_dir(path)? {
+        size += disk_usage(&entry?.path())?;
+    }
+    Ok(size)
+}
+
+#[cfg(test)]
+mod tests;
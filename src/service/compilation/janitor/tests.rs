@@ -0,0 +1,51 @@
+use super::*;
+
+fn mock_dir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("agartex_janitor_{}", rand::random::<u32>()));
+    fs::create_dir_all(dir.join("job").join("out")).unwrap();
+    fs::write(dir.join("job").join("out").join("main.pdf"), [0; 100]).unwrap();
+    fs::write(dir.join("job").join("main.tex"), [0; 20]).unwrap();
+    fs::write(dir.join("stale.tex"), [0; 5]).unwrap();
+    dir
+}
+
+#[test]
+fn sweep_removes_stale_entries() {
+    let dir = mock_dir();
+
+    let stats = sweep(&dir, Duration::ZERO, &HashSet::new()).unwrap();
+
+    assert_eq!(SweepStats { dirs_removed: 2, bytes_reclaimed: 125, failures: 0 }, stats);
+    assert_eq!(0, fs::read_dir(&dir).unwrap().count());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn sweep_keeps_recent_entries() {
+    let dir = mock_dir();
+
+    let stats = sweep(&dir, Duration::from_secs(60 * 60), &HashSet::new()).unwrap();
+
+    assert_eq!(SweepStats::default(), stats);
+    assert_eq!(2, fs::read_dir(&dir).unwrap().count());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn sweep_keeps_listed_entries() {
+    let dir = mock_dir();
+
+    let stats = sweep(&dir, Duration::ZERO, &HashSet::from([dir.join("job")])).unwrap();
+
+    assert_eq!(SweepStats { dirs_removed: 1, bytes_reclaimed: 5, failures: 0 }, stats);
+    assert!(dir.join("job").is_dir());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn remove_job_dir_missing() {
+    remove_job_dir(&std::env::temp_dir().join(format!("agartex_janitor_missing_{}", rand::random::<u32>())));
+}
@@ -1,82 +1,556 @@
-use std::{path::PathBuf, fmt::Debug, fs};
+use std::{collections::{HashMap, HashSet}, path::{PathBuf, Path}, fmt::Debug, ffi::OsStr, fs, io, iter, os::unix::fs::PermissionsExt, sync::{Arc, Mutex}, time::{Duration, SystemTime}};
 
 use axum::async_trait;
-use tracing::error;
+use tracing::{error, warn, info};
 
-use crate::constants;
+use crate::{constants, repository::files::list_dir, domain::{files::is_relative_path, compile::{CompileOptions, CompileJob, Engine, JobOptions, OutputFormat, SourceFile, Diagnostic, CompileFailure, FailureKind}}};
 
-use super::execution::{ExecutionService, ProcessExecutionService, ProcessExecutionError};
+use self::{upload::UploadError, cache::OutputCache, bibliography::BibliographyTool};
+
+use super::execution::{ExecutionOutput, ExecutionService, OutputSender, ProcessExecutionError};
+
+pub mod upload;
+pub mod diagnostics;
+pub mod queue;
+pub mod cache;
+pub mod janitor;
+pub mod bibliography;
+pub mod synctex;
+pub mod progress;
+
+const BUILD_FLAGS_NAME: &str = ".build-flags";
+const HTML_DIR_NAME: &str = "html";
+const HTML_BUILD_DIR_NAME: &str = "html-build";
 
 #[async_trait]
 pub trait CompilationService {
     type CompileOptions;
     type CompilationError: Debug;
 
-    async fn compile(&self, options: Self::CompileOptions) -> Result<PathBuf, Self::CompilationError>;
+    async fn compile(&self, job: &CompileJob, options: Self::CompileOptions, output: OutputSender) -> Result<CompileOutput, Self::CompilationError>;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompileOutput {
+    pub pdf: PathBuf,
+    pub diagnostics: Vec<Diagnostic>,
+    pub artifacts: Vec<PathBuf>,
+    pub job_dir: Option<PathBuf>
+}
+
+impl CompileOutput {
+    // Artifacts are named by their path relative to the pdf, e.g. html/main.html
+    pub fn artifact_names(&self) -> Vec<String> {
+        iter::once(&self.pdf)
+            .chain(&self.artifacts)
+            .filter_map(|path| self.relative_name(path))
+            .map(|name| name.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    pub fn artifact(&self, name: &str) -> Option<&Path> {
+        iter::once(&self.pdf)
+            .chain(&self.artifacts)
+            .find(|path| self.relative_name(path) == Some(Path::new(name)))
+            .map(PathBuf::as_path)
+    }
+
+    fn relative_name<'a>(&self, path: &'a Path) -> Option<&'a Path> {
+        path.strip_prefix(self.pdf.parent()?).ok()
+    }
+}
+
+// Project builds reuse their build directory, so two of them must never run in it at once
+#[derive(Debug, Clone, Default)]
+struct BuildLocks {
+    locks: Arc<Mutex<HashMap<PathBuf, Arc<tokio::sync::Mutex<()>>>>>
+}
+
+impl BuildLocks {
+    fn get(&self, dir: &Path) -> Arc<tokio::sync::Mutex<()>> {
+        self.locks.lock().unwrap()
+            .entry(dir.to_path_buf())
+            .or_default()
+            .clone()
+    }
+
+    fn release(&self, dir: &Path) {
+        let mut locks = self.locks.lock().unwrap();
+        if matches!(locks.get(dir), Some(lock) if Arc::strong_count(lock) == 1) {
+            locks.remove(dir);
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct SimpleCompilationService<T: ExecutionService> {
     executor: T,
+    cache: OutputCache,
+    build_locks: BuildLocks
 }
 
 impl<T: ExecutionService> SimpleCompilationService<T> {
-    pub fn new(executor: T) -> Self {
-        fs::create_dir_all(*constants::COMPILE_DIR).unwrap();
-        Self {
-            executor
-        }
+    pub fn new(executor: T, cache: OutputCache, compile_dir: &Path) -> io::Result<Self> {
+        fs::create_dir_all(compile_dir)?;
+        fs::set_permissions(compile_dir, fs::Permissions::from_mode(0o700))?;
+        Ok(Self {
+            executor,
+            cache,
+            build_locks: BuildLocks::default()
+        })
     }
 }
 
 #[derive(Debug)]
 pub enum SimpleCompilationError {
     Unexpected,
-    Message(String)
+    MissingEntrypoint(String),
+    InvalidUpload(UploadError),
+    Timeout(Duration),
+    ResourceLimit(String),
+    Failed(CompileFailure)
+}
+
+impl From<SimpleCompilationError> for CompileFailure {
+    fn from(err: SimpleCompilationError) -> CompileFailure {
+        let (kind, message) = match err {
+            SimpleCompilationError::Unexpected => (FailureKind::Compilation, "UNKNOWN ERROR".to_owned()),
+            SimpleCompilationError::MissingEntrypoint(entrypoint) => (FailureKind::Compilation, format!("Entrypoint {} does not exist", entrypoint)),
+            SimpleCompilationError::InvalidUpload(err) => (FailureKind::Compilation, err.into()),
+            SimpleCompilationError::Timeout(timeout) => (FailureKind::Timeout, format!("Compilation timed out after {} seconds", timeout.as_secs())),
+            SimpleCompilationError::ResourceLimit(limit) => (FailureKind::ResourceLimit, limit),
+            SimpleCompilationError::Failed(failure) => return failure
+        };
+        CompileFailure { kind, message, diagnostics: Vec::new() }
+    }
+}
+
+#[derive(Debug)]
+pub enum CompileInput {
+    Text(String),
+    Files {
+        files: Vec<SourceFile>,
+        entrypoint: String
+    },
+    Project {
+        dir: PathBuf,
+        files: Vec<SourceFile>,
+        options: CompileOptions
+    }
+}
+
+impl From<String> for CompileInput {
+    fn from(raw_text: String) -> Self {
+        Self::Text(raw_text)
+    }
 }
 
-impl From<SimpleCompilationError> for String {
-    fn from(err: SimpleCompilationError) -> String {
-        match err {
-            SimpleCompilationError::Unexpected => "UNKNOWN ERROR".to_owned(),
-            SimpleCompilationError::Message(msg) => msg
+impl<T: ExecutionService<ExecutionError = ProcessExecutionError>> SimpleCompilationService<T> {
+    async fn compile_input(&self, job: &CompileJob, input: CompileInput, output: &OutputSender) -> Result<CompileOutput, SimpleCompilationError> {
+        match input {
+            CompileInput::Text(raw_text) => self.compile_text(job, raw_text, output).await,
+            CompileInput::Files { files, entrypoint } => self.compile_files(job, files, entrypoint, output).await,
+            CompileInput::Project { dir, files, options } => {
+                let lock = self.build_locks.get(&dir);
+                let result = {
+                    let _guard = lock.lock().await;
+                    self.compile_project(job, dir.clone(), files, options, output).await
+                };
+                drop(lock);
+                self.build_locks.release(&dir);
+                result
+            }
         }
     }
+
+    async fn run_latexmk(&self, input_path: &Path, output_path: &Path, flags: &[String], output: &OutputSender) -> Result<CompileOutput, SimpleCompilationError> {
+        let mut args = vec![format!("-outdir={}", path_str(output_path)?)];
+        args.extend_from_slice(flags);
+        args.push(path_str(input_path)?.to_owned());
+
+        let stem = file_stem(input_path)?;
+        let log_path = output_path.join(format!("{}.log", stem));
+
+        // A bibliography log left over from an earlier build would report stale diagnostics
+        let blg_path = log_path.with_extension("blg");
+        if blg_path.is_file() && !flags.iter().any(|flag| flag == "-bibtex") {
+            if let Err(err) = fs::remove_file(&blg_path) {
+                warn!(%err, "Failed to remove stale bibliography log");
+            }
+        }
+
+        match self.executor.execute(constants::LATEXMK_PATH, &args, Some(output.clone())).await {
+            Err(err) => Err(execution_error(err, &log_path)),
+            Ok(_) => Ok(CompileOutput {
+                pdf: output_path.join(format!("{}.pdf", stem)),
+                diagnostics: diagnostics::read_diagnostics(&log_path).unwrap_or_default(),
+                artifacts: Vec::new(),
+                job_dir: None
+            })
+        }
+    }
+
+    // make4ht has no equivalent of latexmk's -cd, so it runs in its own build directory and finds the sources through TEXINPUTS
+    async fn run_make4ht(&self, engine: Engine, input_path: &Path, output_path: &Path, output: &OutputSender) -> Result<(), SimpleCompilationError> {
+        let build_path = output_path.join(HTML_BUILD_DIR_NAME);
+        let html_path = output_path.join(HTML_DIR_NAME);
+        if let Err(err) = fs::create_dir_all(&build_path) {
+            error!(%err);
+            return Err(SimpleCompilationError::Unexpected);
+        }
+
+        let source_path = input_path.parent().ok_or(SimpleCompilationError::Unexpected)?;
+        let file_name = input_path.file_name().map(Path::new).ok_or(SimpleCompilationError::Unexpected)?;
+        let mut args = vec![
+            "-C".to_string(),
+            path_str(&build_path)?.to_owned(),
+            format!("TEXINPUTS={}:", path_str(source_path)?),
+            constants::MAKE4HT_PATH.to_string()
+        ];
+        args.extend(engine.make4ht_flag().map(str::to_string));
+        args.extend([
+            "-u".to_string(),
+            "-d".to_string(),
+            path_str(&html_path)?.to_owned(),
+            path_str(file_name)?.to_owned()
+        ]);
+
+        let stem = file_stem(input_path)?;
+        let log_path = build_path.join(format!("{}.log", stem));
+
+        self.executor.execute(constants::ENV_PATH, &args, Some(output.clone()))
+            .await
+            .map(|_| ())
+            .map_err(|err| execution_error(err, &log_path))
+    }
+
+    async fn run_cached(&self, options: &JobOptions, key: &str, input_path: &Path, output_path: &Path, flags: &[String], output: &OutputSender) -> Result<CompileOutput, SimpleCompilationError> {
+        let mut compiled = self.run_latexmk(input_path, output_path, flags, output).await?;
+        if options.formats.contains(&OutputFormat::Html) {
+            self.run_make4ht(options.engine, input_path, output_path, output).await?;
+        }
+        compiled.artifacts = collect_artifacts(&compiled.pdf, &options.formats);
+
+        let source_path = input_path.parent().ok_or(SimpleCompilationError::Unexpected)?;
+        if let Err(err) = self.cache.insert(key, &compiled, source_path) {
+            error!(%err, "Failed to cache compile output");
+        }
+        Ok(compiled)
+    }
+
+    async fn compile_text(&self, job: &CompileJob, raw_text: String, output: &OutputSender) -> Result<CompileOutput, SimpleCompilationError> {
+        let source = SourceFile { path: constants::DEFAULT_ENTRYPOINT.to_owned(), content: raw_text.into_bytes() };
+        self.compile_files(job, vec![source], constants::DEFAULT_ENTRYPOINT.to_owned(), output).await
+    }
+
+    async fn compile_files(&self, job: &CompileJob, files: Vec<SourceFile>, entrypoint: String, output: &OutputSender) -> Result<CompileOutput, SimpleCompilationError> {
+        if let Err(err) = upload::check_files(&files) {
+            return Err(SimpleCompilationError::InvalidUpload(err));
+        }
+
+        if !files.iter().any(|file| file.path == entrypoint) {
+            return Err(SimpleCompilationError::MissingEntrypoint(entrypoint));
+        }
+
+        let flags = latexmk_flags(&job.options, bibliography::detect(&files));
+        let key = cache::cache_key(&files, &entrypoint, &key_flags(&flags, &job.options));
+        let pdf_path = job.output_dir().join(format!("{}.pdf", file_stem(Path::new(&entrypoint))?));
+
+        // Fails instead of reusing the directory of another job
+        if let Err(err) = create_job_dir(&job.dir) {
+            error!(%err, ?job.dir);
+            return Err(SimpleCompilationError::Unexpected);
+        }
+
+        match self.cache.restore(&key, &pdf_path, &job.options.formats) {
+            Ok(Some(cached)) => return Ok(CompileOutput { job_dir: Some(job.dir.clone()), ..cached }),
+            Ok(None) => (),
+            Err(err) => warn!(%err, "Failed to restore cached build")
+        }
+
+        if let Err(err) = upload::write_files(&job.source_dir(), &files) {
+            error!(%err);
+            janitor::remove_job_dir(&job.dir);
+            return Err(SimpleCompilationError::Unexpected);
+        }
+
+        match self.run_cached(&job.options, &key, &job.source_dir().join(&entrypoint), &job.output_dir(), &flags, output).await {
+            Ok(compiled) => Ok(CompileOutput { job_dir: Some(job.dir.clone()), ..compiled }),
+            Err(err) => {
+                janitor::remove_job_dir(&job.dir);
+                Err(err)
+            }
+        }
+    }
+
+    async fn compile_project(&self, job: &CompileJob, dir: PathBuf, files: Vec<SourceFile>, options: CompileOptions, output: &OutputSender) -> Result<CompileOutput, SimpleCompilationError> {
+        let entrypoint = options.entrypoint
+            .unwrap_or_else(|| constants::DEFAULT_ENTRYPOINT.to_owned());
+        let source_path = dir.join("src");
+        let output_path = dir.join("out");
+        let input_path = source_path.join(&entrypoint);
+
+        // Runs under the build lock, so no other build of the project is reading the sources meanwhile
+        if let Some(file) = files.iter().find(|file| is_relative_path(&file.path).is_err()) {
+            return Err(SimpleCompilationError::InvalidUpload(UploadError::InvalidPath(file.path.clone())));
+        }
+        if let Err(err) = materialize(&source_path, &files) {
+            error!(%err);
+            return Err(SimpleCompilationError::Unexpected);
+        }
+
+        if let Some(text) = options.text {
+            if let Err(err) = write_if_changed(&input_path, text.as_bytes()) {
+                error!(%err);
+                return Err(SimpleCompilationError::Unexpected);
+            }
+        }
+
+        if !input_path.is_file() {
+            return Err(SimpleCompilationError::MissingEntrypoint(entrypoint));
+        }
+
+        let stem = file_stem(&input_path)?;
+        let pdf_path = output_path.join(format!("{}.pdf", stem));
+        let log_path = output_path.join(format!("{}.log", stem));
+
+        let files = match read_sources(&source_path) {
+            Ok(files) => files,
+            Err(err) => {
+                error!(%err);
+                return Err(SimpleCompilationError::Unexpected);
+            }
+        };
+        let flags = latexmk_flags(&job.options, bibliography::detect(&files));
+        let build_flags = key_flags(&flags, &job.options);
+        let key = cache::cache_key(&files, &entrypoint, &build_flags);
+
+        // The output only counts as up to date if it was also built with the same engine and formats
+        let flags_path = output_path.join(BUILD_FLAGS_NAME);
+        let recorded = build_flags.join("\n");
+        let stale = fs::read_to_string(&flags_path).ok().as_deref() != Some(recorded.as_str());
+
+        if !options.force && !stale && is_up_to_date(&pdf_path, &source_path) {
+            info!("Project sources unchanged, skipping compilation");
+            return Ok(CompileOutput {
+                artifacts: collect_artifacts(&pdf_path, &job.options.formats),
+                pdf: pdf_path,
+                diagnostics: diagnostics::read_diagnostics(&log_path).unwrap_or_default(),
+                job_dir: None
+            });
+        }
+
+        // The aux files of the previous run let latexmk skip passes, unless they were written with other flags
+        if options.force || stale {
+            info!(options.force, stale, "Clearing project build directory");
+            let cleared = clear_dir(&output_path)
+                .and_then(|_| fs::create_dir_all(&output_path))
+                .and_then(|_| fs::write(&flags_path, &recorded));
+            if let Err(err) = cleared {
+                error!(%err);
+                return Err(SimpleCompilationError::Unexpected);
+            }
+        }
+
+        if !options.force {
+            // Restoring the cached build into the project lets later requests see it as up to date
+            match self.cache.restore(&key, &pdf_path, &job.options.formats) {
+                Ok(Some(cached)) => return Ok(cached),
+                Ok(None) => (),
+                Err(err) => warn!(%err, "Failed to restore cached build")
+            }
+        }
+
+        self.run_cached(&job.options, &key, &input_path, &output_path, &flags, output).await
+    }
 }
 
 #[async_trait]
-impl CompilationService for SimpleCompilationService<ProcessExecutionService> {
-    type CompileOptions = String;
+impl<T> CompilationService for SimpleCompilationService<T>
+where
+    T: ExecutionService<ExecutionError = ProcessExecutionError> + Debug + Send + Sync
+{
+    type CompileOptions = CompileInput;
     type CompilationError = SimpleCompilationError;
     
-    #[tracing::instrument]
-    async fn compile(&self, raw_text: Self::CompileOptions) -> Result<PathBuf, Self::CompilationError> {
-        let rand_id = rand::random::<u32>();
-        
-        let input_path = constants::COMPILE_DIR
-            .join(format!("{}.tex", rand_id));
-
-        if let Err(err) = fs::write(&input_path, raw_text) {
+    #[tracing::instrument(skip(job, output), fields(job_id = %job.id))]
+    async fn compile(&self, job: &CompileJob, input: Self::CompileOptions, output: OutputSender) -> Result<CompileOutput, Self::CompilationError> {
+        self.compile_input(job, input, &output).await
+    }
+}
+
+fn execution_error(err: ProcessExecutionError, log_path: &Path) -> SimpleCompilationError {
+    match err {
+        ProcessExecutionError::Unknown => SimpleCompilationError::Unexpected,
+        ProcessExecutionError::Timeout(timeout, output) => {
+            warn!(?output.wall_time, ?output.cpu_time, "Compilation timed out");
+            SimpleCompilationError::Timeout(timeout)
+        },
+        ProcessExecutionError::ResourceLimit(limit, output) => {
+            warn!(?output.signal, ?output.cpu_time, limit, "Compilation hit a resource limit");
+            SimpleCompilationError::ResourceLimit(limit)
+        },
+        ProcessExecutionError::StatusError(output) => {
+            warn!(?output.exit_code, ?output.signal, ?output.wall_time, ?output.cpu_time, "Compilation failed");
+            SimpleCompilationError::Failed(CompileFailure {
+                kind: FailureKind::Compilation,
+                message: failure_message(&output),
+                diagnostics: diagnostics::read_diagnostics(log_path).unwrap_or_default()
+            })
+        }
+    }
+}
+
+// latexmk explains on stderr why it gave up, while the errors of TeX itself end up in the diagnostics
+fn failure_message(output: &ExecutionOutput) -> String {
+    let reason = match output.stderr.trim() {
+        "" => output.stdout.trim(),
+        stderr => stderr
+    };
+    match (output.exit_code, output.signal) {
+        (_, Some(signal)) => format!("Terminated by signal {}\n{}", signal, reason),
+        (Some(code), _) => format!("Exited with code {}\n{}", code, reason),
+        _ => reason.to_owned()
+    }
+}
+
+fn latexmk_flags(options: &JobOptions, bibliography: Option<BibliographyTool>) -> Vec<String> {
+    let mut flags = vec![
+        options.engine.latexmk_flag().to_string(),
+        "-norc".to_string(),
+        "-latexoption=-no-shell-escape".to_string(),
+        "-logfilewarninglist".to_string(),
+        "-cd".to_string()
+    ];
+    flags.extend(bibliography::latexmk_flags(bibliography));
+    flags.extend(options.formats.iter().filter_map(|format| format.latexmk_flag()).map(str::to_string));
+    flags
+}
+
+// Formats that are not produced by latexmk still change the output
+fn key_flags(flags: &[String], options: &JobOptions) -> Vec<String> {
+    let mut key_flags = flags.to_vec();
+    key_flags.extend(options.formats.iter().map(|format| format!("format={}", format.as_str())));
+    key_flags
+}
+
+fn collect_artifacts(pdf_path: &Path, formats: &[OutputFormat]) -> Vec<PathBuf> {
+    let mut artifacts = Vec::new();
+    for format in formats {
+        match format {
+            OutputFormat::Dvi => artifacts.push(pdf_path.with_extension("dvi")),
+            OutputFormat::Ps => artifacts.push(pdf_path.with_extension("ps")),
+            OutputFormat::Synctex => artifacts.push(pdf_path.with_extension("synctex.gz")),
+            OutputFormat::Log => artifacts.push(pdf_path.with_extension("log")),
+            OutputFormat::Html => {
+                let html_path = pdf_path.with_file_name(HTML_DIR_NAME);
+                if let Ok(files) = list_dir(&html_path) {
+                    artifacts.extend(files.into_iter().map(|file| html_path.join(file.path)));
+                }
+            }
+        }
+    }
+    artifacts.retain(|artifact| artifact.is_file());
+    artifacts
+}
+
+// Paths handed to the executor are joined from the compile directory and a checked relative path,
+// so these only fail if something upstream let a bad path through
+fn path_str(path: &Path) -> Result<&str, SimpleCompilationError> {
+    path.to_str().ok_or_else(|| {
+        error!(?path, "Path is not valid UTF-8");
+        SimpleCompilationError::Unexpected
+    })
+}
+
+fn file_stem(path: &Path) -> Result<&str, SimpleCompilationError> {
+    path.file_stem().and_then(OsStr::to_str).ok_or_else(|| {
+        error!(?path, "Path has no file name");
+        SimpleCompilationError::Unexpected
+    })
+}
+
+fn clear_dir(dir: &Path) -> io::Result<()> {
+    match fs::remove_dir_all(dir) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+        _ => Ok(())
+    }
+}
+
+fn create_job_dir(dir: &Path) -> io::Result<()> {
+    if let Some(parent) = dir.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::create_dir(dir)
+}
+
+fn is_up_to_date(pdf_path: &Path, source_path: &Path) -> bool {
+    let compiled = match fs::metadata(pdf_path).and_then(|metadata| metadata.modified()) {
+        Ok(compiled) => compiled,
+        Err(_) => return false
+    };
+
+    match latest_modification(source_path) {
+        Ok(modified) => modified <= compiled,
+        Err(err) => {
             error!(%err);
-            return Err(SimpleCompilationError::Unexpected);
+            false
         }
+    }
+}
 
-        let output_path = constants::COMPILE_DIR
-            .join(rand_id.to_string());
+fn latest_modification(path: &Path) -> io::Result<SystemTime> {
+    let metadata = fs::metadata(path)?;
+    let mut latest = metadata.modified()?;
+    if metadata.is_dir() {
+        for entry in fs::read_dir(path)? {
+            latest = latest.max(latest_modification(&entry?.path())?);
+        }
+    }
+    Ok(latest)
+}
 
-        let args = [
-            format!("-outdir={}", output_path.to_str().unwrap()),
-            "-pdf".to_string(),
-            "-logfilewarninglist".to_string(),
-            input_path.to_str().unwrap().to_owned()
-        ];
-        
-        match self.executor.execute(constants::LATEXMK_PATH, &args).await {
-            Err(ProcessExecutionError::Unknown) => return Err(SimpleCompilationError::Unexpected),
-            Err(ProcessExecutionError::StatusError(_, msg)) => return Err(SimpleCompilationError::Message(msg)),
-            Ok(_) => ()
-        };
+fn read_sources(source_path: &Path) -> io::Result<Vec<SourceFile>> {
+    list_dir(source_path)?
+        .into_iter()
+        .map(|file| Ok(SourceFile {
+            content: fs::read(source_path.join(&file.path))?,
+            path: file.path
+        }))
+        .collect()
+}
+
+fn materialize(source_path: &Path, files: &[SourceFile]) -> io::Result<()> {
+    upload::write_files(source_path, files)?;
 
-        return Ok(output_path.join(format!("{}.pdf", rand_id)));
+    let stored: HashSet<&str> = files.iter()
+        .map(|file| file.path.as_str())
+        .collect();
+    let existing = match list_dir(source_path) {
+        Ok(existing) => existing,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err)
+    };
+
+    for file in existing {
+        if !stored.contains(file.path.as_str()) {
+            fs::remove_file(source_path.join(&file.path))?;
+        }
+    }
+    Ok(())
+}
+
+pub fn write_if_changed(path: &Path, content: &[u8]) -> io::Result<()> {
+    if let Ok(current) = fs::read(path) {
+        if current == content {
+            return Ok(());
+        }
     }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, content)
 }
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,36 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::{domain::compile::JobEvent, service::execution::OutputLine};
+
+use super::diagnostics;
+
+lazy_static! {
+    static ref PASS_REGEX: Regex = Regex::new(r"^Run number \d+ of rule '([^']+)'").unwrap();
+}
+
+// Turns the output of latexmk into job events, numbering passes across all rules
+#[derive(Debug, Default)]
+pub struct Progress {
+    passes: u32
+}
+
+impl Progress {
+    pub fn events(&mut self, line: OutputLine) -> Vec<JobEvent> {
+        let mut events = Vec::new();
+        let text = line.text.trim();
+
+        if let Some(captures) = PASS_REGEX.captures(text) {
+            self.passes += 1;
+            events.push(JobEvent::Pass { number: self.passes, rule: captures[1].to_owned() });
+        } else if diagnostics::is_warning(text) {
+            events.push(JobEvent::Warning { message: text.to_owned() });
+        }
+
+        events.push(JobEvent::Log { stream: line.stream, line: line.text });
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests;
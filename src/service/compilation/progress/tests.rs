@@ -0,0 +1,44 @@
+use crate::domain::compile::LogStream;
+
+use super::*;
+
+fn mock_line(stream: LogStream, text: &str) -> OutputLine {
+    OutputLine { stream, text: String::from(text) }
+}
+
+#[test]
+fn events_numbers_passes() {
+    let mut progress = Progress::default();
+
+    let first = progress.events(mock_line(LogStream::Stderr, "Run number 1 of rule 'pdflatex'"));
+    progress.events(mock_line(LogStream::Stderr, "Run number 1 of rule 'bibtex main'"));
+    let third = progress.events(mock_line(LogStream::Stderr, "Run number 2 of rule 'pdflatex'"));
+
+    assert_eq!(JobEvent::Pass { number: 1, rule: String::from("pdflatex") }, first[0]);
+    assert_eq!(JobEvent::Pass { number: 3, rule: String::from("pdflatex") }, third[0]);
+    assert_eq!(2, first.len());
+}
+
+#[test]
+fn events_warning() {
+    let mut progress = Progress::default();
+
+    let events = progress.events(mock_line(LogStream::Stdout, "LaTeX Warning: Reference `fig:missing' on page 1 undefined on input line 17."));
+
+    assert_eq!(
+        vec![
+            JobEvent::Warning { message: String::from("LaTeX Warning: Reference `fig:missing' on page 1 undefined on input line 17.") },
+            JobEvent::Log { stream: LogStream::Stdout, line: String::from("LaTeX Warning: Reference `fig:missing' on page 1 undefined on input line 17.") }
+        ],
+        events
+    );
+}
+
+#[test]
+fn events_plain_log() {
+    let mut progress = Progress::default();
+
+    let events = progress.events(mock_line(LogStream::Stdout, "(./main.tex"));
+
+    assert_eq!(vec![JobEvent::Log { stream: LogStream::Stdout, line: String::from("(./main.tex") }], events);
+}
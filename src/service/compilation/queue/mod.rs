@@ -0,0 +1,336 @@
+use std::{collections::{HashMap, HashSet, VecDeque}, fmt::{self, Debug, Formatter}, path::PathBuf, sync::{Arc, Mutex}, time::Duration};
+
+use axum::async_trait;
+use mockall::automock;
+use tokio::sync::{Notify, broadcast, mpsc, watch};
+use tracing::{error, info};
+
+use chrono::Utc;
+
+use crate::{constants, domain::compile::{CompileFailure, CompileJob, FailureKind, JobEvent, JobId, JobOptions, JobReport, JobStatus}, service::execution::OutputLine};
+
+use super::{CompilationService, CompileInput, CompileOutput, janitor, progress::Progress};
+
+pub type JobResult = Result<CompileOutput, CompileFailure>;
+
+#[derive(Debug, PartialEq)]
+pub enum JobSubmitError {
+    UserLimit,
+    QueueFull
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QueueConfig {
+    pub workers: usize,
+    pub max_queued: usize,
+    pub max_active_per_user: usize,
+    pub max_finished: usize
+}
+
+impl Default for QueueConfig {
+    fn default() -> Self {
+        Self {
+            workers: constants::DEFAULT_COMPILE_WORKERS,
+            max_queued: constants::MAX_QUEUED_JOBS,
+            max_active_per_user: constants::MAX_ACTIVE_JOBS_PER_USER,
+            max_finished: constants::MAX_FINISHED_JOBS
+        }
+    }
+}
+
+#[automock]
+#[async_trait]
+pub trait JobService {
+    fn submit(&self, owner: Option<i32>, options: JobOptions, input: CompileInput) -> Result<JobId, JobSubmitError>;
+    fn status(&self, owner: Option<i32>, id: JobId) -> Option<JobReport>;
+    fn output(&self, owner: Option<i32>, id: JobId) -> Option<JobResult>;
+    // The status and the subscription are taken together, so that no event falls in between
+    fn events(&self, owner: Option<i32>, id: JobId) -> Option<(JobStatus, broadcast::Receiver<JobEvent>)>;
+    async fn wait(&self, owner: Option<i32>, id: JobId) -> Option<JobResult>;
+}
+
+struct Job {
+    job: CompileJob,
+    status: watch::Sender<JobStatus>,
+    events: broadcast::Sender<JobEvent>,
+    output: Option<CompileOutput>
+}
+
+#[derive(Default)]
+struct QueueState {
+    jobs: HashMap<JobId, Job>,
+    pending: HashMap<Option<i32>, VecDeque<(JobId, CompileInput)>>,
+    owners: VecDeque<Option<i32>>,
+    active: HashMap<Option<i32>, usize>,
+    queued: usize,
+    finished: VecDeque<JobId>
+}
+
+impl QueueState {
+    fn push(&mut self, owner: Option<i32>, options: JobOptions, input: CompileInput, config: &QueueConfig) -> Result<JobId, JobSubmitError> {
+        if self.queued >= config.max_queued {
+            return Err(JobSubmitError::QueueFull);
+        }
+
+        // Anonymous compilations only share the global limit
+        let active = self.active.entry(owner).or_default();
+        if owner.is_some() && *active >= config.max_active_per_user {
+            return Err(JobSubmitError::UserLimit);
+        }
+        *active += 1;
+
+        let job = CompileJob::new(owner, options, &constants::JOBS_DIR);
+        let id = job.id;
+        let (status, _) = watch::channel(JobStatus::Queued);
+        let (events, _) = broadcast::channel(constants::JOB_EVENTS_CAPACITY);
+        self.jobs.insert(id, Job { job, status, events, output: None });
+
+        let pending = self.pending.entry(owner).or_default();
+        if pending.is_empty() {
+            self.owners.push_back(owner);
+        }
+        pending.push_back((id, input));
+        self.queued += 1;
+
+        Ok(id)
+    }
+
+    // Owners with pending jobs take turns, so a single user cannot starve the others
+    fn pop(&mut self) -> Option<(CompileJob, CompileInput, broadcast::Sender<JobEvent>)> {
+        let owner = self.owners.pop_front()?;
+        let pending = self.pending.get_mut(&owner)?;
+        let (id, input) = pending.pop_front()?;
+
+        if pending.is_empty() {
+            self.pending.remove(&owner);
+        } else {
+            self.owners.push_back(owner);
+        }
+        self.queued -= 1;
+
+        let job = self.jobs.get_mut(&id)?;
+        job.job.started_at = Some(Utc::now());
+        job.status.send_replace(JobStatus::Running);
+        let _ = job.events.send(JobEvent::Started);
+        Some((job.job.clone(), input, job.events.clone()))
+    }
+
+    fn finish(&mut self, id: JobId, result: JobResult, config: &QueueConfig) {
+        let job = match self.jobs.get_mut(&id) {
+            Some(job) => job,
+            None => return
+        };
+
+        if let Some(active) = self.active.get_mut(&job.job.owner_id) {
+            *active -= 1;
+            if *active == 0 {
+                self.active.remove(&job.job.owner_id);
+            }
+        }
+        job.job.finished_at = Some(Utc::now());
+
+        let status = match result {
+            Ok(output) => {
+                let status = JobStatus::Succeeded {
+                    diagnostics: output.diagnostics.clone(),
+                    artifacts: output.artifact_names()
+                };
+                job.output = Some(output);
+                status
+            },
+            Err(failure) => JobStatus::Failed(failure)
+        };
+        let _ = job.events.send(JobEvent::Finished(status.clone()));
+        job.status.send_replace(status);
+
+        self.finished.push_back(id);
+        while self.finished.len() > config.max_finished {
+            self.remove_oldest_finished();
+        }
+    }
+
+    // Jobs go away together with their files, so no job is reported as succeeded without its output
+    fn expire(&mut self, retention: Duration) -> usize {
+        let cutoff = Utc::now() - chrono::Duration::from_std(retention).unwrap_or_else(|_| chrono::Duration::max_value());
+        let mut expired = 0;
+        while let Some(id) = self.finished.front() {
+            let finished_at = self.jobs.get(id).and_then(|job| job.job.finished_at);
+            if matches!(finished_at, Some(finished_at) if finished_at > cutoff) {
+                break;
+            }
+            self.remove_oldest_finished();
+            expired += 1;
+        }
+        expired
+    }
+
+    fn remove_oldest_finished(&mut self) {
+        let expired = self.finished.pop_front()
+            .and_then(|expired| self.jobs.remove(&expired))
+            .and_then(|job| job.output)
+            .and_then(|output| output.job_dir);
+        if let Some(job_dir) = expired {
+            janitor::remove_job_dir(&job_dir);
+        }
+    }
+
+    fn job_dirs(&self) -> HashSet<PathBuf> {
+        self.jobs.values().map(|job| job.job.dir.clone()).collect()
+    }
+
+    fn job(&self, owner: Option<i32>, id: JobId) -> Option<&Job> {
+        self.jobs.get(&id).filter(|job| job.job.owner_id == owner)
+    }
+}
+
+#[derive(Clone)]
+pub struct CompileQueue {
+    state: Arc<Mutex<QueueState>>,
+    notify: Arc<Notify>,
+    config: QueueConfig
+}
+
+impl Debug for CompileQueue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CompileQueue")
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+impl CompileQueue {
+    pub fn new<T>(service: T, config: QueueConfig) -> Self
+    where
+        T: CompilationService<CompileOptions = CompileInput> + Clone + Send + Sync + 'static,
+        T::CompilationError: Into<CompileFailure> + Send
+    {
+        let queue = Self {
+            state: Arc::new(Mutex::new(QueueState::default())),
+            notify: Arc::new(Notify::new()),
+            config
+        };
+
+        for worker in 0..config.workers {
+            tokio::spawn(queue.clone().work(worker, service.clone()));
+        }
+        queue
+    }
+
+    // Drops the jobs that finished longer than retention ago, and returns the directories of the others
+    pub fn expire(&self, retention: Duration) -> (usize, HashSet<PathBuf>) {
+        let mut state = self.state.lock().unwrap();
+        let expired = state.expire(retention);
+        (expired, state.job_dirs())
+    }
+
+    async fn work<T>(self, worker: usize, service: T)
+    where
+        T: CompilationService<CompileOptions = CompileInput> + Clone + Send + Sync + 'static,
+        T::CompilationError: Into<CompileFailure> + Send
+    {
+        info!(worker, "Compile worker started");
+        loop {
+            let next = self.state.lock().unwrap().pop();
+            let (job, input, events) = match next {
+                Some(job) => job,
+                None => {
+                    self.notify.notified().await;
+                    continue;
+                }
+            };
+
+            info!(worker, %job.id, "Running compile job");
+            let (output, lines) = mpsc::unbounded_channel();
+            let forward = tokio::spawn(forward_output(lines, events));
+            // A panicking compilation only fails its own job, instead of taking the worker down with it
+            let compile = {
+                let service = service.clone();
+                let job = job.clone();
+                tokio::spawn(async move {
+                    service.compile(&job, input, output).await.map_err(|err| {
+                        error!(?err);
+                        err.into()
+                    })
+                })
+            };
+            let result = match compile.await {
+                Ok(result) => result,
+                Err(err) => {
+                    error!(%err, "Compile job panicked");
+                    Err(CompileFailure {
+                        kind: FailureKind::Compilation,
+                        message: "UNKNOWN ERROR".to_owned(),
+                        diagnostics: Vec::new()
+                    })
+                }
+            };
+            // Every line has to be published before the job is reported as finished
+            if let Err(err) = forward.await {
+                error!(%err);
+            }
+
+            self.state.lock().unwrap().finish(job.id, result, &self.config);
+        }
+    }
+}
+
+async fn forward_output(mut lines: mpsc::UnboundedReceiver<OutputLine>, events: broadcast::Sender<JobEvent>) {
+    let mut progress = Progress::default();
+    while let Some(line) = lines.recv().await {
+        for event in progress.events(line) {
+            // Nobody may be listening, which is fine
+            let _ = events.send(event);
+        }
+    }
+}
+
+#[async_trait]
+impl JobService for CompileQueue {
+    fn submit(&self, owner: Option<i32>, options: JobOptions, input: CompileInput) -> Result<JobId, JobSubmitError> {
+        let id = self.state.lock().unwrap().push(owner, options, input, &self.config)?;
+        self.notify.notify_one();
+        Ok(id)
+    }
+
+    fn status(&self, owner: Option<i32>, id: JobId) -> Option<JobReport> {
+        let state = self.state.lock().unwrap();
+        let job = state.job(owner, id)?;
+        let status = job.status.borrow().clone();
+        Some(JobReport { job: job.job.clone(), status })
+    }
+
+    fn output(&self, owner: Option<i32>, id: JobId) -> Option<JobResult> {
+        let state = self.state.lock().unwrap();
+        let job = state.job(owner, id)?;
+        let result = match (&job.output, &*job.status.borrow()) {
+            (Some(output), _) => Ok(output.clone()),
+            (None, JobStatus::Failed(failure)) => Err(failure.clone()),
+            _ => return None
+        };
+        Some(result)
+    }
+
+    fn events(&self, owner: Option<i32>, id: JobId) -> Option<(JobStatus, broadcast::Receiver<JobEvent>)> {
+        let state = self.state.lock().unwrap();
+        let job = state.job(owner, id)?;
+        let status = job.status.borrow().clone();
+        Some((status, job.events.subscribe()))
+    }
+
+    async fn wait(&self, owner: Option<i32>, id: JobId) -> Option<JobResult> {
+        let mut status = self.state.lock().unwrap()
+            .job(owner, id)?
+            .status
+            .subscribe();
+
+        while !status.borrow_and_update().is_finished() {
+            if status.changed().await.is_err() {
+                break;
+            }
+        }
+        self.output(owner, id)
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,227 @@
+use std::{fs, path::PathBuf};
+
+use crate::domain::compile::{Engine, FailureKind, LogStream};
+
+use crate::service::execution::OutputSender;
+
+use super::*;
+
+#[derive(Debug, Clone)]
+struct StubCompilationService;
+
+#[async_trait]
+impl CompilationService for StubCompilationService {
+    type CompileOptions = CompileInput;
+    type CompilationError = CompileFailure;
+
+    async fn compile(&self, _job: &CompileJob, input: CompileInput, _output: OutputSender) -> Result<CompileOutput, CompileFailure> {
+        match input {
+            CompileInput::Text(text) if text == "fail" => Err(mock_failure()),
+            CompileInput::Text(text) if text == "panic" => panic!("compilation panicked"),
+            _ => Ok(mock_output())
+        }
+    }
+}
+
+fn mock_output() -> CompileOutput {
+    CompileOutput {
+        pdf: PathBuf::from("main.pdf"),
+        diagnostics: Vec::new(),
+        artifacts: Vec::new(),
+        job_dir: None
+    }
+}
+
+fn mock_failure() -> CompileFailure {
+    CompileFailure {
+        kind: FailureKind::Compilation,
+        message: String::from("failed"),
+        diagnostics: Vec::new()
+    }
+}
+
+fn mock_config() -> QueueConfig {
+    QueueConfig {
+        workers: 1,
+        max_queued: 4,
+        max_active_per_user: 2,
+        max_finished: 2
+    }
+}
+
+fn mock_input() -> CompileInput {
+    CompileInput::Text(String::from("text"))
+}
+
+#[test]
+fn pop_alternates_between_owners() {
+    let mut state = QueueState::default();
+    let config = QueueConfig { max_active_per_user: 8, ..mock_config() };
+
+    let first = state.push(Some(1), JobOptions::default(), mock_input(), &config).unwrap();
+    let second = state.push(Some(1), JobOptions::default(), mock_input(), &config).unwrap();
+    let third = state.push(Some(1), JobOptions::default(), mock_input(), &config).unwrap();
+    let other = state.push(Some(2), JobOptions::default(), mock_input(), &config).unwrap();
+
+    let order: Vec<JobId> = std::iter::from_fn(|| state.pop().map(|(job, _, _)| job.id)).collect();
+    assert_eq!(vec![first, other, second, third], order);
+}
+
+#[test]
+fn push_user_limit() {
+    let mut state = QueueState::default();
+
+    state.push(Some(1), JobOptions::default(), mock_input(), &mock_config()).unwrap();
+    state.push(Some(1), JobOptions::default(), mock_input(), &mock_config()).unwrap();
+
+    assert_eq!(Err(JobSubmitError::UserLimit), state.push(Some(1), JobOptions::default(), mock_input(), &mock_config()));
+    assert!(state.push(Some(2), JobOptions::default(), mock_input(), &mock_config()).is_ok());
+}
+
+#[test]
+fn push_queue_full() {
+    let mut state = QueueState::default();
+
+    for _ in 0..mock_config().max_queued {
+        state.push(None, JobOptions::default(), mock_input(), &mock_config()).unwrap();
+    }
+
+    assert_eq!(Err(JobSubmitError::QueueFull), state.push(Some(1), JobOptions::default(), mock_input(), &mock_config()));
+}
+
+#[test]
+fn finish_releases_user_limit() {
+    let mut state = QueueState::default();
+
+    let id = state.push(Some(1), JobOptions::default(), mock_input(), &mock_config()).unwrap();
+    state.push(Some(1), JobOptions::default(), mock_input(), &mock_config()).unwrap();
+    state.pop();
+    state.finish(id, Ok(mock_output()), &mock_config());
+
+    assert!(state.push(Some(1), JobOptions::default(), mock_input(), &mock_config()).is_ok());
+}
+
+#[test]
+fn finish_evicts_oldest_jobs() {
+    let mut state = QueueState::default();
+    let config = QueueConfig { max_active_per_user: 8, ..mock_config() };
+
+    let ids: Vec<JobId> = (0..3)
+        .map(|_| state.push(None, JobOptions::default(), mock_input(), &config).unwrap())
+        .collect();
+    for id in &ids {
+        state.pop();
+        state.finish(*id, Ok(mock_output()), &config);
+    }
+
+    assert!(state.job(None, ids[0]).is_none());
+    assert!(state.job(None, ids[2]).is_some());
+}
+
+#[test]
+fn expire_removes_old_jobs_with_their_dirs() {
+    let mut state = QueueState::default();
+    let job_dir = std::env::temp_dir().join(format!("agartex_queue_expire_{}", rand::random::<u32>()));
+    fs::create_dir_all(&job_dir).unwrap();
+
+    let old = state.push(Some(1), JobOptions::default(), mock_input(), &mock_config()).unwrap();
+    let running = state.push(Some(2), JobOptions::default(), mock_input(), &mock_config()).unwrap();
+    state.pop();
+    state.pop();
+    state.finish(old, Ok(CompileOutput { job_dir: Some(job_dir.clone()), ..mock_output() }), &mock_config());
+
+    assert_eq!(0, state.expire(Duration::from_secs(60 * 60)));
+    assert!(state.job(Some(1), old).is_some());
+
+    assert_eq!(1, state.expire(Duration::ZERO));
+    assert!(state.job(Some(1), old).is_none());
+    assert!(!job_dir.exists());
+    assert_eq!(HashSet::from([state.job(Some(2), running).unwrap().job.dir.clone()]), state.job_dirs());
+}
+
+#[test]
+fn push_assigns_unique_job_dirs() {
+    let mut state = QueueState::default();
+
+    let first = state.push(None, JobOptions::default(), mock_input(), &mock_config()).unwrap();
+    let second = state.push(None, JobOptions::default(), mock_input(), &mock_config()).unwrap();
+
+    assert_ne!(first, second);
+    assert_eq!(constants::JOBS_DIR.join(first.to_string()), state.job(None, first).unwrap().job.dir);
+}
+
+#[tokio::test]
+async fn wait_succeeded() {
+    let queue = CompileQueue::new(StubCompilationService, mock_config());
+
+    let id = queue.submit(Some(1), JobOptions::new(Engine::Xelatex, Vec::new()), mock_input()).unwrap();
+
+    assert_eq!(Some(Ok(mock_output())), queue.wait(Some(1), id).await);
+
+    let report = queue.status(Some(1), id).unwrap();
+    assert_eq!(JobStatus::Succeeded { diagnostics: Vec::new(), artifacts: vec![String::from("main.pdf")] }, report.status);
+    assert_eq!(id, report.job.id);
+    assert_eq!(Some(1), report.job.owner_id);
+    assert_eq!(Engine::Xelatex, report.job.options.engine);
+    assert!(report.job.started_at.is_some());
+    assert!(report.job.finished_at >= report.job.started_at);
+}
+
+#[tokio::test]
+async fn wait_failed() {
+    let queue = CompileQueue::new(StubCompilationService, mock_config());
+
+    let id = queue.submit(None, JobOptions::default(), CompileInput::Text(String::from("fail"))).unwrap();
+
+    assert_eq!(Some(Err(mock_failure())), queue.wait(None, id).await);
+}
+
+#[tokio::test]
+async fn wait_panicked() {
+    let queue = CompileQueue::new(StubCompilationService, mock_config());
+
+    let panicked = queue.submit(None, JobOptions::default(), CompileInput::Text(String::from("panic"))).unwrap();
+
+    assert!(matches!(queue.wait(None, panicked).await, Some(Err(CompileFailure { kind: FailureKind::Compilation, .. }))));
+    assert!(matches!(queue.status(None, panicked).unwrap().status, JobStatus::Failed(_)));
+
+    // The only worker survives the panic
+    let next = queue.submit(None, JobOptions::default(), mock_input()).unwrap();
+    assert_eq!(Some(Ok(mock_output())), queue.wait(None, next).await);
+}
+
+#[tokio::test]
+async fn status_other_owner() {
+    let queue = CompileQueue::new(StubCompilationService, mock_config());
+
+    let id = queue.submit(Some(1), JobOptions::default(), mock_input()).unwrap();
+
+    assert_eq!(None, queue.status(Some(2), id));
+    assert_eq!(None, queue.wait(None, id).await);
+}
+
+#[test]
+fn events_started_and_finished() {
+    let mut state = QueueState::default();
+
+    let id = state.push(Some(1), JobOptions::default(), mock_input(), &mock_config()).unwrap();
+    let mut events = state.job(Some(1), id).unwrap().events.subscribe();
+    state.pop();
+    state.finish(id, Err(mock_failure()), &mock_config());
+
+    assert_eq!(Ok(JobEvent::Started), events.try_recv());
+    assert_eq!(Ok(JobEvent::Finished(JobStatus::Failed(mock_failure()))), events.try_recv());
+}
+
+#[tokio::test]
+async fn forward_output_publishes_progress() {
+    let (output, lines) = mpsc::unbounded_channel();
+    let (events, mut receiver) = broadcast::channel(16);
+
+    output.send(OutputLine { stream: LogStream::Stderr, text: String::from("Run number 1 of rule 'pdflatex'") }).unwrap();
+    drop(output);
+    forward_output(lines, events).await;
+
+    assert_eq!(Ok(JobEvent::Pass { number: 1, rule: String::from("pdflatex") }), receiver.try_recv());
+    assert_eq!(Ok(JobEvent::Log { stream: LogStream::Stderr, line: String::from("Run number 1 of rule 'pdflatex'") }), receiver.try_recv());
+}
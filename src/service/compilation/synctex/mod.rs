@@ -0,0 +1,280 @@
+use std::{collections::HashMap, ffi::OsStr, fs::File, io::{self, BufRead, BufReader, Write}, path::{Component, Path, PathBuf}};
+
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+
+use crate::domain::compile::{PdfBox, SourceLocation};
+
+// TeX scaled points per PostScript point
+const SP_PER_BP: f64 = 65781.76;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RecordKind {
+    HBox,
+    VBox,
+    Point
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Record {
+    kind: RecordKind,
+    page: u32,
+    input: u32,
+    line: u32,
+    column: Option<u32>,
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+    depth: f64
+}
+
+impl Record {
+    fn contains(&self, x: f64, y: f64) -> bool {
+        x >= self.x && x <= self.x + self.width && y >= self.y - self.height && y <= self.y + self.depth
+    }
+
+    fn area(&self) -> f64 {
+        self.width * (self.height + self.depth)
+    }
+
+    fn to_box(&self) -> PdfBox {
+        PdfBox {
+            page: self.page,
+            x: self.x,
+            y: self.y - self.height,
+            width: self.width,
+            height: self.height + self.depth
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct SyncTex {
+    inputs: HashMap<u32, String>,
+    records: Vec<Record>
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Scale {
+    factor: f64,
+    x_offset: f64,
+    y_offset: f64
+}
+
+impl SyncTex {
+    // Inputs are reported relative to root; relative paths in the file are resolved against base, where TeX ran
+    pub fn read(path: &Path, base: &Path, root: &Path) -> io::Result<Self> {
+        let file = File::open(path)?;
+        if path.extension() == Some(OsStr::new("gz")) {
+            Self::parse(BufReader::new(GzDecoder::new(file)), base, root)
+        } else {
+            Self::parse(BufReader::new(file), base, root)
+        }
+    }
+
+    pub fn parse(mut reader: impl BufRead, base: &Path, root: &Path) -> io::Result<Self> {
+        let mut synctex = Self::default();
+        let mut unit = 1.0;
+        let mut magnification = 1000.0;
+        let mut x_offset = 0.0;
+        let mut y_offset = 0.0;
+        let mut scale = None;
+        let mut page = 0;
+
+        let mut buffer = Vec::new();
+        loop {
+            buffer.clear();
+            if reader.read_until(b'\n', &mut buffer)? == 0 {
+                break;
+            }
+            let line = String::from_utf8_lossy(&buffer);
+            let line = line.trim_end_matches(['\r', '\n']);
+
+            let scale = match scale {
+                Some(scale) => scale,
+                None => {
+                    if let Some(input) = line.strip_prefix("Input:") {
+                        if let Some((tag, path)) = input.split_once(':') {
+                            if let Ok(tag) = tag.parse() {
+                                synctex.inputs.insert(tag, normalize_input(path, base, root));
+                            }
+                        }
+                    } else if let Some(value) = line.strip_prefix("Unit:") {
+                        unit = value.trim().parse().unwrap_or(unit);
+                    } else if let Some(value) = line.strip_prefix("Magnification:") {
+                        magnification = value.trim().parse().unwrap_or(magnification);
+                    } else if let Some(value) = line.strip_prefix("X Offset:") {
+                        x_offset = value.trim().parse().unwrap_or(x_offset);
+                    } else if let Some(value) = line.strip_prefix("Y Offset:") {
+                        y_offset = value.trim().parse().unwrap_or(y_offset);
+                    } else if line.starts_with("Content:") {
+                        let factor = unit * magnification / 1000.0 / SP_PER_BP;
+                        scale = Some(Scale { factor, x_offset: x_offset * factor, y_offset: y_offset * factor });
+                    }
+                    continue;
+                }
+            };
+
+            if line.starts_with("Postamble:") {
+                break;
+            }
+
+            let mut chars = line.chars();
+            let kind = match chars.next() {
+                Some('{') => {
+                    page = chars.as_str().parse().unwrap_or(page);
+                    continue;
+                },
+                Some('(') | Some('h') => RecordKind::HBox,
+                Some('[') | Some('v') => RecordKind::VBox,
+                Some('x') | Some('k') | Some('g') | Some('$') => RecordKind::Point,
+                _ => continue
+            };
+
+            if let Some(record) = parse_record(kind, page, chars.as_str(), &scale) {
+                synctex.records.push(record);
+            }
+        }
+
+        Ok(synctex)
+    }
+
+    pub fn forward(&self, file: &str, line: u32, column: Option<u32>) -> Vec<PdfBox> {
+        let inputs: Vec<u32> = self.inputs.iter()
+            .filter(|(_, path)| path.as_str() == file)
+            .map(|(tag, _)| *tag)
+            .collect();
+        let candidates: Vec<&Record> = self.records.iter()
+            .filter(|record| inputs.contains(&record.input) && record.kind != RecordKind::VBox)
+            .collect();
+
+        // Not every source line produces a record, so fall back to the closest one that does
+        let closest = match candidates.iter().map(|record| record.line).min_by_key(|candidate| (candidate.abs_diff(line), *candidate)) {
+            Some(closest) => closest,
+            None => return Vec::new()
+        };
+        let mut matched: Vec<&Record> = candidates.into_iter()
+            .filter(|record| record.line == closest)
+            .collect();
+
+        if let Some(column) = column {
+            let nearest = matched.iter()
+                .filter_map(|record| record.column)
+                .min_by_key(|candidate| candidate.abs_diff(column));
+            if let Some(nearest) = nearest {
+                matched.retain(|record| record.column == Some(nearest));
+            }
+        }
+
+        if matched.iter().any(|record| record.kind == RecordKind::HBox) {
+            matched.retain(|record| record.kind == RecordKind::HBox);
+        }
+        let mut boxes: Vec<PdfBox> = matched.iter().map(|record| record.to_box()).collect();
+        boxes.dedup();
+        boxes
+    }
+
+    pub fn inverse(&self, page: u32, x: f64, y: f64) -> Option<SourceLocation> {
+        let on_page = self.records.iter().filter(|record| record.page == page);
+
+        let containing = on_page.clone()
+            .filter(|record| record.kind == RecordKind::HBox && record.contains(x, y))
+            .min_by(|a, b| a.area().total_cmp(&b.area()));
+        let record = match containing {
+            Some(record) => record,
+            None => on_page
+                .filter(|record| record.kind != RecordKind::VBox)
+                .min_by(|a, b| distance(a, x, y).total_cmp(&distance(b, x, y)))?
+        };
+
+        Some(SourceLocation {
+            file: self.inputs.get(&record.input)?.clone(),
+            line: record.line,
+            column: record.column
+        })
+    }
+}
+
+fn distance(record: &Record, x: f64, y: f64) -> f64 {
+    (record.x - x).powi(2) + (record.y - y).powi(2)
+}
+
+// Records look like tag,line[,column]:x,y[:width,height,depth]
+fn parse_record(kind: RecordKind, page: u32, record: &str, scale: &Scale) -> Option<Record> {
+    let (link, position) = record.split_once(':')?;
+    let mut link = link.split(',');
+    let input = link.next()?.parse().ok()?;
+    let line = link.next()?.parse().ok()?;
+    let column = link.next().and_then(|column| column.parse().ok());
+
+    let mut groups = position.split(':');
+    let (x, y) = groups.next()?.split_once(',')?;
+    let mut size = groups.next()
+        .map(|size| size.split(',').map(|value| value.parse::<f64>()).collect::<Result<Vec<_>, _>>())
+        .transpose()
+        .ok()?
+        .unwrap_or_default();
+    // Kerns only record their width
+    if kind == RecordKind::Point {
+        size.clear();
+    }
+    size.resize(3, 0.0);
+
+    Some(Record {
+        kind,
+        page,
+        input,
+        line,
+        column,
+        x: x.parse::<f64>().ok()? * scale.factor + scale.x_offset,
+        y: y.parse::<f64>().ok()? * scale.factor + scale.y_offset,
+        width: size[0].abs() * scale.factor,
+        height: size[1] * scale.factor,
+        depth: size[2] * scale.factor
+    })
+}
+
+// Copies a compressed SyncTeX file with its inputs below base made relative, so that it can be read
+// for a build of the same sources in another directory. Returns the size of the copy
+pub fn relativize(from: &Path, to: &Path, base: &Path) -> io::Result<u64> {
+    let mut reader = BufReader::new(GzDecoder::new(File::open(from)?));
+    let mut writer = GzEncoder::new(File::create(to)?, Compression::default());
+
+    let mut buffer = Vec::new();
+    while reader.read_until(b'\n', &mut buffer)? > 0 {
+        let line = String::from_utf8_lossy(&buffer);
+        match line.strip_prefix("Input:").and_then(|input| input.split_once(':')) {
+            Some((tag, path)) => {
+                let path = path.trim_end_matches(['\r', '\n']);
+                let relative = normalize_input(path, base, base);
+                writer.write_all(format!("Input:{}:{}\n", tag, relative).as_bytes())?;
+            },
+            None => writer.write_all(&buffer)?
+        }
+        buffer.clear();
+    }
+
+    writer.finish()?.metadata().map(|metadata| metadata.len())
+}
+
+// Inputs outside of root, like the classes of the TeX tree, keep only their file name, so host paths are never exposed
+fn normalize_input(path: &str, base: &Path, root: &Path) -> String {
+    let path = Path::new(path);
+    let path = if path.is_absolute() { path.to_path_buf() } else { base.join(path) };
+    let path: PathBuf = path.components()
+        .filter(|component| *component != Component::CurDir)
+        .collect();
+
+    match path.strip_prefix(root) {
+        Ok(relative) => relative.components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/"),
+        Err(_) => path.file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,150 @@
+use super::*;
+
+fn mock_synctex() -> String {
+    String::from("\
+SyncTeX Version:1
+Input:1:/project/src/./main.tex
+Input:2:chapters/intro.tex
+Input:3:/usr/share/texlive/texmf-dist/tex/latex/base/article.cls
+Output:pdf
+Magnification:1000
+Unit:1
+X Offset:0
+Y Offset:0
+Content:
+!180
+{1
+[1,3:0,0:39469056,52626304,0
+(1,5:6578176,13156352:32890880,657818,197345
+x1,5:9867264,13156352
+k1,5:9867264,13156352:65782
+]
+(2,2:6578176,26312704:32890880,657818,197345
+g2,3:6578176,32890880
+}1
+{2
+(2,10:6578176,13156352:32890880,657818,197345
+}2
+Postamble:
+Count:8
+!420
+Post scriptum:
+")
+}
+
+fn mock_parsed() -> SyncTex {
+    SyncTex::parse(mock_synctex().as_bytes(), Path::new("/project/src"), Path::new("/project/src")).unwrap()
+}
+
+fn assert_close(expected: f64, actual: f64) {
+    assert!((expected - actual).abs() < 0.01, "expected {}, got {}", expected, actual);
+}
+
+#[test]
+fn parse_inputs() {
+    let synctex = mock_parsed();
+
+    assert_eq!(Some(&String::from("main.tex")), synctex.inputs.get(&1));
+    assert_eq!(Some(&String::from("chapters/intro.tex")), synctex.inputs.get(&2));
+    assert_eq!(Some(&String::from("article.cls")), synctex.inputs.get(&3));
+    assert_eq!(7, synctex.records.len());
+}
+
+#[test]
+fn forward_exact_line() {
+    let boxes = mock_parsed().forward("main.tex", 5, None);
+
+    assert_eq!(1, boxes.len());
+    assert_eq!(1, boxes[0].page);
+    assert_close(100.0, boxes[0].x);
+    assert_close(190.0, boxes[0].y);
+    assert_close(500.0, boxes[0].width);
+    assert_close(13.0, boxes[0].height);
+}
+
+#[test]
+fn forward_closest_line() {
+    let boxes = mock_parsed().forward("main.tex", 4, None);
+
+    assert_eq!(1, boxes.len());
+    assert_close(190.0, boxes[0].y);
+}
+
+#[test]
+fn forward_points_only() {
+    let boxes = mock_parsed().forward("chapters/intro.tex", 3, None);
+
+    assert_eq!(1, boxes.len());
+    assert_close(500.0, boxes[0].y);
+    assert_close(0.0, boxes[0].height);
+}
+
+#[test]
+fn forward_unknown_file() {
+    assert!(mock_parsed().forward("missing.tex", 1, None).is_empty());
+}
+
+#[test]
+fn inverse_containing_box() {
+    let synctex = mock_parsed();
+
+    assert_eq!(
+        Some(SourceLocation { file: String::from("main.tex"), line: 5, column: None }),
+        synctex.inverse(1, 300.0, 195.0)
+    );
+    assert_eq!(
+        Some(SourceLocation { file: String::from("chapters/intro.tex"), line: 2, column: None }),
+        synctex.inverse(1, 300.0, 398.0)
+    );
+}
+
+#[test]
+fn inverse_nearest_record() {
+    assert_eq!(
+        Some(SourceLocation { file: String::from("chapters/intro.tex"), line: 10, column: None }),
+        mock_parsed().inverse(2, 0.0, 0.0)
+    );
+}
+
+#[test]
+fn inverse_missing_page() {
+    assert_eq!(None, mock_parsed().inverse(3, 0.0, 0.0));
+}
+
+#[test]
+fn read_compressed() {
+    use std::io::Write;
+    use flate2::{write::GzEncoder, Compression};
+
+    let path = std::env::temp_dir().join(format!("agartex_synctex_{}.synctex.gz", rand::random::<u32>()));
+    let mut encoder = GzEncoder::new(File::create(&path).unwrap(), Compression::default());
+    encoder.write_all(mock_synctex().as_bytes()).unwrap();
+    encoder.finish().unwrap();
+
+    let synctex = SyncTex::read(&path, Path::new("/project/src"), Path::new("/project/src")).unwrap();
+
+    assert_eq!(7, synctex.records.len());
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn relativize_hides_outside_inputs() {
+    use std::io::{Read, Write};
+    use flate2::{write::GzEncoder, Compression};
+
+    let from = std::env::temp_dir().join(format!("agartex_synctex_{}.synctex.gz", rand::random::<u32>()));
+    let to = from.with_extension("copy.gz");
+    let mut encoder = GzEncoder::new(File::create(&from).unwrap(), Compression::default());
+    encoder.write_all(mock_synctex().as_bytes()).unwrap();
+    encoder.finish().unwrap();
+
+    relativize(&from, &to, Path::new("/project/src")).unwrap();
+
+    let mut copy = String::new();
+    GzDecoder::new(File::open(&to).unwrap()).read_to_string(&mut copy).unwrap();
+    assert!(copy.contains("Input:1:main.tex\n"));
+    assert!(copy.contains("Input:3:article.cls\n"));
+    assert!(!copy.contains("/usr/share"));
+    std::fs::remove_file(from).unwrap();
+    std::fs::remove_file(to).unwrap();
+}
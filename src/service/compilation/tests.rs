@@ -0,0 +1,576 @@
+use tempfile::TempDir;
+use tokio::sync::mpsc;
+
+use crate::service::execution::{MockExecutionService, tests::{FakeExecutionService, fake_output}};
+
+use super::*;
+
+fn mock_dir(name: &str) -> TempDir {
+    tempfile::Builder::new().prefix(&format!("agartex_compile_{}_", name)).tempdir().unwrap()
+}
+
+fn mock_service<T: ExecutionService>(executor: T, dir: &Path) -> SimpleCompilationService<T> {
+    SimpleCompilationService::new(executor, OutputCache::new(&dir.join("cache"), constants::MAX_CACHE_BYTES).unwrap(), dir).unwrap()
+}
+
+fn mock_output() -> OutputSender {
+    mpsc::unbounded_channel().0
+}
+
+fn mock_job(dir: &Path) -> CompileJob {
+    CompileJob::new(Some(1), JobOptions::default(), &dir.join("jobs"))
+}
+
+// The cache is keyed by content, so every test compiles a document of its own
+fn mock_text(job: &CompileJob) -> String {
+    format!("\\documentclass{{article}}\\begin{{document}}{}\\end{{document}}", job.id)
+}
+
+fn mock_execution_output(exit_code: i32, stdout: &str, stderr: &str) -> ExecutionOutput {
+    ExecutionOutput {
+        stderr: stderr.to_owned(),
+        exit_code: Some(exit_code),
+        ..fake_output(stdout)
+    }
+}
+
+fn write_latexmk_output(args: &[String]) {
+    let outdir = Path::new(args.iter().find_map(|arg| arg.strip_prefix("-outdir=")).unwrap());
+    let input = Path::new(args.last().unwrap());
+    fs::create_dir_all(outdir).unwrap();
+    fs::write(outdir.join(input.with_extension("pdf").file_name().unwrap()), b"%PDF-1.5").unwrap();
+    fs::write(outdir.join(input.with_extension("log").file_name().unwrap()), b"").unwrap();
+}
+
+#[tokio::test]
+async fn build_locks_serialize_same_dir() {
+    let locks = BuildLocks::default();
+    let dir = PathBuf::from("/projects/1");
+
+    let lock = locks.get(&dir);
+    let _guard = lock.lock().await;
+
+    assert!(locks.get(&dir).try_lock().is_err());
+    assert!(locks.get(Path::new("/projects/2")).try_lock().is_ok());
+}
+
+#[tokio::test]
+async fn build_locks_release_unused() {
+    let locks = BuildLocks::default();
+    let dir = PathBuf::from("/projects/1");
+
+    let lock = locks.get(&dir);
+    locks.release(&dir);
+    assert!(locks.locks.lock().unwrap().contains_key(&dir));
+
+    drop(lock);
+    locks.release(&dir);
+    assert!(!locks.locks.lock().unwrap().contains_key(&dir));
+}
+
+#[tokio::test]
+async fn simple_impl_compile_text_fake_executor() {
+    let temp = mock_dir("fake");
+    let dir = temp.path();
+    let executor = FakeExecutionService::latexmk();
+    let service = mock_service(executor.clone(), dir);
+    let job = CompileJob::new(None, JobOptions::default(), &dir.join("jobs"));
+
+    let compiled = service.compile(&job, CompileInput::Text(format!("fake {}", job.id)), mock_output()).await.unwrap();
+
+    assert_eq!(job.output_dir().join("main.pdf"), compiled.pdf);
+    assert!(compiled.pdf.is_file());
+    assert_eq!(constants::LATEXMK_PATH, executor.calls()[0][0]);
+}
+
+#[tokio::test]
+async fn simple_impl_compile_text_fake_failure() {
+    let temp = mock_dir("fake_failure");
+    let dir = temp.path();
+    let executor = FakeExecutionService::new(|_| Err(ProcessExecutionError::StatusError(ExecutionOutput { exit_code: Some(12), ..fake_output("! Undefined control sequence.") })));
+    let service = mock_service(executor, dir);
+    let job = CompileJob::new(None, JobOptions::default(), &dir.join("jobs"));
+
+    let result = service.compile(&job, CompileInput::Text(format!("fake {}", job.id)), mock_output()).await;
+
+    match result {
+        Err(SimpleCompilationError::Failed(failure)) => assert_eq!("Exited with code 12\n! Undefined control sequence.", failure.message),
+        other => panic!("unexpected result {:?}", other)
+    }
+    assert!(!job.dir.exists());
+}
+
+#[tokio::test]
+async fn simple_impl_compile_text_normal() {
+    let temp = mock_dir("text_normal");
+    let dir = temp.path();
+    let job = mock_job(dir);
+    let mut executor = MockExecutionService::new();
+
+    let outdir = format!("-outdir={}", job.output_dir().to_str().unwrap());
+    let input = job.source_dir().join("main.tex").to_str().unwrap().to_owned();
+    executor
+        .expect_execute::<&'static str, String>()
+        .withf(move |comm, args, _| *comm == constants::LATEXMK_PATH && args.first() == Some(&outdir) && args.last() == Some(&input))
+        .times(1)
+        .returning(|_, args, _| {
+            write_latexmk_output(args);
+            Ok(fake_output(""))
+        });
+
+    let service = mock_service(executor, dir);
+    let compiled = service.compile(&job, CompileInput::Text(mock_text(&job)), mock_output()).await.unwrap();
+
+    assert_eq!(job.output_dir().join("main.pdf"), compiled.pdf);
+    assert_eq!(Some(job.dir.clone()), compiled.job_dir);
+    assert_eq!(b"%PDF-1.5".to_vec(), fs::read(&compiled.pdf).unwrap());
+}
+
+#[tokio::test]
+async fn simple_impl_compile_text_cached() {
+    let temp = mock_dir("text_cached");
+    let dir = temp.path();
+    let job = mock_job(dir);
+    let mut executor = MockExecutionService::new();
+
+    executor
+        .expect_execute::<&'static str, String>()
+        .times(1)
+        .returning(|_, args, _| {
+            write_latexmk_output(args);
+            Ok(fake_output(""))
+        });
+
+    let service = mock_service(executor, dir);
+    let compiled = service.compile(&job, CompileInput::Text(mock_text(&job)), mock_output()).await.unwrap();
+    let other = mock_job(dir);
+    let cached = service.compile(&other, CompileInput::Text(mock_text(&job)), mock_output()).await.unwrap();
+
+    assert_eq!(other.output_dir().join("main.pdf"), cached.pdf);
+    assert_eq!(compiled.artifact_names(), cached.artifact_names());
+    assert_eq!(Some(other.dir.clone()), cached.job_dir);
+    assert!(cached.pdf.is_file());
+}
+
+#[tokio::test]
+async fn simple_impl_compile_text_compilation_failure() {
+    let temp = mock_dir("text_failure");
+    let dir = temp.path();
+    let job = mock_job(dir);
+    let mut executor = MockExecutionService::new();
+
+    executor
+        .expect_execute::<&'static str, String>()
+        .times(1)
+        .returning(|_, _, _| Err(ProcessExecutionError::StatusError(mock_execution_output(12, "! Undefined control sequence.", "Latexmk: Errors, so I did not complete making targets"))));
+
+    let service = mock_service(executor, dir);
+    let result = service.compile(&job, CompileInput::Text(mock_text(&job)), mock_output()).await;
+
+    match result {
+        Err(SimpleCompilationError::Failed(failure)) => {
+            assert_eq!(FailureKind::Compilation, failure.kind);
+            assert_eq!("Exited with code 12\nLatexmk: Errors, so I did not complete making targets", failure.message);
+        },
+        other => panic!("unexpected result {:?}", other)
+    }
+    assert!(!job.dir.exists());
+}
+
+#[tokio::test]
+async fn simple_impl_compile_text_timeout() {
+    let temp = mock_dir("text_timeout");
+    let dir = temp.path();
+    let job = mock_job(dir);
+    let mut executor = MockExecutionService::new();
+
+    executor
+        .expect_execute::<&'static str, String>()
+        .times(1)
+        .returning(|_, _, _| Err(ProcessExecutionError::Timeout(Duration::from_secs(60), fake_output(""))));
+
+    let service = mock_service(executor, dir);
+    let result = service.compile(&job, CompileInput::Text(mock_text(&job)), mock_output()).await;
+
+    assert!(matches!(result, Err(SimpleCompilationError::Timeout(timeout)) if timeout == Duration::from_secs(60)));
+}
+
+#[tokio::test]
+async fn simple_impl_compile_text_resource_limit() {
+    let temp = mock_dir("text_limit");
+    let dir = temp.path();
+    let job = mock_job(dir);
+    let mut executor = MockExecutionService::new();
+
+    executor
+        .expect_execute::<&'static str, String>()
+        .times(1)
+        .returning(|_, _, _| Err(ProcessExecutionError::ResourceLimit(String::from("Memory limit exceeded"), fake_output(""))));
+
+    let service = mock_service(executor, dir);
+    let result = service.compile(&job, CompileInput::Text(mock_text(&job)), mock_output()).await;
+
+    assert!(matches!(result, Err(SimpleCompilationError::ResourceLimit(limit)) if limit == "Memory limit exceeded"));
+}
+
+#[tokio::test]
+async fn simple_impl_compile_text_execution_error() {
+    let temp = mock_dir("text_execution_error");
+    let dir = temp.path();
+    let job = mock_job(dir);
+    let mut executor = MockExecutionService::new();
+
+    executor
+        .expect_execute::<&'static str, String>()
+        .times(1)
+        .returning(|_, _, _| Err(ProcessExecutionError::Unknown));
+
+    let service = mock_service(executor, dir);
+    let result = service.compile(&job, CompileInput::Text(mock_text(&job)), mock_output()).await;
+
+    assert!(matches!(result, Err(SimpleCompilationError::Unexpected)));
+    assert!(!job.dir.exists());
+}
+
+#[tokio::test]
+async fn simple_impl_compile_text_existing_job_dir() {
+    let temp = mock_dir("text_existing_dir");
+    let dir = temp.path();
+    let job = mock_job(dir);
+    let mut executor = MockExecutionService::new();
+
+    executor
+        .expect_execute::<&'static str, String>()
+        .times(0);
+
+    fs::create_dir_all(&job.dir).unwrap();
+    let service = mock_service(executor, dir);
+    let result = service.compile(&job, CompileInput::Text(mock_text(&job)), mock_output()).await;
+
+    assert!(matches!(result, Err(SimpleCompilationError::Unexpected)));
+    assert!(job.dir.exists());
+}
+
+#[tokio::test]
+async fn simple_impl_compile_files_nested_entrypoint() {
+    let temp = mock_dir("files_nested");
+    let dir = temp.path();
+    let job = mock_job(dir);
+    let mut executor = MockExecutionService::new();
+    let files = vec![
+        SourceFile { path: String::from("chapters/thesis.tex"), content: mock_text(&job).into_bytes() },
+        SourceFile { path: String::from("chapters/intro.tex"), content: b"Intro".to_vec() }
+    ];
+
+    let input = job.source_dir().join("chapters").join("thesis.tex").to_str().unwrap().to_owned();
+    executor
+        .expect_execute::<&'static str, String>()
+        .withf(move |_, args, _| args.last() == Some(&input) && args.contains(&String::from("-cd")))
+        .times(1)
+        .returning(|_, args, _| {
+            write_latexmk_output(args);
+            Ok(fake_output(""))
+        });
+
+    let service = mock_service(executor, dir);
+    let compiled = service.compile(&job, CompileInput::Files { files, entrypoint: String::from("chapters/thesis.tex") }, mock_output()).await.unwrap();
+
+    assert_eq!(job.output_dir().join("thesis.pdf"), compiled.pdf);
+    assert_eq!(b"Intro".to_vec(), fs::read(job.source_dir().join("chapters").join("intro.tex")).unwrap());
+}
+
+#[tokio::test]
+async fn simple_impl_compile_files_missing_entrypoint() {
+    let temp = mock_dir("files_missing");
+    let dir = temp.path();
+    let job = mock_job(dir);
+    let mut executor = MockExecutionService::new();
+    let files = vec![SourceFile { path: String::from("main.tex"), content: mock_text(&job).into_bytes() }];
+
+    executor
+        .expect_execute::<&'static str, String>()
+        .times(0);
+
+    let service = mock_service(executor, dir);
+    let result = service.compile(&job, CompileInput::Files { files, entrypoint: String::from("thesis.tex") }, mock_output()).await;
+
+    assert!(matches!(result, Err(SimpleCompilationError::MissingEntrypoint(entrypoint)) if entrypoint == "thesis.tex"));
+    assert!(!job.dir.exists());
+}
+
+#[tokio::test]
+async fn simple_impl_compile_files_path_traversal() {
+    let temp = mock_dir("files_traversal");
+    let dir = temp.path();
+    let job = mock_job(dir);
+    let mut executor = MockExecutionService::new();
+    let files = vec![
+        SourceFile { path: String::from("main.tex"), content: mock_text(&job).into_bytes() },
+        SourceFile { path: String::from("../escape.tex"), content: b"escape".to_vec() }
+    ];
+
+    executor
+        .expect_execute::<&'static str, String>()
+        .times(0);
+
+    let service = mock_service(executor, dir);
+    let result = service.compile(&job, CompileInput::Files { files, entrypoint: String::from("main.tex") }, mock_output()).await;
+
+    assert!(matches!(result, Err(SimpleCompilationError::InvalidUpload(UploadError::InvalidPath(_)))));
+    assert!(!job.dir.exists());
+}
+
+#[tokio::test]
+async fn simple_impl_compile_project_missing_entrypoint() {
+    let temp = mock_dir("project_missing");
+    let dir = temp.path();
+    let job = mock_job(dir);
+    let mut executor = MockExecutionService::new();
+    let options = CompileOptions {
+        entrypoint: Some(String::from("thesis.tex")),
+        force: false,
+        text: None,
+        engine: None,
+        formats: Vec::new()
+    };
+
+    executor
+        .expect_execute::<&'static str, String>()
+        .times(0);
+
+    let service = mock_service(executor, dir);
+    let result = service.compile(&job, CompileInput::Project { dir: dir.join("project"), files: Vec::new(), options }, mock_output()).await;
+
+    assert!(matches!(result, Err(SimpleCompilationError::MissingEntrypoint(entrypoint)) if entrypoint == "thesis.tex"));
+}
+
+#[tokio::test]
+async fn simple_impl_compile_project_materializes_sources() {
+    let temp = mock_dir("project_materialize");
+    let dir = temp.path();
+    let job = mock_job(dir);
+    let mut executor = MockExecutionService::new();
+    let options = CompileOptions {
+        entrypoint: Some(String::from("thesis.tex")),
+        force: false,
+        text: None,
+        engine: None,
+        formats: Vec::new()
+    };
+    let files = vec![SourceFile { path: String::from("chapters/intro.tex"), content: b"intro".to_vec() }];
+    fs::create_dir_all(dir.join("project/src")).unwrap();
+    fs::write(dir.join("project/src/stale.tex"), "stale").unwrap();
+
+    executor
+        .expect_execute::<&'static str, String>()
+        .times(0);
+
+    let service = mock_service(executor, dir);
+    let result = service.compile(&job, CompileInput::Project { dir: dir.join("project"), files, options }, mock_output()).await;
+
+    assert!(matches!(result, Err(SimpleCompilationError::MissingEntrypoint(_))));
+    assert_eq!(b"intro".to_vec(), fs::read(dir.join("project/src/chapters/intro.tex")).unwrap());
+    assert!(!dir.join("project/src/stale.tex").exists());
+}
+
+#[tokio::test]
+async fn simple_impl_compile_project_path_traversal() {
+    let temp = mock_dir("project_traversal");
+    let dir = temp.path();
+    let job = mock_job(dir);
+    let mut executor = MockExecutionService::new();
+    let options = CompileOptions {
+        entrypoint: None,
+        force: false,
+        text: None,
+        engine: None,
+        formats: Vec::new()
+    };
+    let files = vec![SourceFile { path: String::from("../escaped.tex"), content: Vec::new() }];
+
+    executor
+        .expect_execute::<&'static str, String>()
+        .times(0);
+
+    let service = mock_service(executor, dir);
+    let result = service.compile(&job, CompileInput::Project { dir: dir.join("project"), files, options }, mock_output()).await;
+
+    assert!(matches!(result, Err(SimpleCompilationError::InvalidUpload(UploadError::InvalidPath(_)))));
+    assert!(!dir.join("project/escaped.tex").exists());
+}
+
+fn write_synctex_output(args: &[String]) {
+    use std::io::Write;
+    use flate2::{write::GzEncoder, Compression};
+
+    write_latexmk_output(args);
+    let outdir = Path::new(args.iter().find_map(|arg| arg.strip_prefix("-outdir=")).unwrap());
+    let input = Path::new(args.last().unwrap());
+    let synctex = format!(
+        "SyncTeX Version:1\nInput:1:{}/./main.tex\nOutput:pdf\nMagnification:1000\nUnit:1\nX Offset:0\nY Offset:0\nContent:\n{{1\n(1,5:6578176,13156352:32890880,657818,197345\n}}1\nPostamble:\n",
+        input.parent().unwrap().display()
+    );
+    let mut encoder = GzEncoder::new(fs::File::create(outdir.join("main.synctex.gz")).unwrap(), Compression::default());
+    encoder.write_all(synctex.as_bytes()).unwrap();
+    encoder.finish().unwrap();
+}
+
+#[tokio::test]
+async fn simple_impl_compile_project_synctex_after_cache_hit() {
+    let temp = mock_dir("project_synctex");
+    let dir = temp.path();
+    let job = CompileJob::new(Some(1), JobOptions::new(Engine::Pdflatex, vec![OutputFormat::Synctex]), &dir.join("jobs"));
+    let mut executor = MockExecutionService::new();
+    let options = CompileOptions {
+        entrypoint: None,
+        force: false,
+        text: None,
+        engine: None,
+        formats: Vec::new()
+    };
+    let files = vec![SourceFile { path: String::from("main.tex"), content: mock_text(&job).into_bytes() }];
+
+    executor
+        .expect_execute::<&'static str, String>()
+        .times(1)
+        .returning(|_, args, _| {
+            write_synctex_output(args);
+            Ok(fake_output(""))
+        });
+
+    let service = mock_service(executor, dir);
+    service.compile(&job, CompileInput::Project { dir: dir.join("first"), files: files.clone(), options: options.clone() }, mock_output()).await.unwrap();
+    let cached = service.compile(&job, CompileInput::Project { dir: dir.join("second"), files, options }, mock_output()).await.unwrap();
+
+    let source_path = dir.join("second/src");
+    let synctex = synctex::SyncTex::read(&cached.pdf.with_extension("synctex.gz"), &source_path, &source_path).unwrap();
+    assert_eq!(1, synctex.forward("main.tex", 5, None).len());
+    assert_eq!(Some(String::from("main.tex")), synctex.inverse(1, 150.0, 195.0).map(|location| location.file));
+}
+
+#[tokio::test]
+async fn simple_impl_compile_project_engine_change_rebuilds() {
+    let temp = mock_dir("project_engine");
+    let dir = temp.path();
+    let job = mock_job(dir);
+    let xelatex_job = CompileJob::new(Some(1), JobOptions::new(Engine::Xelatex, Vec::new()), &dir.join("jobs"));
+    let mut executor = MockExecutionService::new();
+    let options = CompileOptions {
+        entrypoint: None,
+        force: false,
+        text: None,
+        engine: None,
+        formats: Vec::new()
+    };
+    let files = vec![SourceFile { path: String::from("main.tex"), content: mock_text(&job).into_bytes() }];
+
+    executor
+        .expect_execute::<&'static str, String>()
+        .times(2)
+        .returning(|_, args, _| {
+            write_latexmk_output(args);
+            Ok(fake_output(""))
+        });
+
+    let service = mock_service(executor, dir);
+    service.compile(&job, CompileInput::Project { dir: dir.join("project"), files: files.clone(), options: options.clone() }, mock_output()).await.unwrap();
+    fs::write(dir.join("project/out/main.aux"), "pdflatex").unwrap();
+    service.compile(&xelatex_job, CompileInput::Project { dir: dir.join("project"), files, options }, mock_output()).await.unwrap();
+
+    assert!(!dir.join("project/out/main.aux").exists());
+}
+
+#[tokio::test]
+async fn simple_impl_compile_project_keeps_aux_files() {
+    let temp = mock_dir("project_aux");
+    let dir = temp.path();
+    let job = mock_job(dir);
+    let mut executor = MockExecutionService::new();
+    let options = CompileOptions {
+        entrypoint: None,
+        force: false,
+        text: None,
+        engine: None,
+        formats: Vec::new()
+    };
+
+    executor
+        .expect_execute::<&'static str, String>()
+        .times(2)
+        .returning(|_, args, _| {
+            write_latexmk_output(args);
+            Ok(fake_output(""))
+        });
+
+    let service = mock_service(executor, dir);
+    let files = vec![SourceFile { path: String::from("main.tex"), content: mock_text(&job).into_bytes() }];
+    service.compile(&job, CompileInput::Project { dir: dir.join("project"), files, options: options.clone() }, mock_output()).await.unwrap();
+    fs::write(dir.join("project/out/main.aux"), "aux").unwrap();
+
+    let files = vec![SourceFile { path: String::from("main.tex"), content: format!("{} changed", mock_text(&job)).into_bytes() }];
+    service.compile(&job, CompileInput::Project { dir: dir.join("project"), files, options }, mock_output()).await.unwrap();
+
+    assert_eq!("aux", fs::read_to_string(dir.join("project/out/main.aux")).unwrap());
+}
+
+#[tokio::test]
+async fn simple_impl_compile_project_force_clears_output() {
+    let temp = mock_dir("project_force");
+    let dir = temp.path();
+    let job = mock_job(dir);
+    let mut executor = MockExecutionService::new();
+    let options = CompileOptions {
+        entrypoint: None,
+        force: true,
+        text: None,
+        engine: None,
+        formats: Vec::new()
+    };
+    let files = vec![SourceFile { path: String::from("main.tex"), content: mock_text(&job).into_bytes() }];
+
+    executor
+        .expect_execute::<&'static str, String>()
+        .times(2)
+        .returning(|_, args, _| {
+            write_latexmk_output(args);
+            Ok(fake_output(""))
+        });
+
+    let service = mock_service(executor, dir);
+    service.compile(&job, CompileInput::Project { dir: dir.join("project"), files: files.clone(), options: options.clone() }, mock_output()).await.unwrap();
+    fs::write(dir.join("project/out/main.aux"), "aux").unwrap();
+    service.compile(&job, CompileInput::Project { dir: dir.join("project"), files, options }, mock_output()).await.unwrap();
+
+    assert!(!dir.join("project/out/main.aux").exists());
+    assert!(dir.join("project/out/main.pdf").is_file());
+}
+
+#[tokio::test]
+async fn simple_impl_compile_project_unchanged_skips_executor() {
+    let temp = mock_dir("project_unchanged");
+    let dir = temp.path();
+    let job = mock_job(dir);
+    let mut executor = MockExecutionService::new();
+    let options = CompileOptions {
+        entrypoint: None,
+        force: false,
+        text: None,
+        engine: None,
+        formats: Vec::new()
+    };
+    let files = vec![SourceFile { path: String::from("main.tex"), content: mock_text(&job).into_bytes() }];
+
+    executor
+        .expect_execute::<&'static str, String>()
+        .times(1)
+        .returning(|_, args, _| {
+            write_latexmk_output(args);
+            Ok(fake_output(""))
+        });
+
+    let service = mock_service(executor, dir);
+    let first = service.compile(&job, CompileInput::Project { dir: dir.join("project"), files: files.clone(), options: options.clone() }, mock_output()).await.unwrap();
+    let second = service.compile(&job, CompileInput::Project { dir: dir.join("project"), files, options }, mock_output()).await.unwrap();
+
+    assert_eq!(first.pdf, second.pdf);
+}
@@ -0,0 +1,108 @@
+use std::{io::{self, Cursor, Read}, path::Path};
+
+use tracing::warn;
+use zip::ZipArchive;
+
+use crate::{constants, domain::{compile::SourceFile, files::is_relative_path}};
+
+use super::write_if_changed;
+
+#[derive(Debug, PartialEq)]
+pub enum UploadError {
+    InvalidArchive,
+    InvalidPath(String),
+    TooManyEntries,
+    TooLarge
+}
+
+impl From<UploadError> for String {
+    fn from(err: UploadError) -> String {
+        match err {
+            UploadError::InvalidArchive => "Uploaded archive is not a valid zip file".to_owned(),
+            UploadError::InvalidPath(path) => format!("Invalid file path {}", path),
+            UploadError::TooManyEntries => format!("Upload contains more than {} files", constants::MAX_UPLOAD_ENTRIES),
+            UploadError::TooLarge => format!("Upload exceeds {} bytes when unpacked", constants::MAX_UNPACKED_BYTES)
+        }
+    }
+}
+
+pub fn unpack_zip(archive: &[u8]) -> Result<Vec<SourceFile>, UploadError> {
+    let mut archive = match ZipArchive::new(Cursor::new(archive)) {
+        Ok(archive) => archive,
+        Err(err) => {
+            warn!(%err);
+            return Err(UploadError::InvalidArchive);
+        }
+    };
+
+    if archive.len() > constants::MAX_UPLOAD_ENTRIES {
+        return Err(UploadError::TooManyEntries);
+    }
+
+    let mut remaining = constants::MAX_UNPACKED_BYTES;
+    let mut files = Vec::new();
+    for index in 0..archive.len() {
+        let entry = match archive.by_index(index) {
+            Ok(entry) => entry,
+            Err(err) => {
+                warn!(%err);
+                return Err(UploadError::InvalidArchive);
+            }
+        };
+
+        if entry.is_dir() {
+            continue;
+        }
+
+        let path = entry.name().to_owned();
+        if entry.enclosed_name().is_none() || is_relative_path(&path).is_err() {
+            return Err(UploadError::InvalidPath(path));
+        }
+
+        // The declared size can't be trusted, so cap the actual amount read as well
+        if entry.size() > remaining {
+            return Err(UploadError::TooLarge);
+        }
+
+        let mut content = Vec::new();
+        if let Err(err) = entry.take(remaining + 1).read_to_end(&mut content) {
+            warn!(%err);
+            return Err(UploadError::InvalidArchive);
+        }
+
+        if content.len() as u64 > remaining {
+            return Err(UploadError::TooLarge);
+        }
+        remaining -= content.len() as u64;
+
+        files.push(SourceFile { path, content });
+    }
+
+    Ok(files)
+}
+
+pub fn check_files(files: &[SourceFile]) -> Result<(), UploadError> {
+    if files.len() > constants::MAX_UPLOAD_ENTRIES {
+        return Err(UploadError::TooManyEntries);
+    }
+
+    let total: u64 = files.iter().map(|file| file.content.len() as u64).sum();
+    if total > constants::MAX_UNPACKED_BYTES {
+        return Err(UploadError::TooLarge);
+    }
+
+    match files.iter().find(|file| is_relative_path(&file.path).is_err()) {
+        Some(file) => Err(UploadError::InvalidPath(file.path.clone())),
+        None => Ok(())
+    }
+}
+
+pub fn write_files(dir: &Path, files: &[SourceFile]) -> io::Result<()> {
+    for file in files {
+        write_if_changed(&dir.join(&file.path), &file.content)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests;
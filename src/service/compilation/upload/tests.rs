@@ -0,0 +1,67 @@
+use std::io::Write;
+
+use zip::{ZipWriter, write::FileOptions};
+
+use super::*;
+
+fn mock_archive(entries: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, content) in entries {
+        writer.start_file(*name, FileOptions::default()).unwrap();
+        writer.write_all(content).unwrap();
+    }
+    writer.finish().unwrap().into_inner()
+}
+
+#[test]
+fn unpack_zip_normal() {
+    let archive = mock_archive(&[
+        ("main.tex", b"\\input{chapters/intro}"),
+        ("chapters/intro.tex", b"Intro")
+    ]);
+
+    let files = unpack_zip(&archive).unwrap();
+
+    assert_eq!(vec![
+        SourceFile { path: String::from("main.tex"), content: b"\\input{chapters/intro}".to_vec() },
+        SourceFile { path: String::from("chapters/intro.tex"), content: b"Intro".to_vec() }
+    ], files);
+}
+
+#[test]
+fn unpack_zip_path_traversal() {
+    let archive = mock_archive(&[("../evil.tex", b"evil")]);
+
+    assert_eq!(Err(UploadError::InvalidPath(String::from("../evil.tex"))), unpack_zip(&archive));
+}
+
+#[test]
+fn unpack_zip_absolute_path() {
+    let archive = mock_archive(&[("/etc/evil.tex", b"evil")]);
+
+    assert_eq!(Err(UploadError::InvalidPath(String::from("/etc/evil.tex"))), unpack_zip(&archive));
+}
+
+#[test]
+fn unpack_zip_invalid_archive() {
+    assert_eq!(Err(UploadError::InvalidArchive), unpack_zip(b"not a zip"));
+}
+
+#[test]
+fn unpack_zip_too_many_entries() {
+    let names: Vec<String> = (0..=constants::MAX_UPLOAD_ENTRIES)
+        .map(|index| format!("{}.tex", index))
+        .collect();
+    let entries: Vec<(&str, &[u8])> = names.iter()
+        .map(|name| (name.as_str(), &b""[..]))
+        .collect();
+
+    assert_eq!(Err(UploadError::TooManyEntries), unpack_zip(&mock_archive(&entries)));
+}
+
+#[test]
+fn check_files_invalid_path() {
+    let files = vec![SourceFile { path: String::from("a/../../b.tex"), content: Vec::new() }];
+
+    assert_eq!(Err(UploadError::InvalidPath(String::from("a/../../b.tex"))), check_files(&files));
+}
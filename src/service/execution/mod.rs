@@ -1,55 +1,521 @@
-use std::{fmt::Debug, ffi::OsStr};
-use async_process::Command;
+use std::{collections::VecDeque, fmt::Debug, ffi::{CString, OsStr}, env, fs, io, mem, os::unix::{ffi::OsStrExt, fs::DirBuilderExt, process::ExitStatusExt}, path::{Path, PathBuf}, process::ExitStatus, time::{Duration, Instant}};
+use async_process::{Command, Stdio, unix::CommandExt};
 
 use axum::async_trait;
-use tracing::{error, info};
+use futures::{AsyncBufReadExt, AsyncRead, AsyncReadExt, io::BufReader};
+use mockall::automock;
+use tokio::sync::mpsc;
+use tracing::{error, info, warn};
+use uuid::Uuid;
 
+use crate::{constants, domain::compile::LogStream};
+
+pub mod sandbox;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputLine {
+    pub stream: LogStream,
+    pub text: String
+}
+
+pub type OutputSender = mpsc::UnboundedSender<OutputLine>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExecutionOutput {
+    pub stdout: String,
+    pub stderr: String,
+    pub exit_code: Option<i32>,
+    pub signal: Option<i32>,
+    pub wall_time: Duration,
+    pub cpu_time: Duration
+}
+
+impl ExecutionOutput {
+    fn new(stdout: &mut OutputTail, stderr: &mut OutputTail, status: Option<ExitStatus>, wall_time: Duration, cpu_time: Duration) -> Self {
+        Self {
+            stdout: stdout.text(),
+            stderr: stderr.text(),
+            exit_code: status.and_then(|status| status.code()),
+            signal: status.and_then(|status| status.signal()),
+            wall_time,
+            cpu_time
+        }
+    }
+}
+
+const TRUNCATED_MARKER: &str = "[output truncated]\n";
+
+// Keeps the last bytes written to a stream, so that a command flooding its output cannot exhaust the service's memory
+#[derive(Debug)]
+struct OutputTail {
+    content: VecDeque<u8>,
+    limit: usize,
+    truncated: bool
+}
+
+impl OutputTail {
+    fn new(limit: usize) -> Self {
+        Self {
+            content: VecDeque::new(),
+            limit,
+            truncated: false
+        }
+    }
+
+    fn push(&mut self, bytes: &[u8]) {
+        self.content.extend(bytes);
+        let excess = self.content.len().saturating_sub(self.limit);
+        if excess > 0 {
+            self.content.drain(..excess);
+            self.truncated = true;
+        }
+    }
+
+    // Only the end is kept, as that is where errors are reported
+    fn text(&mut self) -> String {
+        let text = String::from_utf8_lossy(self.content.make_contiguous());
+        match self.truncated {
+            true => format!("{}{}", TRUNCATED_MARKER, text),
+            false => text.into_owned()
+        }
+    }
+}
+
+#[automock(type ExecutionError = ProcessExecutionError;)]
 #[async_trait]
 pub trait ExecutionService {
     type ExecutionError;
-    async fn execute<'a>(&self, comm: impl AsRef<OsStr> + Debug + Send, args: &'a [impl AsRef<OsStr> + Debug + Sync]) -> Result<String, Self::ExecutionError>;
+    // With an output sender, every line written to stdout or stderr is sent as soon as it is read
+    async fn execute<C, A>(&self, comm: C, args: &[A], output: Option<OutputSender>) -> Result<ExecutionOutput, Self::ExecutionError>
+    where
+        C: AsRef<OsStr> + Debug + Send + 'static,
+        A: AsRef<OsStr> + Debug + Sync + 'static;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExecutionLimits {
+    pub timeout: Duration,
+    pub cpu_seconds: u64,
+    pub address_space_bytes: u64,
+    pub file_size_bytes: u64,
+    pub memory_bytes: u64,
+    pub processes: u64,
+    pub user_processes: u64,
+    pub output_bytes: usize
+}
+
+impl Default for ExecutionLimits {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(constants::EXECUTION_TIMEOUT_SECONDS),
+            cpu_seconds: constants::EXECUTION_CPU_SECONDS,
+            address_space_bytes: constants::EXECUTION_ADDRESS_SPACE_BYTES,
+            file_size_bytes: constants::EXECUTION_FILE_SIZE_BYTES,
+            memory_bytes: constants::EXECUTION_MEMORY_BYTES,
+            processes: constants::EXECUTION_PROCESSES,
+            user_processes: constants::EXECUTION_USER_PROCESSES,
+            output_bytes: constants::EXECUTION_OUTPUT_BYTES
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
-pub struct ProcessExecutionService;
+pub struct ProcessExecutionService {
+    limits: ExecutionLimits,
+    cgroup: Option<PathBuf>
+}
+
+impl ProcessExecutionService {
+    pub fn new(limits: ExecutionLimits) -> Self {
+        Self {
+            limits,
+            cgroup: None
+        }
+    }
+
+    // Every command runs in a cgroup of its own below this one, which limits the processes and memory of the
+    // whole process tree. Without it, neither is limited
+    pub fn with_cgroup(mut self, cgroup: &Path) -> Self {
+        self.cgroup = Some(cgroup.to_path_buf());
+        self
+    }
+}
 
+#[derive(Debug, PartialEq)]
 pub enum ProcessExecutionError {
     Unknown,
-    StatusError(Option<i32>, String)
+    StatusError(ExecutionOutput),
+    Timeout(Duration, ExecutionOutput),
+    ResourceLimit(String, ExecutionOutput)
 }
 
 #[async_trait]
 impl ExecutionService for ProcessExecutionService {
     type ExecutionError = ProcessExecutionError;
-    
-    #[tracing::instrument]
-    async fn execute<'a>(&self, comm: impl AsRef<OsStr> + Debug + Send, args: &'a [impl AsRef<OsStr> + Debug + Sync]) -> Result<String, Self::ExecutionError> {
+
+    #[tracing::instrument(skip(output))]
+    async fn execute<C, A>(&self, comm: C, args: &[A], output: Option<OutputSender>) -> Result<ExecutionOutput, Self::ExecutionError>
+    where
+        C: AsRef<OsStr> + Debug + Send + 'static,
+        A: AsRef<OsStr> + Debug + Sync + 'static
+    {
         info!("Received command.");
-        
-        let command = Command::new(comm)
+
+        let workdir = match create_workdir() {
+            Ok(workdir) => workdir,
+            Err(err) => {
+                error!(%err);
+                return Err(ProcessExecutionError::Unknown);
+            }
+        };
+
+        let cgroup = match self.cgroup.as_deref().map(|parent| RunCgroup::create(parent, &self.limits)).transpose() {
+            Ok(cgroup) => cgroup,
+            Err(err) => {
+                error!(%err, "Failed to create cgroup");
+                if let Err(err) = fs::remove_dir_all(&workdir) {
+                    warn!(%err, "Failed to remove working directory");
+                }
+                return Err(ProcessExecutionError::Unknown);
+            }
+        };
+
+        let result = self.run(comm, args, &workdir, cgroup.as_ref(), output.as_ref()).await;
+
+        if let Some(cgroup) = cgroup {
+            cgroup.remove().await;
+        }
+        if let Err(err) = fs::remove_dir_all(&workdir) {
+            warn!(%err, "Failed to remove working directory");
+        }
+
+        result
+    }
+}
+
+impl ProcessExecutionService {
+    async fn run(&self, comm: impl AsRef<OsStr>, args: &[impl AsRef<OsStr>], workdir: &Path, cgroup: Option<&RunCgroup>, output: Option<&OutputSender>) -> Result<ExecutionOutput, ProcessExecutionError> {
+        let limits = self.limits;
+        let procs = match cgroup.map(RunCgroup::procs).transpose() {
+            Ok(procs) => procs,
+            Err(err) => {
+                error!(%err);
+                return Err(ProcessExecutionError::Unknown);
+            }
+        };
+
+        let mut command = Command::new(comm);
+        command
             .args(args)
-            .output();
-        
-        let out = match command.await {
-            Ok(out) => out,
+            .current_dir(workdir)
+            .env_clear()
+            .env("PATH", env::var_os("PATH").unwrap_or_else(|| constants::DEFAULT_EXECUTION_PATH.into()))
+            .env("HOME", workdir)
+            .env("TEXMFVAR", workdir.join("texmf-var"))
+            .envs(constants::EXECUTION_ENV)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+
+        // SAFETY: only async-signal-safe libc calls are made between fork and exec
+        unsafe {
+            command.pre_exec(move || {
+                if let Some(procs) = &procs {
+                    join_cgroup(procs)?;
+                }
+                apply_limits(&limits, procs.is_none())
+            });
+        }
+
+        let started = Instant::now();
+        let mut child = match command.spawn() {
+            Ok(child) => child,
             Err(err) => {
                 error!(%err);
                 return Err(ProcessExecutionError::Unknown);
             }
         };
+        let pid = child.id();
 
-        let msg = match String::from_utf8(out.stdout) {
-            Ok(msg) => msg,
+        let (stdout_pipe, stderr_pipe) = match (child.stdout.take(), child.stderr.take()) {
+            (Some(stdout), Some(stderr)) => (stdout, stderr),
+            _ => return Err(ProcessExecutionError::Unknown)
+        };
+        // The buffers live outside of the timed future, so output read before a timeout is kept
+        let mut stdout = OutputTail::new(limits.output_bytes);
+        let mut stderr = OutputTail::new(limits.output_bytes);
+        let finished = async {
+            let (stdout, stderr, cpu_time) = futures::join!(
+                read_lines(stdout_pipe, LogStream::Stdout, output, limits.output_bytes, &mut stdout),
+                read_lines(stderr_pipe, LogStream::Stderr, output, limits.output_bytes, &mut stderr),
+                wait_for_exit(pid)
+            );
+            stdout?;
+            stderr?;
+            Ok::<_, io::Error>(cpu_time)
+        };
+
+        let result = tokio::time::timeout(limits.timeout, finished).await;
+        // The child has not been reaped yet, so its process group cannot have been reused. Anything still running
+        // in it was left behind by the command, or the command itself timed out
+        kill_group(pid, cgroup);
+        let cpu_time = match result {
+            Ok(Ok(cpu_time)) => cpu_time,
+            Ok(Err(err)) => {
+                error!(%err);
+                let _ = child.status().await;
+                return Err(ProcessExecutionError::Unknown);
+            },
+            Err(_) => {
+                warn!(?limits.timeout, "Execution timed out");
+                let cpu_time = wait_for_exit(pid).await;
+                let status = child.status().await.ok();
+                let output = ExecutionOutput::new(&mut stdout, &mut stderr, status, started.elapsed(), cpu_time);
+                return Err(ProcessExecutionError::Timeout(limits.timeout, output));
+            }
+        };
+        let status = match child.status().await {
+            Ok(status) => status,
             Err(err) => {
                 error!(%err);
                 return Err(ProcessExecutionError::Unknown);
             }
         };
 
-        if !out.status.success() {
-            Err(ProcessExecutionError::StatusError(out.status.code(), msg))
+        let output = ExecutionOutput::new(&mut stdout, &mut stderr, Some(status), started.elapsed(), cpu_time);
+
+        match output.signal {
+            Some(libc::SIGXCPU) => return Err(ProcessExecutionError::ResourceLimit("CPU time limit exceeded".to_owned(), output)),
+            Some(libc::SIGXFSZ) => return Err(ProcessExecutionError::ResourceLimit("File size limit exceeded".to_owned(), output)),
+            _ => ()
+        };
+
+        if let (false, Some(cgroup)) = (status.success(), cgroup) {
+            if cgroup.event_count("memory.events", "oom_kill") > 0 {
+                return Err(ProcessExecutionError::ResourceLimit("Memory limit exceeded".to_owned(), output));
+            }
+            if cgroup.event_count("pids.events", "max") > 0 {
+                return Err(ProcessExecutionError::ResourceLimit("Process limit exceeded".to_owned(), output));
+            }
+        }
+
+        if !status.success() {
+            Err(ProcessExecutionError::StatusError(output))
         } else {
-            Ok(msg)
+            Ok(output)
+        }
+    }
+}
+
+// Lines longer than the limit are split, so that no single line is buffered whole either
+async fn read_lines(pipe: impl AsyncRead + Unpin, stream: LogStream, output: Option<&OutputSender>, limit: usize, content: &mut OutputTail) -> io::Result<()> {
+    let mut reader = BufReader::new(pipe);
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        if (&mut reader).take(limit as u64).read_until(b'\n', &mut line).await? == 0 {
+            return Ok(());
+        }
+        content.push(&line);
+
+        if let Some(output) = output {
+            let text = String::from_utf8_lossy(&line).trim_end().to_owned();
+            // The receiver may have stopped listening, which must not affect the process
+            let _ = output.send(OutputLine { stream, text });
+        }
+    }
+}
+
+// Waits without reaping the child, so that its CPU time can still be read while it is a zombie
+async fn wait_for_exit(pid: u32) -> Duration {
+    let waited = tokio::task::spawn_blocking(move || {
+        let mut info: libc::siginfo_t = unsafe { mem::zeroed() };
+        loop {
+            if unsafe { libc::waitid(libc::P_PID, pid as libc::id_t, &mut info, libc::WEXITED | libc::WNOWAIT) } == 0 {
+                return cpu_time(pid);
+            }
+            let err = io::Error::last_os_error();
+            if err.kind() != io::ErrorKind::Interrupted {
+                return Err(err);
+            }
+        }
+    }).await;
+
+    match waited {
+        Ok(Ok(cpu_time)) => cpu_time,
+        Ok(Err(err)) => {
+            warn!(%err, "Failed to read CPU time");
+            Duration::ZERO
+        },
+        Err(err) => {
+            warn!(%err);
+            Duration::ZERO
         }
     }
 }
+
+// User and system time of the process and of the children it waited for
+fn cpu_time(pid: u32) -> io::Result<Duration> {
+    let stat = fs::read_to_string(format!("/proc/{}/stat", pid))?;
+    // The command name may contain spaces, so fields are counted from its closing parenthesis
+    let fields: Vec<&str> = match stat.rfind(')') {
+        Some(end) => stat[end + 1..].split_whitespace().collect(),
+        None => return Err(io::ErrorKind::InvalidData.into())
+    };
+    let ticks: u64 = fields.get(11..15)
+        .ok_or(io::ErrorKind::InvalidData)?
+        .iter()
+        .map(|field| field.parse::<u64>().map_err(|_| io::Error::from(io::ErrorKind::InvalidData)))
+        .sum::<io::Result<u64>>()?;
+
+    let ticks_per_second = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+    if ticks_per_second <= 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(Duration::from_secs_f64(ticks as f64 / ticks_per_second as f64))
+}
+
+fn create_workdir() -> io::Result<PathBuf> {
+    let workdir = constants::RUN_DIR.join(Uuid::new_v4().to_string());
+
+    fs::create_dir_all(&*constants::RUN_DIR)?;
+    fs::DirBuilder::new()
+        .mode(0o700)
+        .create(&workdir)?;
+    Ok(workdir)
+}
+
+// A cgroup created for a single run, whose event counters tell which of its limits were hit
+#[derive(Debug)]
+struct RunCgroup {
+    dir: PathBuf
+}
+
+impl RunCgroup {
+    fn create(parent: &Path, limits: &ExecutionLimits) -> io::Result<Self> {
+        let cgroup = Self {
+            dir: parent.join(Uuid::new_v4().to_string())
+        };
+        fs::create_dir(&cgroup.dir)?;
+
+        let written = fs::write(cgroup.dir.join("pids.max"), limits.processes.to_string()).and_then(|_| {
+            // Only a cgroup v2 hierarchy has a memory controller next to the pids controller
+            let memory_max = cgroup.dir.join("memory.max");
+            if memory_max.exists() {
+                fs::write(memory_max, limits.memory_bytes.to_string())?;
+            }
+            Ok(())
+        });
+        if let Err(err) = written {
+            let _ = fs::remove_dir(&cgroup.dir);
+            return Err(err);
+        }
+        Ok(cgroup)
+    }
+
+    fn procs(&self) -> io::Result<CString> {
+        CString::new(self.dir.join("cgroup.procs").as_os_str().as_bytes()).map_err(|_| io::ErrorKind::InvalidInput.into())
+    }
+
+    fn event_count(&self, file: &str, event: &str) -> u64 {
+        fs::read_to_string(self.dir.join(file))
+            .map(|events| event_count(&events, event))
+            .unwrap_or(0)
+    }
+
+    // Processes that left the process group are still in the cgroup
+    fn kill(&self) {
+        match fs::write(self.dir.join("cgroup.kill"), "1") {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => warn!(%err, "Failed to kill cgroup"),
+            _ => ()
+        }
+    }
+
+    // Killed processes leave the cgroup only once they have exited
+    async fn remove(self) {
+        for _ in 0..constants::CGROUP_REMOVE_ATTEMPTS {
+            match fs::remove_dir(&self.dir) {
+                Ok(_) => return,
+                Err(err) if err.raw_os_error() == Some(libc::EBUSY) => tokio::time::sleep(Duration::from_millis(10)).await,
+                Err(err) => {
+                    warn!(%err, "Failed to remove cgroup");
+                    return;
+                }
+            }
+        }
+        warn!(dir = ?self.dir, "Cgroup is still busy");
+    }
+}
+
+fn event_count(events: &str, event: &str) -> u64 {
+    events.lines()
+        .filter_map(|line| line.split_once(' '))
+        .find(|(name, _)| *name == event)
+        .and_then(|(_, count)| count.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+fn kill_group(pid: u32, cgroup: Option<&RunCgroup>) {
+    unsafe {
+        libc::kill(-(pid as libc::pid_t), libc::SIGKILL);
+    }
+    if let Some(cgroup) = cgroup {
+        cgroup.kill();
+    }
+}
+
+// Runs between fork and exec, so it only makes async-signal-safe calls
+fn join_cgroup(procs: &CString) -> io::Result<()> {
+    let fd = unsafe { libc::open(procs.as_ptr(), libc::O_WRONLY | libc::O_CLOEXEC) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // Writing 0 moves the writing process itself
+    let written = unsafe { libc::write(fd, b"0".as_ptr() as *const libc::c_void, 1) };
+    let err = io::Error::last_os_error();
+    unsafe {
+        libc::close(fd);
+    }
+    if written != 1 {
+        return Err(err);
+    }
+    Ok(())
+}
+
+fn apply_limits(limits: &ExecutionLimits, without_cgroup: bool) -> io::Result<()> {
+    // Own process group, so that a timeout kills every process spawned by the command
+    if unsafe { libc::setpgid(0, 0) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    // Counts every process of the service's user, so it is only a fallback that still stops a fork bomb
+    if without_cgroup {
+        set_limit(libc::RLIMIT_NPROC, limits.user_processes)?;
+    }
+
+    set_limit(libc::RLIMIT_CPU, limits.cpu_seconds)?;
+    set_limit(libc::RLIMIT_AS, limits.address_space_bytes)?;
+    set_limit(libc::RLIMIT_FSIZE, limits.file_size_bytes)?;
+    set_limit(libc::RLIMIT_CORE, 0)
+}
+
+// Only ever lowers a limit, since raising the hard limit needs privileges the service may not have
+fn set_limit(resource: libc::__rlimit_resource_t, value: u64) -> io::Result<()> {
+    let mut current: libc::rlimit = unsafe { mem::zeroed() };
+    if unsafe { libc::getrlimit(resource, &mut current) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let value = (value as libc::rlim_t).min(current.rlim_max);
+    let limit = libc::rlimit {
+        rlim_cur: value,
+        rlim_max: value
+    };
+    if unsafe { libc::setrlimit(resource, &limit) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod tests;
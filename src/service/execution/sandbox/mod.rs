@@ -0,0 +1,127 @@
+use std::{collections::BTreeSet, ffi::{OsStr, OsString}, fmt::Debug, fs, path::{Component, Path, PathBuf}};
+
+use axum::async_trait;
+use tracing::error;
+
+use crate::constants;
+
+use super::{ExecutionLimits, ExecutionOutput, ExecutionService, OutputSender, ProcessExecutionError, ProcessExecutionService};
+
+const SANDBOX_HOME: &str = "/tmp/home";
+const SANDBOX_TEXMFVAR: &str = "/tmp/home/texmf-var";
+const SOURCE_DIR_NAME: &str = "src";
+const OUTPUT_DIR_NAME: &str = "out";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SandboxConfig {
+    pub bwrap_path: PathBuf,
+    pub read_only: Vec<PathBuf>,
+    pub compile_dir: PathBuf
+}
+
+impl Default for SandboxConfig {
+    fn default() -> Self {
+        Self {
+            bwrap_path: PathBuf::from(constants::BWRAP_PATH),
+            read_only: constants::SANDBOX_READ_ONLY_PATHS.iter().map(PathBuf::from).collect(),
+            compile_dir: constants::COMPILE_DIR.to_path_buf()
+        }
+    }
+}
+
+// Runs every command through bubblewrap, in fresh namespaces without network access, with the system and the
+// sources of the job directories named in the arguments mounted read-only and only their output directories writable
+#[derive(Debug, Clone)]
+pub struct SandboxExecutionService {
+    process: ProcessExecutionService,
+    config: SandboxConfig
+}
+
+impl SandboxExecutionService {
+    pub fn new(limits: ExecutionLimits, config: SandboxConfig) -> Self {
+        Self {
+            process: ProcessExecutionService::new(limits),
+            config
+        }
+    }
+
+    pub fn with_cgroup(mut self, cgroup: &Path) -> Self {
+        self.process = self.process.with_cgroup(cgroup);
+        self
+    }
+
+    fn sandbox_args(&self, comm: &OsStr, args: &[OsString]) -> Vec<OsString> {
+        let mut sandbox: Vec<OsString> = [
+            "--unshare-all",
+            "--die-with-parent",
+            "--new-session",
+            "--proc", "/proc",
+            "--dev", "/dev",
+            "--tmpfs", "/tmp",
+            "--dir", SANDBOX_HOME,
+            "--chdir", SANDBOX_HOME,
+            "--setenv", "HOME", SANDBOX_HOME,
+            "--setenv", "TEXMFVAR", SANDBOX_TEXMFVAR
+        ].iter().map(OsString::from).collect();
+
+        for path in &self.config.read_only {
+            sandbox.extend(["--ro-bind-try".into(), path.into(), path.into()]);
+        }
+        for dir in job_dirs(&self.config.compile_dir, args) {
+            let source_dir = dir.join(SOURCE_DIR_NAME);
+            let output_dir = dir.join(OUTPUT_DIR_NAME);
+            sandbox.extend(["--ro-bind-try".into(), source_dir.clone().into(), source_dir.into()]);
+            sandbox.extend(["--bind".into(), output_dir.clone().into(), output_dir.into()]);
+        }
+
+        sandbox.push("--".into());
+        sandbox.push(comm.into());
+        sandbox.extend_from_slice(args);
+        sandbox
+    }
+}
+
+#[async_trait]
+impl ExecutionService for SandboxExecutionService {
+    type ExecutionError = ProcessExecutionError;
+
+    async fn execute<C, A>(&self, comm: C, args: &[A], output: Option<OutputSender>) -> Result<ExecutionOutput, Self::ExecutionError>
+    where
+        C: AsRef<OsStr> + Debug + Send + 'static,
+        A: AsRef<OsStr> + Debug + Sync + 'static
+    {
+        let args: Vec<OsString> = args.iter().map(|arg| arg.as_ref().to_owned()).collect();
+        // Bind mounts need their source to exist, and latexmk would only create the output directory inside the sandbox
+        for dir in job_dirs(&self.config.compile_dir, &args) {
+            if let Err(err) = fs::create_dir_all(dir.join(OUTPUT_DIR_NAME)) {
+                error!(%err, ?dir, "Failed to create output directory");
+                return Err(ProcessExecutionError::Unknown);
+            }
+        }
+        let sandbox = self.sandbox_args(comm.as_ref(), &args);
+        self.process.execute(self.config.bwrap_path.clone(), &sandbox, output).await
+    }
+}
+
+// Paths can follow a prefix like -outdir= or TEXINPUTS=, so they are looked for anywhere in an argument
+fn job_dirs(compile_dir: &Path, args: &[OsString]) -> BTreeSet<PathBuf> {
+    let prefix = compile_dir.to_string_lossy();
+    let prefix = prefix.trim_end_matches('/');
+
+    args.iter()
+        .map(|arg| arg.to_string_lossy())
+        .filter_map(|arg| arg.find(prefix).map(|start| arg[start..].to_owned()))
+        .filter_map(|path| {
+            // The job directory is the second level below the compile directory, e.g. jobs/<id> or projects/<id>
+            let relative = Path::new(path.trim_end_matches(':')).strip_prefix(prefix).ok()?.to_path_buf();
+            let mut components = relative.components();
+            match (components.next(), components.next()) {
+                (Some(Component::Normal(kind)), Some(Component::Normal(id))) => Some(Path::new(prefix).join(kind).join(id)),
+                _ => None
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests;
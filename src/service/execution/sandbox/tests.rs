@@ -0,0 +1,53 @@
+use super::*;
+
+fn mock_config() -> SandboxConfig {
+    SandboxConfig {
+        bwrap_path: PathBuf::from("bwrap"),
+        read_only: vec![PathBuf::from("/usr"), PathBuf::from("/etc/texmf")],
+        compile_dir: PathBuf::from("/tmp/agar_service/")
+    }
+}
+
+fn mock_args(args: &[&str]) -> Vec<OsString> {
+    args.iter().map(OsString::from).collect()
+}
+
+#[test]
+fn job_dirs_from_arguments() {
+    let args = mock_args(&[
+        "-outdir=/tmp/agar_service/jobs/1/out",
+        "-pdf",
+        "/tmp/agar_service/jobs/1/src/main.tex",
+        "TEXINPUTS=/tmp/agar_service/projects/2/src:"
+    ]);
+
+    let dirs: Vec<PathBuf> = job_dirs(&mock_config().compile_dir, &args).into_iter().collect();
+
+    assert_eq!(vec![PathBuf::from("/tmp/agar_service/jobs/1"), PathBuf::from("/tmp/agar_service/projects/2")], dirs);
+}
+
+#[test]
+fn job_dirs_ignore_compile_dir_itself() {
+    let args = mock_args(&["/tmp/agar_service/cache", "/tmp/agar_service/", "/home/user/main.tex"]);
+
+    assert!(job_dirs(&mock_config().compile_dir, &args).is_empty());
+}
+
+#[test]
+fn sandbox_args_bind_sources_read_only() {
+    let service = SandboxExecutionService::new(ExecutionLimits::default(), mock_config());
+    let args = mock_args(&["-outdir=/tmp/agar_service/jobs/1/out", "/tmp/agar_service/jobs/1/src/main.tex"]);
+
+    let sandbox: Vec<String> = service.sandbox_args(OsStr::new("latexmk"), &args)
+        .iter()
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect();
+    let command = sandbox.iter().position(|arg| arg == "--").unwrap();
+
+    assert!(sandbox.contains(&String::from("--unshare-all")));
+    assert!(sandbox.windows(3).any(|bind| bind == ["--ro-bind-try", "/usr", "/usr"]));
+    assert!(sandbox.windows(3).any(|bind| bind == ["--ro-bind-try", "/tmp/agar_service/jobs/1/src", "/tmp/agar_service/jobs/1/src"]));
+    assert!(sandbox.windows(3).any(|bind| bind == ["--bind", "/tmp/agar_service/jobs/1/out", "/tmp/agar_service/jobs/1/out"]));
+    assert!(!sandbox.windows(3).any(|bind| bind[0] == "--bind" && bind[1] == "/tmp/agar_service/jobs/1"));
+    assert_eq!(vec!["latexmk", "-outdir=/tmp/agar_service/jobs/1/out", "/tmp/agar_service/jobs/1/src/main.tex"], sandbox[command + 1..]);
+}
@@ -0,0 +1,311 @@
+use std::{fmt::{self, Formatter}, sync::{Arc, Mutex}};
+
+use super::*;
+
+type Handler = dyn Fn(&[String]) -> Result<ExecutionOutput, ProcessExecutionError> + Send + Sync;
+
+// Stands in for TeX in tests: records every command and answers it with the given handler
+#[derive(Clone)]
+pub struct FakeExecutionService {
+    handler: Arc<Handler>,
+    calls: Arc<Mutex<Vec<Vec<String>>>>
+}
+
+impl Debug for FakeExecutionService {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FakeExecutionService")
+            .field("calls", &self.calls)
+            .finish()
+    }
+}
+
+impl FakeExecutionService {
+    pub fn new(handler: impl Fn(&[String]) -> Result<ExecutionOutput, ProcessExecutionError> + Send + Sync + 'static) -> Self {
+        Self {
+            handler: Arc::new(handler),
+            calls: Arc::default()
+        }
+    }
+
+    // Writes a PDF and a log for the input into -outdir=, like a successful latexmk run
+    pub fn latexmk() -> Self {
+        Self::new(|command| {
+            let outdir = command.iter()
+                .find_map(|arg| arg.strip_prefix("-outdir="))
+                .map(Path::new)
+                .unwrap();
+            let stem = Path::new(command.last().unwrap()).file_stem().unwrap().to_str().unwrap();
+
+            fs::create_dir_all(outdir).unwrap();
+            fs::write(outdir.join(format!("{}.pdf", stem)), b"%PDF-1.5").unwrap();
+            fs::write(outdir.join(format!("{}.log", stem)), b"").unwrap();
+            Ok(fake_output("Output written"))
+        })
+    }
+
+    // Each call starts with the command itself
+    pub fn calls(&self) -> Vec<Vec<String>> {
+        self.calls.lock().unwrap().clone()
+    }
+}
+
+pub fn fake_output(stdout: &str) -> ExecutionOutput {
+    ExecutionOutput {
+        stdout: stdout.to_owned(),
+        stderr: String::new(),
+        exit_code: Some(0),
+        signal: None,
+        wall_time: Duration::ZERO,
+        cpu_time: Duration::ZERO
+    }
+}
+
+#[async_trait]
+impl ExecutionService for FakeExecutionService {
+    type ExecutionError = ProcessExecutionError;
+
+    async fn execute<C, A>(&self, comm: C, args: &[A], output: Option<OutputSender>) -> Result<ExecutionOutput, Self::ExecutionError>
+    where
+        C: AsRef<OsStr> + Debug + Send + 'static,
+        A: AsRef<OsStr> + Debug + Sync + 'static
+    {
+        let command: Vec<String> = std::iter::once(comm.as_ref())
+            .chain(args.iter().map(AsRef::as_ref))
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect();
+        self.calls.lock().unwrap().push(command.clone());
+
+        let result = (self.handler)(&command);
+        let stdout = match &result {
+            Ok(out) | Err(ProcessExecutionError::StatusError(out)) => out.stdout.as_str(),
+            _ => ""
+        };
+        if let Some(output) = output {
+            for line in stdout.lines() {
+                let _ = output.send(OutputLine { stream: LogStream::Stdout, text: line.to_owned() });
+            }
+        }
+        result
+    }
+}
+
+fn mock_limits() -> ExecutionLimits {
+    ExecutionLimits {
+        timeout: Duration::from_millis(500),
+        cpu_seconds: 5,
+        address_space_bytes: 1024 * 1024 * 1024,
+        file_size_bytes: 1024,
+        memory_bytes: 1024 * 1024 * 1024,
+        processes: 1024,
+        user_processes: 1024 * 1024,
+        output_bytes: 1024
+    }
+}
+
+#[tokio::test]
+async fn execute_normal() {
+    let service = ProcessExecutionService::new(mock_limits());
+
+    let output = service.execute("sh", &["-c", "echo out"], None).await.unwrap();
+
+    assert_eq!("out\n", output.stdout);
+    assert_eq!(Some(0), output.exit_code);
+    assert_eq!(None, output.signal);
+}
+
+#[tokio::test]
+async fn execute_status_error() {
+    let service = ProcessExecutionService::new(mock_limits());
+
+    let result = service.execute("sh", &["-c", "echo out; echo err >&2; exit 3"], None).await;
+
+    match result {
+        Err(ProcessExecutionError::StatusError(output)) => {
+            assert_eq!("out\n", output.stdout);
+            assert_eq!("err\n", output.stderr);
+            assert_eq!(Some(3), output.exit_code);
+        },
+        other => panic!("unexpected result {:?}", other)
+    }
+}
+
+#[tokio::test]
+async fn execute_non_utf8_output() {
+    let service = ProcessExecutionService::new(mock_limits());
+
+    let output = service.execute("sh", &["-c", "printf 'a\\377b' >&2"], None).await.unwrap();
+
+    assert_eq!("a\u{FFFD}b", output.stderr);
+}
+
+#[tokio::test]
+async fn execute_signal() {
+    let service = ProcessExecutionService::new(mock_limits());
+
+    let result = service.execute("sh", &["-c", "kill -TERM $$"], None).await;
+
+    match result {
+        Err(ProcessExecutionError::StatusError(output)) => {
+            assert_eq!(None, output.exit_code);
+            assert_eq!(Some(libc::SIGTERM), output.signal);
+        },
+        other => panic!("unexpected result {:?}", other)
+    }
+}
+
+#[tokio::test]
+async fn execute_measures_time() {
+    let service = ProcessExecutionService::new(ExecutionLimits { timeout: Duration::from_secs(10), ..mock_limits() });
+
+    let output = service.execute("sh", &["-c", "i=0; while [ $i -lt 100000 ]; do i=$((i+1)); done"], None).await.unwrap();
+
+    assert!(output.cpu_time > Duration::ZERO);
+    assert!(output.wall_time > Duration::ZERO);
+}
+
+#[tokio::test]
+async fn execute_clean_environment() {
+    let service = ProcessExecutionService::new(mock_limits());
+
+    let out = service.execute("sh", &["-c", "env"], None).await.unwrap().stdout;
+
+    let passed = ["PATH", "HOME", "PWD", "SHLVL", "_"];
+    for (name, _) in env::vars().filter(|(name, _)| !passed.contains(&name.as_str())) {
+        assert!(!out.lines().any(|line| line.starts_with(&format!("{}=", name))), "{} was inherited", name);
+    }
+    assert!(out.contains("openin_any=p"));
+    assert!(out.contains("openout_any=p"));
+}
+
+#[tokio::test]
+async fn execute_timeout() {
+    let service = ProcessExecutionService::new(mock_limits());
+
+    let result = service.execute("sh", &["-c", "echo started; sleep 5"], None).await;
+
+    match result {
+        Err(ProcessExecutionError::Timeout(timeout, output)) => {
+            assert_eq!(Duration::from_millis(500), timeout);
+            assert_eq!("started\n", output.stdout);
+            assert_eq!(Some(libc::SIGKILL), output.signal);
+        },
+        other => panic!("unexpected result {:?}", other)
+    }
+}
+
+#[tokio::test]
+async fn execute_user_process_limit_without_cgroup() {
+    let service = ProcessExecutionService::new(ExecutionLimits { user_processes: 4000, ..mock_limits() });
+
+    let output = service.execute("sh", &["-c", "grep 'Max processes' /proc/self/limits"], None).await.unwrap();
+
+    assert!(output.stdout.split_whitespace().eq(["Max", "processes", "4000", "4000", "processes"]));
+}
+
+#[tokio::test]
+async fn execute_truncates_output() {
+    let service = ProcessExecutionService::new(mock_limits());
+    let (output, mut lines) = mpsc::unbounded_channel();
+
+    let result = service.execute("seq", &["1000"], Some(output)).await.unwrap();
+
+    assert!(result.stdout.starts_with(TRUNCATED_MARKER));
+    assert!(result.stdout.len() <= TRUNCATED_MARKER.len() + 1024);
+    assert!(result.stdout.ends_with("999\n1000\n"));
+    let mut received = 0;
+    while lines.recv().await.is_some() {
+        received += 1;
+    }
+    assert_eq!(1000, received);
+}
+
+#[tokio::test]
+async fn execute_splits_long_lines() {
+    let service = ProcessExecutionService::new(mock_limits());
+    let (output, mut lines) = mpsc::unbounded_channel();
+
+    let result = service.execute("sh", &["-c", "head -c 3000 /dev/zero | tr '\\0' a"], Some(output)).await.unwrap();
+
+    assert_eq!(format!("{}{}", TRUNCATED_MARKER, "a".repeat(1024)), result.stdout);
+    let mut received = Vec::new();
+    while let Some(line) = lines.recv().await {
+        received.push(line.text.len());
+    }
+    assert_eq!(vec![1024, 1024, 952], received);
+}
+
+#[tokio::test]
+async fn execute_file_size_limit() {
+    let service = ProcessExecutionService::new(mock_limits());
+
+    let result = service.execute("sh", &["-c", "exec head -c 4096 /dev/zero > out"], None).await;
+
+    assert!(matches!(result, Err(ProcessExecutionError::ResourceLimit(limit, output)) if limit == "File size limit exceeded" && output.signal == Some(libc::SIGXFSZ)));
+}
+
+#[tokio::test]
+async fn execute_streams_output() {
+    let service = ProcessExecutionService::new(mock_limits());
+    let (output, mut lines) = mpsc::unbounded_channel();
+
+    let result = service.execute("sh", &["-c", "echo first; echo second >&2"], Some(output)).await;
+
+    assert_eq!("first\n", result.unwrap().stdout);
+    let mut received = Vec::new();
+    while let Some(line) = lines.recv().await {
+        received.push(line);
+    }
+    assert!(received.contains(&OutputLine { stream: LogStream::Stdout, text: String::from("first") }));
+    assert!(received.contains(&OutputLine { stream: LogStream::Stderr, text: String::from("second") }));
+}
+
+// A writable cgroup with a pids controller, if the tests are allowed to create one
+fn mock_cgroup() -> Option<PathBuf> {
+    ["/sys/fs/cgroup/pids", "/sys/fs/cgroup"].iter()
+        .map(|root| Path::new(root).join(format!("agartex_test_{}", rand::random::<u32>())))
+        .find(|dir| {
+            if fs::create_dir(dir).is_err() {
+                return false;
+            }
+            let usable = dir.join("pids.max").exists();
+            if !usable {
+                let _ = fs::remove_dir(dir);
+            }
+            usable
+        })
+}
+
+#[tokio::test]
+async fn execute_process_limit() {
+    let cgroup = match mock_cgroup() {
+        Some(cgroup) => cgroup,
+        None => return
+    };
+    let service = ProcessExecutionService::new(ExecutionLimits { processes: 4, ..mock_limits() }).with_cgroup(&cgroup);
+
+    let result = service.execute("sh", &["-c", "for i in 1 2 3 4 5 6 7 8; do sleep 0.1 & done; wait"], None).await;
+
+    fs::remove_dir(&cgroup).unwrap();
+    assert!(matches!(result, Err(ProcessExecutionError::ResourceLimit(limit, _)) if limit == "Process limit exceeded"));
+}
+
+#[tokio::test]
+async fn execute_kills_left_behind_processes() {
+    let service = ProcessExecutionService::new(mock_limits());
+
+    let output = service.execute("sh", &["-c", "sleep 5 > /dev/null 2>&1 & echo $!"], None).await.unwrap();
+
+    let pid: u32 = output.stdout.trim().parse().unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let state = fs::read_to_string(format!("/proc/{}/stat", pid)).unwrap_or_default();
+    assert!(state.is_empty() || state.contains(") Z "));
+}
+
+#[test]
+fn event_count_reads_named_counter() {
+    let events = "low 0\nhigh 0\nmax 0\noom 2\noom_kill 1\n";
+
+    assert_eq!(1, event_count(events, "oom_kill"));
+    assert_eq!(0, event_count(events, "max"));
+    assert_eq!(0, event_count(events, "missing"));
+}
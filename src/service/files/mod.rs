@@ -0,0 +1,151 @@
+
+use axum::async_trait;
+use mockall::automock;
+use tracing::{error, info, warn};
+
+use crate::{domain::{compile::SourceFile, files::{ProjectFile, is_relative_path}, users::User}, repository::files::{FileRepository, FileGetError, FileWriteError, FileDeleteError}};
+
+use super::projects::{ProjectService, ProjectAccessError};
+
+#[derive(PartialEq, Debug)]
+pub enum FileAccessError {
+    Missing,
+    Conflict,
+    Unknown
+}
+
+impl From<ProjectAccessError> for FileAccessError {
+    fn from(err: ProjectAccessError) -> Self {
+        match err {
+            ProjectAccessError::Missing => Self::Missing,
+            ProjectAccessError::Unknown => Self::Unknown
+        }
+    }
+}
+
+#[automock]
+#[async_trait]
+pub trait FileService {
+    async fn list(&self, user: &User, project_id: i32) -> Result<Vec<ProjectFile>, FileAccessError>;
+    async fn read(&self, user: &User, project_id: i32, path: &str) -> Result<Vec<u8>, FileAccessError>;
+    async fn write(&self, user: &User, project_id: i32, path: &str, content: Vec<u8>) -> Result<(), FileAccessError>;
+    async fn delete(&self, user: &User, project_id: i32, path: &str) -> Result<(), FileAccessError>;
+    async fn snapshot(&self, user: &User, project_id: i32) -> Result<Vec<SourceFile>, FileAccessError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct SimpleFileService<P, F>
+where
+    P: ProjectService + Send + Sync,
+    F: FileRepository + Send + Sync
+{
+    project_service: P,
+    repository: F
+}
+
+impl<P, F> SimpleFileService<P, F>
+where
+    P: ProjectService + Send + Sync,
+    F: FileRepository + Send + Sync
+{
+    pub fn new(project_service: P, repository: F) -> Self {
+        Self { project_service, repository }
+    }
+}
+
+#[async_trait]
+impl<P, F> FileService for SimpleFileService<P, F>
+where
+    P: ProjectService + Send + Sync,
+    F: FileRepository + Send + Sync
+{
+    #[tracing::instrument(skip(self, user), fields(user_id = user.id))]
+    async fn list(&self, user: &User, project_id: i32) -> Result<Vec<ProjectFile>, FileAccessError> {
+        self.project_service.get(user, project_id).await?;
+
+        match self.repository.list(project_id).await {
+            Ok(files) => Ok(files),
+            Err(FileGetError::Missing) => Ok(Vec::new()),
+            Err(FileGetError::Unknown) => Err(FileAccessError::Unknown)
+        }
+    }
+
+    #[tracing::instrument(skip(self, user), fields(user_id = user.id))]
+    async fn read(&self, user: &User, project_id: i32, path: &str) -> Result<Vec<u8>, FileAccessError> {
+        self.project_service.get(user, project_id).await?;
+
+        match self.repository.read(project_id, path).await {
+            Ok(content) => Ok(content),
+            Err(FileGetError::Missing) => Err(FileAccessError::Missing),
+            Err(FileGetError::Unknown) => Err(FileAccessError::Unknown)
+        }
+    }
+
+    #[tracing::instrument(skip(self, user, content), fields(user_id = user.id, size = content.len()))]
+    async fn write(&self, user: &User, project_id: i32, path: &str, content: Vec<u8>) -> Result<(), FileAccessError> {
+        self.project_service.get(user, project_id).await?;
+
+        // A file cannot also be a directory, which would only fail once the project is written to disk
+        let files = match self.repository.list(project_id).await {
+            Ok(files) => files,
+            Err(FileGetError::Missing) => Vec::new(),
+            Err(FileGetError::Unknown) => return Err(FileAccessError::Unknown)
+        };
+        if files.iter().any(|file| is_nested(&file.path, path) || is_nested(path, &file.path)) {
+            warn!("File path conflicts with an existing file or directory");
+            return Err(FileAccessError::Conflict);
+        }
+
+        match self.repository.write(project_id, path, &content).await {
+            Ok(()) => {
+                info!("Stored project file");
+                Ok(())
+            },
+            Err(FileWriteError::Unknown) => Err(FileAccessError::Unknown)
+        }
+    }
+
+    #[tracing::instrument(skip(self, user), fields(user_id = user.id))]
+    async fn delete(&self, user: &User, project_id: i32, path: &str) -> Result<(), FileAccessError> {
+        self.project_service.get(user, project_id).await?;
+
+        match self.repository.delete(project_id, path).await {
+            Ok(()) => {
+                info!("Deleted project file");
+                Ok(())
+            },
+            Err(FileDeleteError::Missing) => Err(FileAccessError::Missing),
+            Err(FileDeleteError::Unknown) => Err(FileAccessError::Unknown)
+        }
+    }
+
+    #[tracing::instrument(skip(self, user), fields(user_id = user.id))]
+    async fn snapshot(&self, user: &User, project_id: i32) -> Result<Vec<SourceFile>, FileAccessError> {
+        let files = self.list(user, project_id).await?;
+        let mut sources = Vec::with_capacity(files.len());
+
+        for file in files {
+            // Stored paths end up joined onto the build directory, so they are checked again no matter where they came from
+            if is_relative_path(&file.path).is_err() {
+                error!(path = file.path, "Refusing to read a file outside the project directory");
+                return Err(FileAccessError::Unknown);
+            }
+
+            let content = match self.repository.read(project_id, &file.path).await {
+                Ok(content) => content,
+                Err(FileGetError::Missing) => return Err(FileAccessError::Missing),
+                Err(FileGetError::Unknown) => return Err(FileAccessError::Unknown)
+            };
+            sources.push(SourceFile { path: file.path, content });
+        }
+
+        Ok(sources)
+    }
+}
+
+fn is_nested(path: &str, dir: &str) -> bool {
+    matches!(path.strip_prefix(dir), Some(rest) if rest.starts_with('/'))
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,233 @@
+use chrono::NaiveDateTime;
+use mockall::predicate;
+
+use crate::{repository::files::MockFileRepository, service::projects::MockProjectService, domain::{projects::Project, compile::Engine}};
+
+use super::*;
+
+fn mock_user() -> User {
+    User {
+        id: 1,
+        email: String::from("email"),
+        password_hash: String::from("password_hash")
+    }
+}
+
+fn mock_project_id() -> i32 {
+    10
+}
+
+fn mock_project() -> Project {
+    Project {
+        id: mock_project_id(),
+        owner_id: mock_user().id,
+        name: String::from("project"),
+        engine: Engine::Pdflatex,
+        created_at: NaiveDateTime::from_timestamp_opt(0, 0).unwrap(),
+        updated_at: NaiveDateTime::from_timestamp_opt(0, 0).unwrap()
+    }
+}
+
+fn mock_path() -> String {
+    String::from("chapters/intro.tex")
+}
+
+fn mock_content() -> Vec<u8> {
+    b"\\section{Intro}".to_vec()
+}
+
+fn mock_owned_project_service() -> MockProjectService {
+    let mut project_service = MockProjectService::new();
+    project_service
+        .expect_get()
+        .with(predicate::eq(mock_user()), predicate::eq(mock_project_id()))
+        .returning(|_, _| Ok(mock_project()));
+    project_service
+}
+
+#[tokio::test]
+async fn simple_impl_read_normal() {
+    let mut repository = MockFileRepository::new();
+
+    repository
+        .expect_read()
+        .with(predicate::eq(mock_project_id()), predicate::eq(mock_path()))
+        .times(1)
+        .returning(|_, _| Ok(mock_content()));
+
+    let service = SimpleFileService::new(mock_owned_project_service(), repository);
+
+    assert_eq!(Ok(mock_content()), service.read(&mock_user(), mock_project_id(), &mock_path()).await);
+}
+
+#[tokio::test]
+async fn simple_impl_read_missing_file() {
+    let mut repository = MockFileRepository::new();
+
+    repository
+        .expect_read()
+        .with(predicate::eq(mock_project_id()), predicate::eq(mock_path()))
+        .times(1)
+        .returning(|_, _| Err(FileGetError::Missing));
+
+    let service = SimpleFileService::new(mock_owned_project_service(), repository);
+
+    assert_eq!(Err(FileAccessError::Missing), service.read(&mock_user(), mock_project_id(), &mock_path()).await);
+}
+
+#[tokio::test]
+async fn simple_impl_read_missing_project() {
+    let mut project_service = MockProjectService::new();
+    let mut repository = MockFileRepository::new();
+
+    project_service
+        .expect_get()
+        .with(predicate::eq(mock_user()), predicate::eq(mock_project_id()))
+        .times(1)
+        .returning(|_, _| Err(ProjectAccessError::Missing));
+
+    repository
+        .expect_read()
+        .never();
+
+    let service = SimpleFileService::new(project_service, repository);
+
+    assert_eq!(Err(FileAccessError::Missing), service.read(&mock_user(), mock_project_id(), &mock_path()).await);
+}
+
+#[tokio::test]
+async fn simple_impl_write_normal() {
+    let mut repository = MockFileRepository::new();
+
+    repository
+        .expect_list()
+        .with(predicate::eq(mock_project_id()))
+        .times(1)
+        .returning(|_| Ok(vec![ProjectFile { path: String::from("chapters/intro.tex"), size: 0 }]));
+
+    repository
+        .expect_write()
+        .with(predicate::eq(mock_project_id()), predicate::eq(mock_path()), predicate::eq(mock_content()))
+        .times(1)
+        .returning(|_, _, _| Ok(()));
+
+    let service = SimpleFileService::new(mock_owned_project_service(), repository);
+
+    assert_eq!(Ok(()), service.write(&mock_user(), mock_project_id(), &mock_path(), mock_content()).await);
+}
+
+#[tokio::test]
+async fn simple_impl_write_unknown_error() {
+    let mut repository = MockFileRepository::new();
+
+    repository
+        .expect_list()
+        .with(predicate::eq(mock_project_id()))
+        .times(1)
+        .returning(|_| Ok(vec![ProjectFile { path: String::from("chapters/intro.tex"), size: 0 }]));
+
+    repository
+        .expect_write()
+        .with(predicate::eq(mock_project_id()), predicate::eq(mock_path()), predicate::eq(mock_content()))
+        .times(1)
+        .returning(|_, _, _| Err(FileWriteError::Unknown));
+
+    let service = SimpleFileService::new(mock_owned_project_service(), repository);
+
+    assert_eq!(Err(FileAccessError::Unknown), service.write(&mock_user(), mock_project_id(), &mock_path(), mock_content()).await);
+}
+
+#[tokio::test]
+async fn simple_impl_write_file_over_directory() {
+    let mut repository = MockFileRepository::new();
+
+    repository
+        .expect_list()
+        .with(predicate::eq(mock_project_id()))
+        .times(1)
+        .returning(|_| Ok(vec![ProjectFile { path: String::from("chapters/intro.tex"), size: 0 }]));
+
+    repository
+        .expect_write()
+        .never();
+
+    let service = SimpleFileService::new(mock_owned_project_service(), repository);
+
+    assert_eq!(Err(FileAccessError::Conflict), service.write(&mock_user(), mock_project_id(), "chapters", mock_content()).await);
+}
+
+#[tokio::test]
+async fn simple_impl_write_below_file() {
+    let mut repository = MockFileRepository::new();
+
+    repository
+        .expect_list()
+        .with(predicate::eq(mock_project_id()))
+        .times(1)
+        .returning(|_| Ok(vec![ProjectFile { path: String::from("chapters/intro.tex"), size: 0 }]));
+
+    repository
+        .expect_write()
+        .never();
+
+    let service = SimpleFileService::new(mock_owned_project_service(), repository);
+
+    assert_eq!(Err(FileAccessError::Conflict), service.write(&mock_user(), mock_project_id(), "chapters/intro.tex/figure.tex", mock_content()).await);
+}
+
+#[tokio::test]
+async fn simple_impl_delete_missing_file() {
+    let mut repository = MockFileRepository::new();
+
+    repository
+        .expect_delete()
+        .with(predicate::eq(mock_project_id()), predicate::eq(mock_path()))
+        .times(1)
+        .returning(|_, _| Err(FileDeleteError::Missing));
+
+    let service = SimpleFileService::new(mock_owned_project_service(), repository);
+
+    assert_eq!(Err(FileAccessError::Missing), service.delete(&mock_user(), mock_project_id(), &mock_path()).await);
+}
+
+#[tokio::test]
+async fn simple_impl_snapshot_normal() {
+    let mut repository = MockFileRepository::new();
+
+    repository
+        .expect_list()
+        .with(predicate::eq(mock_project_id()))
+        .times(1)
+        .returning(|_| Ok(vec![ProjectFile { path: mock_path(), size: mock_content().len() as i64 }]));
+
+    repository
+        .expect_read()
+        .with(predicate::eq(mock_project_id()), predicate::eq(mock_path()))
+        .times(1)
+        .returning(|_, _| Ok(mock_content()));
+
+    let service = SimpleFileService::new(mock_owned_project_service(), repository);
+
+    let files = service.snapshot(&mock_user(), mock_project_id()).await.unwrap();
+    assert_eq!(1, files.len());
+    assert_eq!(mock_path(), files[0].path);
+    assert_eq!(mock_content(), files[0].content);
+}
+
+#[tokio::test]
+async fn simple_impl_snapshot_path_traversal() {
+    let mut repository = MockFileRepository::new();
+
+    repository
+        .expect_list()
+        .times(1)
+        .returning(|_| Ok(vec![ProjectFile { path: String::from("../escaped.tex"), size: 0 }]));
+
+    repository
+        .expect_read()
+        .never();
+
+    let service = SimpleFileService::new(mock_owned_project_service(), repository);
+
+    assert_eq!(Err(FileAccessError::Unknown), service.snapshot(&mock_user(), mock_project_id()).await.map(|_| ()));
+}
@@ -3,3 +3,6 @@ pub mod sessions;
 pub mod hash;
 pub mod compilation;
 pub mod execution;
+pub mod projects;
+pub mod files;
+pub mod tokens;
@@ -0,0 +1,128 @@
+use std::{fs, io};
+
+use axum::async_trait;
+use mockall::automock;
+use tracing::{error, info, warn};
+
+use crate::{domain::{projects::{Project, ProjectMetadata}, users::User}, repository::{projects::{ProjectRepository, ProjectGetError, ProjectInsertError, ProjectUpdateError, ProjectDeleteError}, files::FileRepository}, constants};
+
+#[derive(PartialEq, Debug)]
+pub enum ProjectAccessError {
+    Missing,
+    Unknown
+}
+
+#[derive(PartialEq, Debug)]
+pub enum ProjectCreationError {
+    Unknown
+}
+
+#[automock]
+#[async_trait]
+pub trait ProjectService {
+    async fn get(&self, user: &User, id: i32) -> Result<Project, ProjectAccessError>;
+    async fn list(&self, user: &User) -> Result<Vec<Project>, ProjectAccessError>;
+    async fn create(&self, user: &User, metadata: ProjectMetadata) -> Result<Project, ProjectCreationError>;
+    async fn update(&self, user: &User, id: i32, metadata: ProjectMetadata) -> Result<Project, ProjectAccessError>;
+    async fn delete(&self, user: &User, id: i32) -> Result<(), ProjectAccessError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct SimpleProjectService<P, F>
+where
+    P: ProjectRepository + Send + Sync,
+    F: FileRepository + Send + Sync
+{
+    repository: P,
+    file_repository: F
+}
+
+impl<P, F> SimpleProjectService<P, F>
+where
+    P: ProjectRepository + Send + Sync,
+    F: FileRepository + Send + Sync
+{
+    pub fn new(repository: P, file_repository: F) -> Self {
+        Self { repository, file_repository }
+    }
+}
+
+#[async_trait]
+impl<P, F> ProjectService for SimpleProjectService<P, F>
+where
+    P: ProjectRepository + Send + Sync,
+    F: FileRepository + Send + Sync
+{
+    #[tracing::instrument(skip(self, user), fields(user_id = user.id))]
+    async fn get(&self, user: &User, id: i32) -> Result<Project, ProjectAccessError> {
+        let project = match self.repository.get(id).await {
+            Ok(project) => project,
+            Err(ProjectGetError::Missing) => return Err(ProjectAccessError::Missing),
+            Err(ProjectGetError::Unknown) => return Err(ProjectAccessError::Unknown)
+        };
+
+        if project.owner_id != user.id {
+            warn!("Attempted to access project of another user");
+            return Err(ProjectAccessError::Missing);
+        }
+
+        Ok(project)
+    }
+
+    #[tracing::instrument(skip_all, fields(user_id = user.id))]
+    async fn list(&self, user: &User) -> Result<Vec<Project>, ProjectAccessError> {
+        match self.repository.get_by_owner(user.id).await {
+            Ok(projects) => Ok(projects),
+            Err(ProjectGetError::Missing) => Ok(Vec::new()),
+            Err(ProjectGetError::Unknown) => Err(ProjectAccessError::Unknown)
+        }
+    }
+
+    #[tracing::instrument(skip_all, fields(user_id = user.id, name = metadata.name))]
+    async fn create(&self, user: &User, metadata: ProjectMetadata) -> Result<Project, ProjectCreationError> {
+        match self.repository.insert(user.id, &metadata).await {
+            Ok(project) => {
+                info!(project_id = project.id, "Created project");
+                Ok(project)
+            },
+            Err(ProjectInsertError::Unknown) => Err(ProjectCreationError::Unknown)
+        }
+    }
+
+    #[tracing::instrument(skip(self, user, metadata), fields(user_id = user.id))]
+    async fn update(&self, user: &User, id: i32, metadata: ProjectMetadata) -> Result<Project, ProjectAccessError> {
+        self.get(user, id).await?;
+
+        match self.repository.update(id, &metadata).await {
+            Ok(project) => Ok(project),
+            Err(ProjectUpdateError::Missing) => Err(ProjectAccessError::Missing),
+            Err(ProjectUpdateError::Unknown) => Err(ProjectAccessError::Unknown)
+        }
+    }
+
+    #[tracing::instrument(skip(self, user), fields(user_id = user.id))]
+    async fn delete(&self, user: &User, id: i32) -> Result<(), ProjectAccessError> {
+        self.get(user, id).await?;
+
+        if self.file_repository.delete_all(id).await.is_err() {
+            return Err(ProjectAccessError::Unknown);
+        }
+
+        match self.repository.delete(id).await {
+            Ok(()) => (),
+            Err(ProjectDeleteError::Missing) => return Err(ProjectAccessError::Missing),
+            Err(ProjectDeleteError::Unknown) => return Err(ProjectAccessError::Unknown)
+        };
+
+        match fs::remove_dir_all(constants::PROJECTS_DIR.join(id.to_string())) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => error!(%err),
+            _ => ()
+        };
+
+        info!("Deleted project");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests;
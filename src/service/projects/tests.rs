@@ -0,0 +1,290 @@
+use chrono::NaiveDateTime;
+use mockall::predicate;
+
+use crate::{repository::{projects::MockProjectRepository, files::MockFileRepository}, domain::compile::Engine};
+
+use super::*;
+
+fn mock_user() -> User {
+    User {
+        id: 1,
+        email: String::from("email"),
+        password_hash: String::from("password_hash")
+    }
+}
+
+fn mock_other_user() -> User {
+    User {
+        id: 2,
+        email: String::from("other_email"),
+        password_hash: String::from("password_hash")
+    }
+}
+
+fn mock_project_id() -> i32 {
+    10
+}
+
+fn mock_metadata() -> ProjectMetadata {
+    ProjectMetadata {
+        name: String::from("project"),
+        engine: None
+    }
+}
+
+fn mock_project() -> Project {
+    Project {
+        id: mock_project_id(),
+        owner_id: mock_user().id,
+        name: mock_metadata().name,
+        engine: Engine::Pdflatex,
+        created_at: NaiveDateTime::from_timestamp_opt(0, 0).unwrap(),
+        updated_at: NaiveDateTime::from_timestamp_opt(0, 0).unwrap()
+    }
+}
+
+#[tokio::test]
+async fn simple_impl_get_normal() {
+    let mut repository = MockProjectRepository::new();
+    let file_repository = MockFileRepository::new();
+
+    repository
+        .expect_get()
+        .with(predicate::eq(mock_project_id()))
+        .times(1)
+        .returning(|_| Ok(mock_project()));
+
+    let service = SimpleProjectService::new(repository, file_repository);
+
+    assert_eq!(Ok(mock_project()), service.get(&mock_user(), mock_project_id()).await);
+}
+
+#[tokio::test]
+async fn simple_impl_get_other_owner() {
+    let mut repository = MockProjectRepository::new();
+    let file_repository = MockFileRepository::new();
+
+    repository
+        .expect_get()
+        .with(predicate::eq(mock_project_id()))
+        .times(1)
+        .returning(|_| Ok(mock_project()));
+
+    let service = SimpleProjectService::new(repository, file_repository);
+
+    assert_eq!(Err(ProjectAccessError::Missing), service.get(&mock_other_user(), mock_project_id()).await);
+}
+
+#[tokio::test]
+async fn simple_impl_get_missing() {
+    let mut repository = MockProjectRepository::new();
+    let file_repository = MockFileRepository::new();
+
+    repository
+        .expect_get()
+        .with(predicate::eq(mock_project_id()))
+        .times(1)
+        .returning(|_| Err(ProjectGetError::Missing));
+
+    let service = SimpleProjectService::new(repository, file_repository);
+
+    assert_eq!(Err(ProjectAccessError::Missing), service.get(&mock_user(), mock_project_id()).await);
+}
+
+#[tokio::test]
+async fn simple_impl_get_unknown_error() {
+    let mut repository = MockProjectRepository::new();
+    let file_repository = MockFileRepository::new();
+
+    repository
+        .expect_get()
+        .with(predicate::eq(mock_project_id()))
+        .times(1)
+        .returning(|_| Err(ProjectGetError::Unknown));
+
+    let service = SimpleProjectService::new(repository, file_repository);
+
+    assert_eq!(Err(ProjectAccessError::Unknown), service.get(&mock_user(), mock_project_id()).await);
+}
+
+#[tokio::test]
+async fn simple_impl_list_normal() {
+    let mut repository = MockProjectRepository::new();
+    let file_repository = MockFileRepository::new();
+
+    repository
+        .expect_get_by_owner()
+        .with(predicate::eq(mock_user().id))
+        .times(1)
+        .returning(|_| Ok(vec![mock_project()]));
+
+    let service = SimpleProjectService::new(repository, file_repository);
+
+    assert_eq!(Ok(vec![mock_project()]), service.list(&mock_user()).await);
+}
+
+#[tokio::test]
+async fn simple_impl_list_unknown_error() {
+    let mut repository = MockProjectRepository::new();
+    let file_repository = MockFileRepository::new();
+
+    repository
+        .expect_get_by_owner()
+        .with(predicate::eq(mock_user().id))
+        .times(1)
+        .returning(|_| Err(ProjectGetError::Unknown));
+
+    let service = SimpleProjectService::new(repository, file_repository);
+
+    assert_eq!(Err(ProjectAccessError::Unknown), service.list(&mock_user()).await);
+}
+
+#[tokio::test]
+async fn simple_impl_create_normal() {
+    let mut repository = MockProjectRepository::new();
+    let file_repository = MockFileRepository::new();
+
+    repository
+        .expect_insert()
+        .with(predicate::eq(mock_user().id), predicate::eq(mock_metadata()))
+        .times(1)
+        .returning(|_, _| Ok(mock_project()));
+
+    let service = SimpleProjectService::new(repository, file_repository);
+
+    assert_eq!(Ok(mock_project()), service.create(&mock_user(), mock_metadata()).await);
+}
+
+#[tokio::test]
+async fn simple_impl_create_unknown_error() {
+    let mut repository = MockProjectRepository::new();
+    let file_repository = MockFileRepository::new();
+
+    repository
+        .expect_insert()
+        .with(predicate::eq(mock_user().id), predicate::eq(mock_metadata()))
+        .times(1)
+        .returning(|_, _| Err(ProjectInsertError::Unknown));
+
+    let service = SimpleProjectService::new(repository, file_repository);
+
+    assert_eq!(Err(ProjectCreationError::Unknown), service.create(&mock_user(), mock_metadata()).await);
+}
+
+#[tokio::test]
+async fn simple_impl_update_normal() {
+    let mut repository = MockProjectRepository::new();
+    let file_repository = MockFileRepository::new();
+
+    repository
+        .expect_get()
+        .with(predicate::eq(mock_project_id()))
+        .times(1)
+        .returning(|_| Ok(mock_project()));
+
+    repository
+        .expect_update()
+        .with(predicate::eq(mock_project_id()), predicate::eq(mock_metadata()))
+        .times(1)
+        .returning(|_, _| Ok(mock_project()));
+
+    let service = SimpleProjectService::new(repository, file_repository);
+
+    assert_eq!(Ok(mock_project()), service.update(&mock_user(), mock_project_id(), mock_metadata()).await);
+}
+
+#[tokio::test]
+async fn simple_impl_update_other_owner() {
+    let mut repository = MockProjectRepository::new();
+    let file_repository = MockFileRepository::new();
+
+    repository
+        .expect_get()
+        .with(predicate::eq(mock_project_id()))
+        .times(1)
+        .returning(|_| Ok(mock_project()));
+
+    repository
+        .expect_update()
+        .never();
+
+    let service = SimpleProjectService::new(repository, file_repository);
+
+    assert_eq!(Err(ProjectAccessError::Missing), service.update(&mock_other_user(), mock_project_id(), mock_metadata()).await);
+}
+
+#[tokio::test]
+async fn simple_impl_delete_normal() {
+    let mut repository = MockProjectRepository::new();
+    let mut file_repository = MockFileRepository::new();
+
+    repository
+        .expect_get()
+        .with(predicate::eq(mock_project_id()))
+        .times(1)
+        .returning(|_| Ok(mock_project()));
+
+    file_repository
+        .expect_delete_all()
+        .with(predicate::eq(mock_project_id()))
+        .times(1)
+        .returning(|_| Ok(()));
+
+    repository
+        .expect_delete()
+        .with(predicate::eq(mock_project_id()))
+        .times(1)
+        .returning(|_| Ok(()));
+
+    let service = SimpleProjectService::new(repository, file_repository);
+
+    assert_eq!(Ok(()), service.delete(&mock_user(), mock_project_id()).await);
+}
+
+#[tokio::test]
+async fn simple_impl_delete_other_owner() {
+    let mut repository = MockProjectRepository::new();
+    let file_repository = MockFileRepository::new();
+
+    repository
+        .expect_get()
+        .with(predicate::eq(mock_project_id()))
+        .times(1)
+        .returning(|_| Ok(mock_project()));
+
+    repository
+        .expect_delete()
+        .never();
+
+    let service = SimpleProjectService::new(repository, file_repository);
+
+    assert_eq!(Err(ProjectAccessError::Missing), service.delete(&mock_other_user(), mock_project_id()).await);
+}
+
+#[tokio::test]
+async fn simple_impl_delete_unknown_error() {
+    let mut repository = MockProjectRepository::new();
+    let mut file_repository = MockFileRepository::new();
+
+    repository
+        .expect_get()
+        .with(predicate::eq(mock_project_id()))
+        .times(1)
+        .returning(|_| Ok(mock_project()));
+
+    file_repository
+        .expect_delete_all()
+        .with(predicate::eq(mock_project_id()))
+        .times(1)
+        .returning(|_| Ok(()));
+
+    repository
+        .expect_delete()
+        .with(predicate::eq(mock_project_id()))
+        .times(1)
+        .returning(|_| Err(ProjectDeleteError::Unknown));
+
+    let service = SimpleProjectService::new(repository, file_repository);
+
+    assert_eq!(Err(ProjectAccessError::Unknown), service.delete(&mock_user(), mock_project_id()).await);
+}
@@ -3,10 +3,12 @@ use mockall::automock;
 use sqlx::types::chrono::{Utc, NaiveDateTime, DateTime};
 use tracing::{warn, error, info};
 
-use crate::{domain::{users::{Credentials, User}, sessions::Session}, auth, constants::SESSION_LENGTH_SECONDS, repository::{sessions::{SessionRepository, SessionInsertError, SessionGetError}, users::{UserRepository, UserGetError}}};
+use crate::{domain::{users::Credentials, sessions::{ClientInfo, Session, SessionInfo, VerifiedSession}}, auth, constants, repository::{sessions::{SessionRepository, SessionInsertError, SessionGetError, SessionDeleteError}, users::{UserRepository, UserGetError}}};
 
 use super::hash::HashService;
 
+pub mod purge;
+
 #[derive(PartialEq, Debug)]
 pub enum LoginError {
     NoUser,
@@ -19,11 +21,55 @@ pub enum SessionVerifyError {
     Unknown
 }
 
+#[derive(PartialEq, Debug)]
+pub enum LogoutError {
+    Unknown
+}
+
+#[derive(PartialEq, Debug)]
+pub enum SessionListError {
+    Unknown
+}
+
+#[derive(PartialEq, Debug)]
+pub enum SessionRevokeError {
+    Missing,
+    Unknown
+}
+
 #[automock]
 #[async_trait]
 pub trait SessionService {
-    async fn login(&self, credentials: Credentials) -> Result<Session, LoginError>;
-    async fn verify(&self, id: &str) -> Result<User, SessionVerifyError>;
+    async fn login(&self, credentials: Credentials, client: ClientInfo) -> Result<Session, LoginError>;
+    async fn verify(&self, id: &str) -> Result<VerifiedSession, SessionVerifyError>;
+    async fn logout(&self, id: &str) -> Result<(), LogoutError>;
+    async fn logout_all(&self, user_id: i32) -> Result<u64, LogoutError>;
+    async fn list(&self, user_id: i32, current_id: &str) -> Result<Vec<SessionInfo>, SessionListError>;
+    async fn revoke(&self, user_id: i32, public_id: i32) -> Result<(), SessionRevokeError>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SessionConfig {
+    pub idle_seconds: i64,
+    pub lifetime_seconds: i64,
+    pub refresh_seconds: i64
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            idle_seconds: constants::SESSION_IDLE_SECONDS,
+            lifetime_seconds: constants::SESSION_LENGTH_SECONDS,
+            refresh_seconds: constants::SESSION_REFRESH_SECONDS
+        }
+    }
+}
+
+impl SessionConfig {
+    // Sessions end after being idle for too long, but never outlive their absolute lifetime
+    fn expires(&self, created_at: i64, last_seen: i64) -> i64 {
+        (last_seen + self.idle_seconds).min(created_at + self.lifetime_seconds)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -35,7 +81,8 @@ where
 {
     session_repository: S,
     user_repository: U,
-    hash_service: H
+    hash_service: H,
+    config: SessionConfig
 }
 
 impl<S, U, H> HashSessionService<S, U, H>
@@ -44,8 +91,8 @@ where
     U: UserRepository + Send + Sync,
     H: HashService + Send + Sync
 {
-    pub fn new(session_repository: S, user_repository: U, hash_service: H) -> Self {
-        Self { session_repository, user_repository, hash_service }
+    pub fn new(session_repository: S, user_repository: U, hash_service: H, config: SessionConfig) -> Self {
+        Self { session_repository, user_repository, hash_service, config }
     }
 }
 
@@ -57,7 +104,7 @@ where
     H: HashService + Send + Sync
 {
     #[tracing::instrument(skip_all, field(email = credentials.email))]
-    async fn login(&self, credentials: Credentials) -> Result<Session, LoginError> {
+    async fn login(&self, credentials: Credentials, client: ClientInfo) -> Result<Session, LoginError> {
         info!("Attempting to login user");
         let user = match self.user_repository.get_by_email(&credentials.email).await {
             Ok(user) => user,
@@ -80,10 +127,14 @@ where
             Ok(true) => ()
         };
 
+        let now = Utc::now().timestamp();
         let session = Session {
-            id: auth::generate_session_id(),
+            id: auth::generate_token(),
             user,
-            expires: Utc::now().timestamp() + SESSION_LENGTH_SECONDS
+            expires: self.config.expires(now, now),
+            created_at: now,
+            last_seen: now,
+            client
         };
 
         match self.session_repository.insert(&session).await {
@@ -95,7 +146,7 @@ where
         }
     }
 
-    async fn verify(&self, id: &str) -> Result<User, SessionVerifyError> {
+    async fn verify(&self, id: &str) -> Result<VerifiedSession, SessionVerifyError> {
         let session = match self.session_repository.get(id).await {
             Ok(session) => session,
             Err(SessionGetError::Missing) => return Err(SessionVerifyError::Missing),
@@ -119,7 +170,62 @@ where
             });
         }
 
-        Ok(session.user)
+        // Writing on every request would turn each read into a database write
+        let now = Utc::now().timestamp();
+        if now - session.last_seen < self.config.refresh_seconds {
+            return Ok(VerifiedSession { user: session.user, expires: session.expires, extended: false });
+        }
+
+        let expires = self.config.expires(session.created_at, now);
+        match self.session_repository.touch(id, now, expires).await {
+            Ok(()) => Ok(VerifiedSession { user: session.user, expires, extended: expires != session.expires }),
+            Err(_) => {
+                warn!("Failed to extend session");
+                Ok(VerifiedSession { user: session.user, expires: session.expires, extended: false })
+            }
+        }
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn logout(&self, id: &str) -> Result<(), LogoutError> {
+        match self.session_repository.delete(id).await {
+            Ok(()) | Err(SessionDeleteError::Missing) => {
+                info!("Logged out");
+                Ok(())
+            },
+            Err(SessionDeleteError::Unknown) => Err(LogoutError::Unknown)
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn logout_all(&self, user_id: i32) -> Result<u64, LogoutError> {
+        match self.session_repository.delete_all(user_id).await {
+            Ok(count) => {
+                info!(count, "Logged out of every session");
+                Ok(count)
+            },
+            Err(_) => Err(LogoutError::Unknown)
+        }
+    }
+
+    #[tracing::instrument(skip(self, current_id))]
+    async fn list(&self, user_id: i32, current_id: &str) -> Result<Vec<SessionInfo>, SessionListError> {
+        match self.session_repository.list(user_id, current_id).await {
+            Ok(sessions) => Ok(sessions),
+            Err(_) => Err(SessionListError::Unknown)
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn revoke(&self, user_id: i32, public_id: i32) -> Result<(), SessionRevokeError> {
+        match self.session_repository.delete_by_public_id(user_id, public_id).await {
+            Ok(()) => {
+                info!("Revoked session");
+                Ok(())
+            },
+            Err(SessionDeleteError::Missing) => Err(SessionRevokeError::Missing),
+            Err(SessionDeleteError::Unknown) => Err(SessionRevokeError::Unknown)
+        }
     }
 }
 
@@ -0,0 +1,43 @@
+use std::time::Duration;
+
+use sqlx::types::chrono::Utc;
+use tracing::{error, info};
+
+use crate::repository::sessions::{SessionRepository, SessionDeleteError};
+
+#[derive(Debug, Clone)]
+pub struct SessionPurger<S: SessionRepository + Send + Sync> {
+    session_repository: S
+}
+
+impl<S: SessionRepository + Send + Sync + 'static> SessionPurger<S> {
+    pub fn new(session_repository: S) -> Self {
+        Self { session_repository }
+    }
+
+    // verify only removes expired sessions whose cookie is presented again, everything else is left to this task
+    pub fn spawn(self, interval: Duration) {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            let mut total: u64 = 0;
+            loop {
+                interval.tick().await;
+                if let Ok(count) = self.purge().await {
+                    total += count;
+                    info!(count, total, "Purged expired sessions");
+                }
+            }
+        });
+    }
+
+    pub async fn purge(&self) -> Result<u64, SessionDeleteError> {
+        let result = self.session_repository.delete_expired(Utc::now().timestamp()).await;
+        if result.is_err() {
+            error!("Could not purge expired sessions");
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests;
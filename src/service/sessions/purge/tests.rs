@@ -0,0 +1,35 @@
+use sqlx::types::chrono::Utc;
+
+use crate::repository::sessions::{MockSessionRepository, SessionDeleteError};
+
+use super::*;
+
+#[tokio::test]
+async fn purge_normal() {
+    let mut session_repository = MockSessionRepository::new();
+    let now = Utc::now().timestamp();
+
+    session_repository
+        .expect_delete_expired()
+        .withf(move |timestamp| *timestamp >= now)
+        .times(1)
+        .returning(|_| Ok(3));
+
+    let purger = SessionPurger::new(session_repository);
+
+    assert!(matches!(purger.purge().await, Ok(3)));
+}
+
+#[tokio::test]
+async fn purge_error() {
+    let mut session_repository = MockSessionRepository::new();
+
+    session_repository
+        .expect_delete_expired()
+        .times(1)
+        .returning(|_| Err(SessionDeleteError::Unknown));
+
+    let purger = SessionPurger::new(session_repository);
+
+    assert!(matches!(purger.purge().await, Err(SessionDeleteError::Unknown)));
+}
@@ -1,6 +1,6 @@
 use mockall::predicate;
 
-use crate::{repository::{sessions::{MockSessionRepository, SessionDeleteError}, users::MockUserRepository}, service::hash::MockHashService, constants};
+use crate::{domain::users::User, repository::{sessions::{MockSessionRepository, SessionDeleteError, SessionUpdateError}, users::MockUserRepository}, service::hash::MockHashService, constants};
 
 use super::*;
 
@@ -39,27 +39,44 @@ fn mock_session_id() -> String {
     String::from("session_id")
 }
 
-fn mock_ok_session() -> Session {
-    Session {
-        id: mock_session_id(),
-        user: mock_user(),
-        expires: Utc::now().timestamp() + constants::SESSION_LENGTH_SECONDS
+fn mock_client() -> ClientInfo {
+    ClientInfo {
+        ip_address: Some(String::from("127.0.0.1")),
+        user_agent: Some(String::from("user_agent"))
     }
 }
 
-fn mock_out_of_range_timestamp_session() -> Session {
+fn mock_session(expires: i64) -> Session {
     Session {
         id: mock_session_id(),
         user: mock_user(),
-        expires: 1000*1000*1000*1000*1000
+        expires,
+        created_at: Utc::now().timestamp(),
+        last_seen: Utc::now().timestamp(),
+        client: mock_client()
     }
 }
 
+fn mock_ok_session() -> Session {
+    mock_session(Utc::now().timestamp() + constants::SESSION_LENGTH_SECONDS)
+}
+
+fn mock_out_of_range_timestamp_session() -> Session {
+    mock_session(1000*1000*1000*1000*1000)
+}
+
 fn mock_expired_timestamp_session() -> Session {
-    Session {
-        id: mock_session_id(),
-        user: mock_user(),
-        expires: Utc::now().timestamp() - 100*1000
+    mock_session(Utc::now().timestamp() - 100*1000)
+}
+
+fn mock_session_info(public_id: i32, current: bool) -> SessionInfo {
+    SessionInfo {
+        id: public_id,
+        created_at: 0,
+        last_seen: 0,
+        expires: 0,
+        client: mock_client(),
+        current
     }
 }
 
@@ -86,10 +103,11 @@ async fn hash_impl_login_normal() -> Result<(), LoginError> {
         .times(1)
         .returning(|_| Ok(()));
 
-    let service = HashSessionService::new(session_repository, user_repository, hash_service);
+    let service = HashSessionService::new(session_repository, user_repository, hash_service, SessionConfig::default());
 
-    let session = service.login(mock_credentials()).await?;
+    let session = service.login(mock_credentials(), mock_client()).await?;
     assert_eq!(session.user, mock_user());
+    assert_eq!(session.client, mock_client());
 
     Ok(())
 }
@@ -114,9 +132,9 @@ async fn hash_impl_login_get_user_error_missing() {
         .expect_insert()
         .never();
 
-    let service = HashSessionService::new(session_repository, user_repository, hash_service);
+    let service = HashSessionService::new(session_repository, user_repository, hash_service, SessionConfig::default());
 
-    assert_eq!(Err(LoginError::NoUser), service.login(mock_credentials()).await);
+    assert_eq!(Err(LoginError::NoUser), service.login(mock_credentials(), mock_client()).await);
 }
 
 #[tokio::test]
@@ -139,9 +157,9 @@ async fn hash_impl_login_get_user_error_unknown() {
         .expect_insert()
         .never();
 
-    let service = HashSessionService::new(session_repository, user_repository, hash_service);
+    let service = HashSessionService::new(session_repository, user_repository, hash_service, SessionConfig::default());
 
-    assert_eq!(Err(LoginError::Unknown), service.login(mock_credentials()).await);
+    assert_eq!(Err(LoginError::Unknown), service.login(mock_credentials(), mock_client()).await);
 }
 
 #[tokio::test]
@@ -166,9 +184,9 @@ async fn hash_impl_login_hash_verify_false() {
         .expect_insert()
         .never();
 
-    let service = HashSessionService::new(session_repository, user_repository, hash_service);
+    let service = HashSessionService::new(session_repository, user_repository, hash_service, SessionConfig::default());
 
-    assert_eq!(Err(LoginError::NoUser), service.login(mock_credentials()).await);
+    assert_eq!(Err(LoginError::NoUser), service.login(mock_credentials(), mock_client()).await);
 }
 
 #[tokio::test]
@@ -193,9 +211,9 @@ async fn hash_impl_login_hash_verify_error() {
         .expect_insert()
         .never();
 
-    let service = HashSessionService::new(session_repository, user_repository, hash_service);
+    let service = HashSessionService::new(session_repository, user_repository, hash_service, SessionConfig::default());
 
-    assert_eq!(Err(LoginError::Unknown), service.login(mock_credentials()).await);
+    assert_eq!(Err(LoginError::Unknown), service.login(mock_credentials(), mock_client()).await);
 }
 
 #[tokio::test]
@@ -221,9 +239,9 @@ async fn hash_impl_login_session_insert_error() {
         .times(1)
         .returning(|_| Err(SessionInsertError::Unknown));
 
-    let service = HashSessionService::new(session_repository, user_repository, hash_service);
+    let service = HashSessionService::new(session_repository, user_repository, hash_service, SessionConfig::default());
 
-    assert_eq!(Err(LoginError::Unknown), service.login(mock_credentials()).await);
+    assert_eq!(Err(LoginError::Unknown), service.login(mock_credentials(), mock_client()).await);
 }
 
 #[tokio::test]
@@ -238,9 +256,90 @@ async fn hash_impl_verify_normal() {
         .times(1)
         .returning(|_| Ok(mock_ok_session()));
 
-    let service = HashSessionService::new(session_repository, user_repository, hash_service);
+    let service = HashSessionService::new(session_repository, user_repository, hash_service, SessionConfig::default());
+
+    let session = service.verify(&mock_session_id()).await.unwrap();
+    assert_eq!(mock_user(), session.user);
+    assert!(!session.extended);
+}
 
-    assert_eq!(Ok(mock_user()), service.verify(&mock_session_id()).await);
+#[tokio::test]
+async fn hash_impl_verify_extends_idle_session() {
+    let mut session_repository = MockSessionRepository::new();
+    let user_repository = MockUserRepository::new();
+    let hash_service = MockHashService::new();
+    let config = SessionConfig { idle_seconds: 3600, lifetime_seconds: 86400, refresh_seconds: 60 };
+    let now = Utc::now().timestamp();
+
+    session_repository
+        .expect_get()
+        .with(predicate::eq(mock_session_id()))
+        .times(1)
+        .returning(move |_| Ok(Session { created_at: now - 600, last_seen: now - 120, ..mock_session(now + 3480) }));
+
+    session_repository
+        .expect_touch()
+        .withf(move |id, last_seen, expires| id == mock_session_id() && *last_seen >= now && *expires == *last_seen + 3600)
+        .times(1)
+        .returning(|_, _, _| Ok(()));
+
+    let service = HashSessionService::new(session_repository, user_repository, hash_service, config);
+
+    let session = service.verify(&mock_session_id()).await.unwrap();
+    assert!(session.extended);
+    assert!(session.expires >= now + 3600);
+}
+
+#[tokio::test]
+async fn hash_impl_verify_caps_absolute_lifetime() {
+    let mut session_repository = MockSessionRepository::new();
+    let user_repository = MockUserRepository::new();
+    let hash_service = MockHashService::new();
+    let config = SessionConfig { idle_seconds: 3600, lifetime_seconds: 86400, refresh_seconds: 60 };
+    let now = Utc::now().timestamp();
+    let created_at = now - 86000;
+
+    session_repository
+        .expect_get()
+        .times(1)
+        .returning(move |_| Ok(Session { created_at, last_seen: now - 120, ..mock_session(created_at + 86400) }));
+
+    session_repository
+        .expect_touch()
+        .withf(move |_, _, expires| *expires == created_at + 86400)
+        .times(1)
+        .returning(|_, _, _| Ok(()));
+
+    let service = HashSessionService::new(session_repository, user_repository, hash_service, config);
+
+    let session = service.verify(&mock_session_id()).await.unwrap();
+    assert!(!session.extended);
+    assert_eq!(created_at + 86400, session.expires);
+}
+
+#[tokio::test]
+async fn hash_impl_verify_touch_error() {
+    let mut session_repository = MockSessionRepository::new();
+    let user_repository = MockUserRepository::new();
+    let hash_service = MockHashService::new();
+    let config = SessionConfig { idle_seconds: 3600, lifetime_seconds: 86400, refresh_seconds: 60 };
+    let now = Utc::now().timestamp();
+
+    session_repository
+        .expect_get()
+        .times(1)
+        .returning(move |_| Ok(Session { created_at: now - 600, last_seen: now - 120, ..mock_session(now + 3480) }));
+
+    session_repository
+        .expect_touch()
+        .times(1)
+        .returning(|_, _, _| Err(SessionUpdateError::Unknown));
+
+    let service = HashSessionService::new(session_repository, user_repository, hash_service, config);
+
+    let session = service.verify(&mock_session_id()).await.unwrap();
+    assert_eq!(mock_user(), session.user);
+    assert!(!session.extended);
 }
 
 #[tokio::test]
@@ -261,7 +360,7 @@ async fn hash_impl_verify_invalid_timestamp() {
         .times(1)
         .returning(|_| Ok(()));
 
-    let service = HashSessionService::new(session_repository, user_repository, hash_service);
+    let service = HashSessionService::new(session_repository, user_repository, hash_service, SessionConfig::default());
 
     assert_eq!(Err(SessionVerifyError::Missing), service.verify(&mock_session_id()).await);
 }
@@ -284,7 +383,7 @@ async fn hash_impl_verify_invalid_timestamp_delete_error() {
         .times(1)
         .returning(|_| Err(SessionDeleteError::Unknown));
 
-    let service = HashSessionService::new(session_repository, user_repository, hash_service);
+    let service = HashSessionService::new(session_repository, user_repository, hash_service, SessionConfig::default());
 
     assert_eq!(Err(SessionVerifyError::Unknown), service.verify(&mock_session_id()).await);
 }
@@ -307,7 +406,7 @@ async fn hash_impl_verify_expired_timestamp() {
         .times(1)
         .returning(|_| Ok(()));
 
-    let service = HashSessionService::new(session_repository, user_repository, hash_service);
+    let service = HashSessionService::new(session_repository, user_repository, hash_service, SessionConfig::default());
 
     assert_eq!(Err(SessionVerifyError::Missing), service.verify(&mock_session_id()).await);
 }
@@ -330,7 +429,110 @@ async fn hash_impl_verify_expired_timestamp_delete_error() {
         .times(1)
         .returning(|_| Err(SessionDeleteError::Unknown));
 
-    let service = HashSessionService::new(session_repository, user_repository, hash_service);
+    let service = HashSessionService::new(session_repository, user_repository, hash_service, SessionConfig::default());
 
     assert_eq!(Err(SessionVerifyError::Unknown), service.verify(&mock_session_id()).await);
 }
+
+#[tokio::test]
+async fn hash_impl_logout_normal() {
+    let mut session_repository = MockSessionRepository::new();
+    let user_repository = MockUserRepository::new();
+    let hash_service = MockHashService::new();
+
+    session_repository
+        .expect_delete()
+        .with(predicate::eq(mock_session_id()))
+        .times(1)
+        .returning(|_| Ok(()));
+
+    let service = HashSessionService::new(session_repository, user_repository, hash_service, SessionConfig::default());
+
+    assert_eq!(Ok(()), service.logout(&mock_session_id()).await);
+}
+
+#[tokio::test]
+async fn hash_impl_logout_delete_error() {
+    let mut session_repository = MockSessionRepository::new();
+    let user_repository = MockUserRepository::new();
+    let hash_service = MockHashService::new();
+
+    session_repository
+        .expect_delete()
+        .with(predicate::eq(mock_session_id()))
+        .times(1)
+        .returning(|_| Err(SessionDeleteError::Unknown));
+
+    let service = HashSessionService::new(session_repository, user_repository, hash_service, SessionConfig::default());
+
+    assert_eq!(Err(LogoutError::Unknown), service.logout(&mock_session_id()).await);
+}
+
+#[tokio::test]
+async fn hash_impl_logout_all_normal() {
+    let mut session_repository = MockSessionRepository::new();
+    let user_repository = MockUserRepository::new();
+    let hash_service = MockHashService::new();
+
+    session_repository
+        .expect_delete_all()
+        .with(predicate::eq(mock_user().id))
+        .times(1)
+        .returning(|_| Ok(3));
+
+    let service = HashSessionService::new(session_repository, user_repository, hash_service, SessionConfig::default());
+
+    assert_eq!(Ok(3), service.logout_all(mock_user().id).await);
+}
+
+#[tokio::test]
+async fn hash_impl_list_normal() {
+    let mut session_repository = MockSessionRepository::new();
+    let user_repository = MockUserRepository::new();
+    let hash_service = MockHashService::new();
+
+    session_repository
+        .expect_list()
+        .with(predicate::eq(mock_user().id), predicate::eq(mock_session_id()))
+        .times(1)
+        .returning(|_, _| Ok(vec![mock_session_info(1, false), mock_session_info(2, true)]));
+
+    let service = HashSessionService::new(session_repository, user_repository, hash_service, SessionConfig::default());
+
+    let sessions = service.list(mock_user().id, &mock_session_id()).await.unwrap();
+    assert!(!sessions[0].current);
+    assert!(sessions[1].current);
+}
+
+#[tokio::test]
+async fn hash_impl_list_error() {
+    let mut session_repository = MockSessionRepository::new();
+    let user_repository = MockUserRepository::new();
+    let hash_service = MockHashService::new();
+
+    session_repository
+        .expect_list()
+        .times(1)
+        .returning(|_, _| Err(SessionGetError::Unknown));
+
+    let service = HashSessionService::new(session_repository, user_repository, hash_service, SessionConfig::default());
+
+    assert_eq!(Err(SessionListError::Unknown), service.list(mock_user().id, &mock_session_id()).await);
+}
+
+#[tokio::test]
+async fn hash_impl_revoke_missing() {
+    let mut session_repository = MockSessionRepository::new();
+    let user_repository = MockUserRepository::new();
+    let hash_service = MockHashService::new();
+
+    session_repository
+        .expect_delete_by_public_id()
+        .with(predicate::eq(mock_user().id), predicate::eq(7))
+        .times(1)
+        .returning(|_, _| Err(SessionDeleteError::Missing));
+
+    let service = HashSessionService::new(session_repository, user_repository, hash_service, SessionConfig::default());
+
+    assert_eq!(Err(SessionRevokeError::Missing), service.revoke(mock_user().id, 7).await);
+}
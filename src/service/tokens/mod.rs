@@ -0,0 +1,118 @@
+use axum::async_trait;
+use mockall::automock;
+use sqlx::types::chrono::Utc;
+use tracing::{info, warn};
+
+use crate::{domain::tokens::{ApiToken, CreatedApiToken, TokenOwner, TokenRequest, TokenScopes}, auth, constants, repository::tokens::{ApiTokenRepository, ApiTokenGetError, ApiTokenInsertError, ApiTokenDeleteError}};
+
+#[derive(PartialEq, Debug)]
+pub enum TokenCreateError {
+    Unknown
+}
+
+#[derive(PartialEq, Debug)]
+pub enum TokenListError {
+    Unknown
+}
+
+#[derive(PartialEq, Debug)]
+pub enum TokenRevokeError {
+    Missing,
+    Unknown
+}
+
+#[derive(PartialEq, Debug)]
+pub enum TokenVerifyError {
+    Missing,
+    Unknown
+}
+
+#[automock]
+#[async_trait]
+pub trait TokenService {
+    async fn create(&self, user_id: i32, request: TokenRequest) -> Result<CreatedApiToken, TokenCreateError>;
+    async fn list(&self, user_id: i32) -> Result<Vec<ApiToken>, TokenListError>;
+    async fn revoke(&self, user_id: i32, id: i32) -> Result<(), TokenRevokeError>;
+    async fn verify(&self, secret: &str) -> Result<TokenOwner, TokenVerifyError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct SimpleTokenService<R: ApiTokenRepository + Send + Sync> {
+    repository: R
+}
+
+impl<R: ApiTokenRepository + Send + Sync> SimpleTokenService<R> {
+    pub fn new(repository: R) -> Self {
+        Self { repository }
+    }
+}
+
+#[async_trait]
+impl<R: ApiTokenRepository + Send + Sync> TokenService for SimpleTokenService<R> {
+    #[tracing::instrument(skip(self, request), fields(name = request.name))]
+    async fn create(&self, user_id: i32, request: TokenRequest) -> Result<CreatedApiToken, TokenCreateError> {
+        // The prefix makes leaked tokens easy to spot for secret scanners
+        let secret = format!("{}{}", constants::API_TOKEN_PREFIX, auth::generate_token());
+        let expires = request.expires_in_days
+            .map(|days| Utc::now().timestamp() + i64::from(days) * 24 * 60 * 60);
+
+        let mut scopes = request.scopes;
+        scopes.sort();
+        scopes.dedup();
+
+        match self.repository.insert(user_id, &secret, &request.name, &TokenScopes(scopes), expires).await {
+            Ok(token) => {
+                info!(token_id = token.id, "Created API token");
+                Ok(CreatedApiToken { token, secret })
+            },
+            Err(ApiTokenInsertError::Unknown) => Err(TokenCreateError::Unknown)
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn list(&self, user_id: i32) -> Result<Vec<ApiToken>, TokenListError> {
+        match self.repository.list(user_id).await {
+            Ok(tokens) => Ok(tokens),
+            Err(ApiTokenGetError::Missing) => Ok(Vec::new()),
+            Err(ApiTokenGetError::Unknown) => Err(TokenListError::Unknown)
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn revoke(&self, user_id: i32, id: i32) -> Result<(), TokenRevokeError> {
+        match self.repository.delete(user_id, id).await {
+            Ok(()) => {
+                info!("Revoked API token");
+                Ok(())
+            },
+            Err(ApiTokenDeleteError::Missing) => Err(TokenRevokeError::Missing),
+            Err(ApiTokenDeleteError::Unknown) => Err(TokenRevokeError::Unknown)
+        }
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn verify(&self, secret: &str) -> Result<TokenOwner, TokenVerifyError> {
+        let owner = match self.repository.get(secret).await {
+            Ok(owner) => owner,
+            Err(ApiTokenGetError::Missing) => return Err(TokenVerifyError::Missing),
+            Err(ApiTokenGetError::Unknown) => return Err(TokenVerifyError::Unknown)
+        };
+
+        let now = Utc::now().timestamp();
+        if matches!(owner.token.expires, Some(expires) if expires < now) {
+            warn!(token_id = owner.token.id, "Attempted to use an expired API token");
+            return Err(TokenVerifyError::Missing);
+        }
+
+        // Only the last use is recorded, so there is no need to write on every request
+        if !matches!(owner.token.last_used, Some(last_used) if now - last_used < constants::API_TOKEN_REFRESH_SECONDS)
+            && self.repository.touch(owner.token.id, now).await.is_err() {
+            warn!(token_id = owner.token.id, "Could not record API token use");
+        }
+
+        Ok(owner)
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,225 @@
+use mockall::predicate;
+
+use crate::{repository::tokens::{MockApiTokenRepository, ApiTokenUpdateError}, domain::{tokens::TokenScope, users::User}};
+
+use super::*;
+
+fn mock_user() -> User {
+    User {
+        id: 1,
+        email: String::from("email"),
+        password_hash: String::from("password_hash")
+    }
+}
+
+fn mock_token_id() -> i32 {
+    5
+}
+
+fn mock_secret() -> String {
+    String::from("agartex_secret")
+}
+
+fn mock_request() -> TokenRequest {
+    TokenRequest {
+        name: String::from("ci"),
+        scopes: vec![TokenScope::Compile, TokenScope::Read, TokenScope::Compile],
+        expires_in_days: Some(30)
+    }
+}
+
+fn mock_token(expires: Option<i64>, last_used: Option<i64>) -> ApiToken {
+    ApiToken {
+        id: mock_token_id(),
+        name: String::from("ci"),
+        scopes: TokenScopes(vec![TokenScope::Read, TokenScope::Compile]),
+        created_at: 0,
+        expires,
+        last_used
+    }
+}
+
+fn mock_owner(expires: Option<i64>, last_used: Option<i64>) -> TokenOwner {
+    TokenOwner {
+        user: mock_user(),
+        token: mock_token(expires, last_used)
+    }
+}
+
+#[tokio::test]
+async fn simple_impl_create_normal() {
+    let mut repository = MockApiTokenRepository::new();
+    let now = Utc::now().timestamp();
+
+    repository
+        .expect_insert()
+        .withf(move |user_id, secret, name, scopes, expires| {
+            *user_id == mock_user().id
+                && secret.starts_with(constants::API_TOKEN_PREFIX)
+                && secret.len() > constants::API_TOKEN_PREFIX.len() + 40
+                && name == "ci"
+                && *scopes == TokenScopes(vec![TokenScope::Read, TokenScope::Compile])
+                && matches!(expires, Some(expires) if *expires >= now + 30 * 24 * 60 * 60)
+        })
+        .times(1)
+        .returning(|_, _, _, _, expires| Ok(mock_token(expires, None)));
+
+    let service = SimpleTokenService::new(repository);
+
+    let created = service.create(mock_user().id, mock_request()).await.unwrap();
+    assert_eq!(mock_token_id(), created.token.id);
+    assert!(created.secret.starts_with(constants::API_TOKEN_PREFIX));
+}
+
+#[tokio::test]
+async fn simple_impl_create_without_expiry() {
+    let mut repository = MockApiTokenRepository::new();
+
+    repository
+        .expect_insert()
+        .withf(|_, _, _, _, expires| expires.is_none())
+        .times(1)
+        .returning(|_, _, _, _, _| Ok(mock_token(None, None)));
+
+    let service = SimpleTokenService::new(repository);
+
+    let request = TokenRequest { expires_in_days: None, ..mock_request() };
+    assert!(service.create(mock_user().id, request).await.is_ok());
+}
+
+#[tokio::test]
+async fn simple_impl_create_error() {
+    let mut repository = MockApiTokenRepository::new();
+
+    repository
+        .expect_insert()
+        .times(1)
+        .returning(|_, _, _, _, _| Err(ApiTokenInsertError::Unknown));
+
+    let service = SimpleTokenService::new(repository);
+
+    assert_eq!(Err(TokenCreateError::Unknown), service.create(mock_user().id, mock_request()).await);
+}
+
+#[tokio::test]
+async fn simple_impl_list_normal() {
+    let mut repository = MockApiTokenRepository::new();
+
+    repository
+        .expect_list()
+        .with(predicate::eq(mock_user().id))
+        .times(1)
+        .returning(|_| Ok(vec![mock_token(None, None)]));
+
+    let service = SimpleTokenService::new(repository);
+
+    assert_eq!(Ok(vec![mock_token(None, None)]), service.list(mock_user().id).await);
+}
+
+#[tokio::test]
+async fn simple_impl_revoke_missing() {
+    let mut repository = MockApiTokenRepository::new();
+
+    repository
+        .expect_delete()
+        .with(predicate::eq(mock_user().id), predicate::eq(mock_token_id()))
+        .times(1)
+        .returning(|_, _| Err(ApiTokenDeleteError::Missing));
+
+    let service = SimpleTokenService::new(repository);
+
+    assert_eq!(Err(TokenRevokeError::Missing), service.revoke(mock_user().id, mock_token_id()).await);
+}
+
+#[tokio::test]
+async fn simple_impl_verify_normal() {
+    let mut repository = MockApiTokenRepository::new();
+    let now = Utc::now().timestamp();
+
+    repository
+        .expect_get()
+        .with(predicate::eq(mock_secret()))
+        .times(1)
+        .returning(|_| Ok(mock_owner(None, None)));
+
+    repository
+        .expect_touch()
+        .withf(move |id, last_used| *id == mock_token_id() && *last_used >= now)
+        .times(1)
+        .returning(|_, _| Ok(()));
+
+    let service = SimpleTokenService::new(repository);
+
+    assert_eq!(Ok(mock_owner(None, None)), service.verify(&mock_secret()).await);
+}
+
+#[tokio::test]
+async fn simple_impl_verify_recently_used() {
+    let mut repository = MockApiTokenRepository::new();
+    let now = Utc::now().timestamp();
+
+    repository
+        .expect_get()
+        .times(1)
+        .returning(move |_| Ok(mock_owner(None, Some(now))));
+
+    repository
+        .expect_touch()
+        .never();
+
+    let service = SimpleTokenService::new(repository);
+
+    assert!(service.verify(&mock_secret()).await.is_ok());
+}
+
+#[tokio::test]
+async fn simple_impl_verify_touch_error() {
+    let mut repository = MockApiTokenRepository::new();
+
+    repository
+        .expect_get()
+        .times(1)
+        .returning(|_| Ok(mock_owner(None, None)));
+
+    repository
+        .expect_touch()
+        .times(1)
+        .returning(|_, _| Err(ApiTokenUpdateError::Unknown));
+
+    let service = SimpleTokenService::new(repository);
+
+    assert!(service.verify(&mock_secret()).await.is_ok());
+}
+
+#[tokio::test]
+async fn simple_impl_verify_expired() {
+    let mut repository = MockApiTokenRepository::new();
+    let now = Utc::now().timestamp();
+
+    repository
+        .expect_get()
+        .times(1)
+        .returning(move |_| Ok(mock_owner(Some(now - 100), None)));
+
+    repository
+        .expect_touch()
+        .never();
+
+    let service = SimpleTokenService::new(repository);
+
+    assert_eq!(Err(TokenVerifyError::Missing), service.verify(&mock_secret()).await);
+}
+
+#[tokio::test]
+async fn simple_impl_verify_missing() {
+    let mut repository = MockApiTokenRepository::new();
+
+    repository
+        .expect_get()
+        .times(1)
+        .returning(|_| Err(ApiTokenGetError::Missing));
+
+    let service = SimpleTokenService::new(repository);
+
+    assert_eq!(Err(TokenVerifyError::Missing), service.verify(&mock_secret()).await);
+}